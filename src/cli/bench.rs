@@ -0,0 +1,140 @@
+use crate::{
+    core::{bench::BenchRun, ForgeConfig},
+    Result,
+};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Only run the bench target with this name
+    pub bench: Option<String>,
+    /// Save the results as the baseline NAME
+    #[arg(long, value_name = "NAME")]
+    pub save_baseline: Option<String>,
+    /// Compare the results with the baseline NAME and fail on regressions
+    #[arg(long, value_name = "NAME")]
+    pub baseline: Option<String>,
+    /// Slowdown in percent that counts as a regression
+    #[arg(long, value_name = "PCT", default_value_t = 5.0)]
+    pub threshold: f64,
+    /// Arguments passed to the benchmark binaries
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+impl BenchArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let config = ForgeConfig::from_file()?;
+
+        let run = BenchRun {
+            target: self.bench.clone(),
+            args: self.args.clone(),
+            save_baseline: self.save_baseline.clone(),
+            baseline: self.baseline.clone(),
+            threshold: self.threshold,
+        };
+        config.bench(&run)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        bench::{self, BENCH_DIR},
+        build_system::BuildSystems,
+        language::{CStandard, Language},
+        package_manager::PackageManagers,
+        test_framework::TestFrameworks,
+    };
+    use serial_test::serial;
+    use std::{env, fs, path::PathBuf};
+
+    // Utility functions
+    fn create_dummy_project(path: &PathBuf) -> anyhow::Result<()> {
+        fs::create_dir_all(path)?;
+        Ok(())
+    }
+
+    fn check_file_exits(path: &PathBuf) -> bool {
+        return match fs::exists(path) {
+            Ok(s) => s,
+            Err(_) => false,
+        };
+    }
+
+    fn delete_dummy_project(path: &PathBuf) -> anyhow::Result<()> {
+        fs::remove_dir_all(path)?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_process_command_baseline() -> anyhow::Result<()> {
+        let name = "dummy";
+        let cwd = env::current_dir()?;
+        let path = cwd.join(&name);
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let config = ForgeConfig::new(
+            name.to_string(),
+            path.clone(),
+            Language::C(CStandard::C99),
+            "gcc".to_string(),
+            BuildSystems::Make,
+            PackageManagers::Vcpkg,
+            TestFrameworks::CMocka,
+            String::new(),
+        );
+        config.to_file()?;
+
+        // Test
+        env::set_current_dir(&path)?;
+        let save = BenchArgs {
+            bench: None,
+            save_baseline: Some("main".to_string()),
+            baseline: None,
+            threshold: 5.0,
+            args: vec![],
+        }
+        .process_command();
+        let compare = BenchArgs {
+            bench: Some("dummy_bench".to_string()),
+            save_baseline: None,
+            baseline: Some("main".to_string()),
+            threshold: 1000.0,
+            args: vec![],
+        }
+        .process_command();
+        let missing = BenchArgs {
+            bench: None,
+            save_baseline: None,
+            baseline: Some("missing".to_string()),
+            threshold: 5.0,
+            args: vec![],
+        }
+        .process_command();
+        env::set_current_dir(&cwd)?;
+        save?;
+        compare?;
+
+        // Validate
+        assert!(check_file_exits(&path.join("bench").join("bench.h")));
+        assert!(check_file_exits(
+            &path.join("build/release/benches/dummy_bench")
+        ));
+        let results = path.join(BENCH_DIR);
+        assert!(check_file_exits(&results.join("dummy_bench.json")));
+        let baseline = fs::read_to_string(bench::baseline_path(&path, "main"))?;
+        assert!(baseline.contains("\"dummy_bench/bench_sum\""));
+        assert!(missing.is_err());
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+}
@@ -1,8 +1,23 @@
-use crate::{core::ForgeConfig, Result};
+use crate::{
+    core::{diagnostics::MessageFormat, profile::Profile, sanitizer::SanitizerSet, ForgeConfig},
+    Result,
+};
 use clap::Args;
 
 #[derive(Debug, Args)]
 pub struct BuildArgs {
+    /// Build with sanitizers in a separate tree, e.g. `address,undefined`, `thread` or `memory`
+    #[arg(long, value_name = "LIST", value_parser = SanitizerSet::parse)]
+    pub sanitize: Option<SanitizerSet>,
+    /// Use the release profile, built in build/release
+    #[arg(long)]
+    pub release: bool,
+    /// How to report compiler diagnostics: `human` or `json`, one message per line on stdout
+    #[arg(long, value_name = "FMT", value_parser = MessageFormat::parse, default_value = "human")]
+    pub message_format: MessageFormat,
+    /// Record compile times to build/timings and print the N slowest files
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "10")]
+    pub timings: Option<usize>,
     /// Compiler flags
     #[arg(last = true)]
     pub options: Option<Vec<String>>,
@@ -12,7 +27,12 @@ impl BuildArgs {
     pub fn process_command(&self) -> Result<()> {
         let config = ForgeConfig::from_file()?;
 
-        config.build(self.options.as_ref())?;
+        let mut tree =
+            config.build_tree(self.sanitize.as_ref(), Profile::from_release(self.release))?;
+        tree.message_format = self.message_format;
+        tree.timings = self.timings;
+
+        config.build(&tree, self.options.as_ref())?;
 
         Ok(())
     }
@@ -54,7 +74,11 @@ mod tests {
         create_dummy_project(&path)?;
 
         env::set_current_dir(&path)?;
-        let args = InitArgs {};
+        let args = InitArgs {
+            force: false,
+            keep_existing: false,
+            import: false,
+        };
         args.process_command()?;
 
         let config_args = ConfigArgs {
@@ -63,7 +87,13 @@ mod tests {
         };
         config_args.process_command()?;
 
-        let build_args = BuildArgs { options: None };
+        let build_args = BuildArgs {
+            sanitize: None,
+            release: false,
+            message_format: MessageFormat::Human,
+            timings: None,
+            options: None,
+        };
         build_args.process_command()?;
         env::set_current_dir(&cwd)?;
 
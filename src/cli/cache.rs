@@ -0,0 +1,42 @@
+use crate::{core::ForgeConfig, Result};
+use clap::{Args, Subcommand};
+
+#[derive(Debug, Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub command: CacheCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheCommands {
+    /// Show the hits, misses and hit rate of the compiler cache.
+    Stats,
+    /// Empty the compiler cache and reset its statistics.
+    Clear,
+}
+
+impl CacheArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let config = ForgeConfig::from_file()?;
+        let cache = config.compiler_cache()?;
+
+        match self.command {
+            CacheCommands::Stats => {
+                let stats = cache.stats()?;
+                println!(
+                    "{}: {} hits, {} misses, {:.1}% hit rate",
+                    cache.as_str(),
+                    stats.hits,
+                    stats.misses,
+                    stats.hit_rate()
+                );
+            }
+            CacheCommands::Clear => {
+                cache.clear()?;
+                println!("Cleared the {} cache", cache.as_str());
+            }
+        }
+
+        Ok(())
+    }
+}
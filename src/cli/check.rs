@@ -0,0 +1,26 @@
+use crate::{
+    core::{compile_db::default_jobs, ForgeConfig},
+    Result,
+};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    /// Only check files changed according to git, and the sources including changed headers
+    #[arg(long)]
+    pub changed: bool,
+    /// Number of files checked in parallel
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+}
+
+impl CheckArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let config = ForgeConfig::from_file()?;
+        let jobs = self.jobs.unwrap_or_else(default_jobs);
+
+        config.check(self.changed, jobs)?;
+
+        Ok(())
+    }
+}
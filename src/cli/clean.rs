@@ -1,16 +1,50 @@
-use crate::{core::ForgeConfig, Result};
-use clap::Args;
+use crate::{
+    core::{clean::CleanScope, profile::Profile, ForgeConfig},
+    Result,
+};
+use clap::{ArgGroup, Args};
 
 #[derive(Debug, Args)]
-pub struct CleanArgs {}
+#[command(group(ArgGroup::new("scope").args(["profile", "target", "deps", "all"])))]
+pub struct CleanArgs {
+    /// Only remove what was built in this profile's tree, keeping it configured
+    #[arg(long, value_name = "PROFILE", value_parser = Profile::parse)]
+    pub profile: Option<Profile>,
+    /// Only remove the objects and output of this target
+    #[arg(long, value_name = "NAME")]
+    pub target: Option<String>,
+    /// Only remove the dependencies installed into the project, e.g. vcpkg_installed
+    #[arg(long)]
+    pub deps: bool,
+    /// Remove the build trees and the installed dependencies
+    #[arg(long)]
+    pub all: bool,
+    /// List what would be removed without removing it
+    #[arg(long)]
+    pub dry_run: bool,
+}
 
 impl CleanArgs {
     pub fn process_command(&self) -> Result<()> {
         let config = ForgeConfig::from_file()?;
-        config.clean()?;
+        config.clean(&self.scope(), self.dry_run)?;
 
         Ok(())
     }
+
+    fn scope(&self) -> CleanScope {
+        if let Some(profile) = self.profile {
+            CleanScope::Profile(profile)
+        } else if let Some(target) = &self.target {
+            CleanScope::Target(target.clone())
+        } else if self.deps {
+            CleanScope::Deps
+        } else if self.all {
+            CleanScope::All
+        } else {
+            CleanScope::Build
+        }
+    }
 }
 
 #[cfg(test)]
@@ -49,7 +83,11 @@ mod tests {
         create_dummy_project(&path)?;
 
         env::set_current_dir(&path)?;
-        let args = InitArgs {};
+        let args = InitArgs {
+            force: false,
+            keep_existing: false,
+            import: false,
+        };
         args.process_command()?;
 
         let config_args = ConfigArgs {
@@ -64,7 +102,13 @@ mod tests {
             true
         );
 
-        let clean_args = CleanArgs {};
+        let clean_args = CleanArgs {
+            profile: None,
+            target: None,
+            deps: false,
+            all: false,
+            dry_run: false,
+        };
         clean_args.process_command()?;
         env::set_current_dir(&cwd)?;
 
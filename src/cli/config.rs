@@ -62,7 +62,11 @@ mod tests {
         create_dummy_project(&path)?;
 
         env::set_current_dir(&path)?;
-        let args = InitArgs {};
+        let args = InitArgs {
+            force: false,
+            keep_existing: false,
+            import: false,
+        };
         args.process_command()?;
 
         let config_args = ConfigArgs {
@@ -0,0 +1,25 @@
+use crate::{core::ForgeConfig, Result};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct CoverageArgs {
+    /// Also write an HTML report (requires genhtml)
+    #[arg(long)]
+    pub html: bool,
+    /// Also write a Cobertura XML report
+    #[arg(long)]
+    pub cobertura: bool,
+    /// Fail when total line coverage is below PCT percent
+    #[arg(long, value_name = "PCT")]
+    pub fail_under: Option<f64>,
+}
+
+impl CoverageArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let config = ForgeConfig::from_file()?;
+
+        config.coverage(self.html, self.cobertura, self.fail_under)?;
+
+        Ok(())
+    }
+}
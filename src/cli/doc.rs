@@ -0,0 +1,26 @@
+use crate::{core::ForgeConfig, Result};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct DocArgs {
+    /// Print the path of the generated index page
+    #[arg(long)]
+    pub open: bool,
+    /// Fail on undocumented public symbols in include/
+    #[arg(long)]
+    pub check: bool,
+}
+
+impl DocArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let config = ForgeConfig::from_file()?;
+
+        let index = config.doc(self.check)?;
+        match self.open {
+            true => println!("{}", index.display()),
+            false => println!("Wrote documentation to {}", index.display()),
+        }
+
+        Ok(())
+    }
+}
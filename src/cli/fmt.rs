@@ -0,0 +1,37 @@
+use crate::{core::formatter::Formatter, error, Error, Result};
+use clap::Args;
+use std::env;
+
+#[derive(Debug, Args)]
+pub struct FmtArgs {
+    /// List unformatted files and exit non-zero instead of formatting them
+    #[arg(long)]
+    pub check: bool,
+}
+
+impl FmtArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let formatter = Formatter::new(env::current_dir()?);
+
+        if !self.check {
+            let count = formatter.format()?;
+            println!("Formatted {} file(s)", count);
+            return Ok(());
+        }
+
+        let unformatted = formatter.check()?;
+        for file in &unformatted {
+            println!("{}", file.display());
+        }
+
+        if !unformatted.is_empty() {
+            return Err(error!(
+                CustomError,
+                "{} file(s) need formatting, run `forge fmt`",
+                unformatted.len()
+            ));
+        }
+
+        Ok(())
+    }
+}
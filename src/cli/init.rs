@@ -1,18 +1,43 @@
-use crate::{core::ForgeConfig, Result};
+use crate::{
+    core::{
+        build_system::BUILD_DIR,
+        formatter::Formatter,
+        init_plan::{ExistingFiles, InitPlan},
+        linter::Linter,
+        ForgeConfig,
+    },
+    Result,
+};
 use clap::Args;
-use std::env;
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 
 use super::prompter::{get_prompter, Prompter};
 
 #[derive(Debug, Args)]
-pub struct InitArgs {}
+pub struct InitArgs {
+    /// Overwrite files that already exist
+    #[arg(long, conflicts_with = "keep_existing")]
+    pub force: bool,
+    /// Keep files that already exist and only create the missing ones
+    #[arg(long)]
+    pub keep_existing: bool,
+    /// Only write Forge.toml, for the existing CMake project in this directory
+    #[arg(long, conflicts_with = "keep_existing")]
+    pub import: bool,
+}
 
 impl InitArgs {
     pub fn process_command(&self) -> Result<()> {
+        let cwd = env::current_dir()?;
+        if self.import {
+            return self.import(cwd);
+        }
         let prompter = get_prompter();
 
         // Project
-        let cwd = env::current_dir()?;
         let name = cwd
             .file_name()
             .and_then(|name| name.to_str())
@@ -22,11 +47,13 @@ impl InitArgs {
         let test_framework = prompter.select_test_framework()?;
         let build_system = prompter.select_build_system()?;
         let package_manager = prompter.select_package_manager()?;
+        let format_style = prompter.select_format_style()?;
+        let lint_preset = prompter.select_lint_preset()?;
 
         let config = ForgeConfig::new(
             name.to_string(),
-            cwd,
-            language,
+            cwd.clone(),
+            language.clone(),
             compiler.to_string(),
             build_system,
             package_manager,
@@ -34,10 +61,46 @@ impl InitArgs {
             "intellisenseMode ".to_string(),
         );
 
-        config.init()?;
-
+        let mut files = config.init_files();
+        files.extend([".clang-format", ".clang-tidy"].map(PathBuf::from));
+        let plan = InitPlan::new(&cwd, files, self.existing_files());
+        plan.print();
+        plan.check()?;
+
+        config.init(&plan)?;
+        if plan.writes(Path::new(".clang-format")) {
+            Formatter::new(cwd.clone()).write_config(&format_style)?;
+        }
+        if plan.writes(Path::new(".clang-tidy")) {
+            Linter::new(cwd.clone(), BUILD_DIR.into(), language).write_config(&lint_preset)?;
+        }
         Ok(())
     }
+
+    /// Writes Forge.toml from what CMake reports about the project, keeping its build files.
+    fn import(&self, cwd: PathBuf) -> Result<()> {
+        let config = ForgeConfig::import(cwd.clone())?;
+
+        // `--keep-existing` conflicts with `--import`, so Forge.toml is always written.
+        let existing = match self.force {
+            true => ExistingFiles::Overwrite,
+            false => ExistingFiles::Refuse,
+        };
+        let plan = InitPlan::new(&cwd, vec!["Forge.toml".into()], existing);
+        plan.print();
+        plan.check()?;
+        config.to_file()
+    }
+
+    fn existing_files(&self) -> ExistingFiles {
+        if self.force {
+            ExistingFiles::Overwrite
+        } else if self.keep_existing {
+            ExistingFiles::Keep
+        } else {
+            ExistingFiles::Refuse
+        }
+    }
 }
 
 #[cfg(test)]
@@ -76,13 +139,19 @@ mod tests {
         create_dummy_project(&path)?;
 
         env::set_current_dir(&path)?;
-        let args = InitArgs {};
+        let args = InitArgs {
+            force: false,
+            keep_existing: false,
+            import: false,
+        };
         args.process_command()?;
         env::set_current_dir(&cwd)?;
 
         // Validate
         assert!(check_file_exits(&path.join("CMakeLists.txt")));
         assert!(check_file_exits(&path.join(".gitignore")));
+        assert!(check_file_exits(&path.join(".clang-format")));
+        assert!(check_file_exits(&path.join(".clang-tidy")));
         assert!(check_file_exits(&path.join(".git")));
         assert!(check_file_exits(&path.join("include").join("lib.h")));
         assert!(check_file_exits(&path.join("src").join("lib.c")));
@@ -111,4 +180,38 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn test_existing_files_refused() -> anyhow::Result<()> {
+        let name = "dummy";
+        let cwd = env::current_dir()?;
+        let path = cwd.join(&name);
+        let main = "int main(void) { return 1; }\n";
+
+        // Set-up
+        create_dummy_project(&path.join("src"))?;
+        fs::write(path.join("src").join("main.c"), main)?;
+
+        // Test
+        env::set_current_dir(&path)?;
+        let args = InitArgs {
+            force: false,
+            keep_existing: false,
+            import: false,
+        };
+        let result = args.process_command();
+        env::set_current_dir(&cwd)?;
+
+        // Validate
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(path.join("src").join("main.c"))?, main);
+        assert!(!check_file_exits(&path.join("Forge.toml")));
+        assert!(!check_file_exits(&path.join("CMakeLists.txt")));
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
 }
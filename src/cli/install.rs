@@ -0,0 +1,20 @@
+use crate::{core::ForgeConfig, Result};
+use clap::Args;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct InstallArgs {
+    /// Install prefix, defaults to the build system's (e.g. /usr/local)
+    #[arg(long, value_name = "DIR")]
+    pub prefix: Option<PathBuf>,
+}
+
+impl InstallArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let config = ForgeConfig::from_file()?;
+
+        config.install(self.prefix.as_deref())?;
+
+        Ok(())
+    }
+}
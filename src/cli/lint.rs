@@ -0,0 +1,36 @@
+use crate::{
+    core::{
+        compile_db::default_jobs,
+        linter::{LintBackend, LintPreset},
+        ForgeConfig,
+    },
+    Result,
+};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct LintArgs {
+    /// Apply the fixes suggested by clang-tidy
+    #[arg(long)]
+    pub fix: bool,
+    /// Linter to run: `clang-tidy` or `cppcheck`
+    #[arg(long, default_value = "clang-tidy", value_parser = LintBackend::parse)]
+    pub backend: LintBackend,
+    /// Rewrite .clang-tidy with a check preset: minimal, recommended or strict
+    #[arg(long, value_parser = LintPreset::parse)]
+    pub preset: Option<LintPreset>,
+    /// Number of files linted in parallel
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+}
+
+impl LintArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let config = ForgeConfig::from_file()?;
+        let jobs = self.jobs.unwrap_or_else(default_jobs);
+
+        config.lint(&self.backend, self.fix, jobs, self.preset.as_ref())?;
+
+        Ok(())
+    }
+}
@@ -1,21 +1,45 @@
+mod bench;
 mod build;
+mod cache;
+mod check;
 mod clean;
 mod config;
+mod coverage;
+mod doc;
+mod fmt;
 pub mod init;
+mod install;
+mod lint;
 mod new;
+mod package;
 pub mod prompter;
+mod regen;
 mod run;
 mod testing;
+mod time_compile;
+mod version;
 
+use bench::BenchArgs;
 use build::BuildArgs;
+use cache::CacheArgs;
+use check::CheckArgs;
 use clap::{Parser, Subcommand};
 use clap_builder::builder::styling::{AnsiColor, Styles};
 use clean::CleanArgs;
 use config::ConfigArgs;
+use coverage::CoverageArgs;
+use doc::DocArgs;
+use fmt::FmtArgs;
 use init::InitArgs;
+use install::InstallArgs;
+use lint::LintArgs;
 use new::NewArgs;
+use package::PackageArgs;
+use regen::RegenArgs;
 use run::RunArgs;
 use testing::TestArgs;
+use time_compile::TimeCompileArgs;
+use version::VersionArgs;
 
 pub const CLAP_STYLING: Styles = Styles::styled()
     .header(AnsiColor::Green.on_default())
@@ -37,16 +61,41 @@ pub enum Commands {
     Config(ConfigArgs),
     /// Build an existing project.
     Build(BuildArgs),
+    /// Check that the sources compile, without building.
+    Check(CheckArgs),
     /// Initialize project in current working directory.
     Init(InitArgs),
     /// Create a new project including directory.
     New(NewArgs),
     /// Run binary build.
     Run(RunArgs),
+    /// Build the release profile and install it.
+    Install(InstallArgs),
+    /// Build the release profile and package it as .tar.gz, .deb or .rpm.
+    Package(PackageArgs),
     /// Run compiled tests.
     Test(TestArgs),
-    /// Clean build artifacts.
+    /// Build the benchmarks in release, run them and compare with a baseline.
+    Bench(BenchArgs),
+    /// Run the tests against an instrumented build and report code coverage.
+    Coverage(CoverageArgs),
+    /// Show statistics of, or clear, the compiler cache.
+    Cache(CacheArgs),
+    /// Generate the API documentation with Doxygen.
+    Doc(DocArgs),
+    /// Format sources with clang-format.
+    Fmt(FmtArgs),
+    /// Lint sources with clang-tidy or cppcheck.
+    Lint(LintArgs),
+    /// Regenerate the build files from Forge.toml.
+    Regen(RegenArgs),
+    /// Print or bump the project version.
+    Version(VersionArgs),
+    /// Clean build artifacts, every build tree unless a scope is given.
     Clean(CleanArgs),
+    /// Run a compiler command and log its duration, used by `forge build --timings`.
+    #[command(hide = true)]
+    TimeCompile(TimeCompileArgs),
 }
 
 impl Commands {
@@ -54,11 +103,23 @@ impl Commands {
         match self {
             Commands::Config(args) => Ok(args.process_command()?),
             Commands::Build(args) => Ok(args.process_command()?),
+            Commands::Check(args) => Ok(args.process_command()?),
             Commands::Init(args) => Ok(args.process_command()?),
             Commands::New(args) => Ok(args.process_command()?),
             Commands::Run(args) => Ok(args.process_command()?),
+            Commands::Install(args) => Ok(args.process_command()?),
+            Commands::Package(args) => Ok(args.process_command()?),
             Commands::Test(args) => Ok(args.process_command()?),
+            Commands::Bench(args) => Ok(args.process_command()?),
+            Commands::Coverage(args) => Ok(args.process_command()?),
+            Commands::Cache(args) => Ok(args.process_command()?),
+            Commands::Doc(args) => Ok(args.process_command()?),
+            Commands::Fmt(args) => Ok(args.process_command()?),
+            Commands::Lint(args) => Ok(args.process_command()?),
+            Commands::Regen(args) => Ok(args.process_command()?),
+            Commands::Version(args) => Ok(args.process_command()?),
             Commands::Clean(args) => Ok(args.process_command()?),
+            Commands::TimeCompile(args) => Ok(args.process_command()?),
         }
     }
 }
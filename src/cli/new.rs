@@ -1,4 +1,13 @@
-use crate::{core::ForgeConfig, Result};
+use crate::{
+    core::{
+        build_system::BUILD_DIR,
+        formatter::Formatter,
+        init_plan::{ExistingFiles, InitPlan},
+        linter::Linter,
+        ForgeConfig,
+    },
+    Result,
+};
 use clap::Args;
 use std::env;
 
@@ -21,11 +30,13 @@ impl NewArgs {
         let test_framework = prompter.select_test_framework()?;
         let build_system = prompter.select_build_system()?;
         let package_manager = prompter.select_package_manager()?;
+        let format_style = prompter.select_format_style()?;
+        let lint_preset = prompter.select_lint_preset()?;
 
         let config = ForgeConfig::new(
             name.to_string(),
-            cwd,
-            language,
+            cwd.clone(),
+            language.clone(),
             compiler.to_string(),
             build_system,
             package_manager,
@@ -33,7 +44,14 @@ impl NewArgs {
             "intellisenseMode ".to_string(),
         );
 
-        config.init()?;
+        // A new directory, so every file is created.
+        config.init(&InitPlan::new(
+            &cwd,
+            config.init_files(),
+            ExistingFiles::Overwrite,
+        ))?;
+        Formatter::new(cwd.clone()).write_config(&format_style)?;
+        Linter::new(cwd, BUILD_DIR.into(), language).write_config(&lint_preset)?;
 
         Ok(())
     }
@@ -74,6 +92,8 @@ mod tests {
         // Validate
         assert!(check_file_exits(&path.join("CMakeLists.txt")));
         assert!(check_file_exits(&path.join(".gitignore")));
+        assert!(check_file_exits(&path.join(".clang-format")));
+        assert!(check_file_exits(&path.join(".clang-tidy")));
         assert!(check_file_exits(&path.join(".git")));
         assert!(check_file_exits(&path.join("include").join("lib.h")));
         assert!(check_file_exits(&path.join("src").join("lib.c")));
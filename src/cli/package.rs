@@ -0,0 +1,23 @@
+use crate::{
+    core::{packager::PackageFormat, ForgeConfig},
+    Result,
+};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct PackageArgs {
+    /// Comma separated package formats: tgz, deb, rpm (deb and rpm need CMake)
+    #[arg(long, default_value = "tgz")]
+    pub format: String,
+}
+
+impl PackageArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let config = ForgeConfig::from_file()?;
+        let formats = PackageFormat::parse_list(&self.format)?;
+
+        config.package(&formats)?;
+
+        Ok(())
+    }
+}
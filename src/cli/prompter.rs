@@ -1,5 +1,7 @@
 use crate::core::build_system::BuildSystems;
 use crate::core::compiler::detect_compilers;
+use crate::core::formatter::FormatStyle;
+use crate::core::linter::LintPreset;
 use crate::core::package_manager::PackageManagers;
 use crate::core::test_framework::TestFrameworks;
 use crate::{core::language::Language, Result};
@@ -21,6 +23,8 @@ pub trait Prompter {
     fn select_test_framework(&self) -> Result<TestFrameworks>;
     fn select_build_system(&self) -> Result<BuildSystems>;
     fn select_package_manager(&self) -> Result<PackageManagers>;
+    fn select_format_style(&self) -> Result<FormatStyle>;
+    fn select_lint_preset(&self) -> Result<LintPreset>;
 }
 
 pub struct RealPrompter {}
@@ -51,6 +55,14 @@ impl Prompter for RealPrompter {
         let package_manager = PackageManagers::from_str(&choice);
         Ok(package_manager)
     }
+    fn select_format_style(&self) -> Result<FormatStyle> {
+        let choice = Select::new("Format Style:", FormatStyle::variants()).prompt()?;
+        FormatStyle::parse(choice)
+    }
+    fn select_lint_preset(&self) -> Result<LintPreset> {
+        let choice = Select::new("Lint Preset:", LintPreset::variants()).prompt()?;
+        LintPreset::parse(choice)
+    }
 }
 
 pub struct MockPrompter {}
@@ -72,4 +84,10 @@ impl Prompter for MockPrompter {
     fn select_package_manager(&self) -> Result<PackageManagers> {
         Ok(PackageManagers::Vcpkg)
     }
+    fn select_format_style(&self) -> Result<FormatStyle> {
+        Ok(FormatStyle::Llvm)
+    }
+    fn select_lint_preset(&self) -> Result<LintPreset> {
+        Ok(LintPreset::Recommended)
+    }
 }
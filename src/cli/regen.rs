@@ -0,0 +1,100 @@
+use crate::{core::ForgeConfig, Result};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct RegenArgs {
+    /// Replace a build file without forge regions, keeping a .bak copy
+    #[arg(long)]
+    pub force: bool,
+}
+
+impl RegenArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let config = ForgeConfig::from_file()?;
+
+        config.regen(self.force)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        build_system::BuildSystems,
+        language::{CStandard, Language},
+        package_manager::PackageManagers,
+        test_framework::TestFrameworks,
+    };
+    use serial_test::serial;
+    use std::{env, fs, path::PathBuf};
+
+    // Utility functions
+    fn create_dummy_project(path: &PathBuf) -> anyhow::Result<()> {
+        fs::create_dir_all(path)?;
+        Ok(())
+    }
+
+    fn delete_dummy_project(path: &PathBuf) -> anyhow::Result<()> {
+        fs::remove_dir_all(path)?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_process_command() -> anyhow::Result<()> {
+        let name = "dummy";
+        let cwd = env::current_dir()?;
+        let path = cwd.join(&name);
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let config = ForgeConfig::new(
+            name.to_string(),
+            path.clone(),
+            Language::C(CStandard::C11),
+            "gcc".to_string(),
+            BuildSystems::Make,
+            PackageManagers::Vcpkg,
+            TestFrameworks::CMocka,
+            String::new(),
+        );
+        config.to_file()?;
+        fs::write(path.join("Makefile"), "all:\n")?;
+
+        // Test
+        env::set_current_dir(&path)?;
+        let refused = RegenArgs { force: false }.process_command();
+        let forced = RegenArgs { force: true }.process_command();
+
+        let makefile = fs::read_to_string(path.join("Makefile"))?;
+        let makefile = makefile.replace(
+            "# forge:begin settings\n",
+            "# forge:begin settings\nCPPFLAGS += -DUSER\n",
+        );
+        fs::write(path.join("Makefile"), makefile)?;
+        let forge_toml = fs::read_to_string(path.join("Forge.toml"))?;
+        fs::write(
+            path.join("Forge.toml"),
+            forge_toml.replacen("C = \"C11\"", "C = \"C99\"", 1),
+        )?;
+        let regenerated = RegenArgs { force: false }.process_command();
+        env::set_current_dir(&cwd)?;
+
+        // Validate
+        assert!(refused.is_err());
+        forced?;
+        regenerated?;
+        assert_eq!(fs::read_to_string(path.join("Makefile.bak"))?, "all:\n");
+        let makefile = fs::read_to_string(path.join("Makefile"))?;
+        assert!(makefile.contains("STD := -std=c99"));
+        assert!(makefile.contains("# forge:begin settings\nCPPFLAGS += -DUSER\n# forge:end"));
+        assert!(path.join("include").join("version.h").exists());
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+}
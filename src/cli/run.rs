@@ -1,27 +1,62 @@
-use crate::{core::ForgeConfig, Result};
+use crate::{
+    core::{profile::Profile, sanitizer::SanitizerSet, ForgeConfig},
+    Result,
+};
 use clap::Args;
 
 #[derive(Debug, Args)]
 pub struct RunArgs {
-    /// Compiler flags
+    /// Run the binary target with this name instead of the project's binary
+    #[arg(long, value_name = "NAME")]
+    pub bin: Option<String>,
+    /// Run the example with this name
+    #[arg(long, value_name = "NAME", conflicts_with = "bin")]
+    pub example: Option<String>,
+    /// Build and run with sanitizers, e.g. `address,undefined`, `thread` or `memory`
+    #[arg(long, value_name = "LIST", value_parser = SanitizerSet::parse)]
+    pub sanitize: Option<SanitizerSet>,
+    /// Use the release profile, built in build/release
+    #[arg(long)]
+    pub release: bool,
+    /// Run the binary under valgrind and fail on memory errors or leaks
+    #[arg(long, conflicts_with = "sanitize")]
+    pub memcheck: bool,
+    /// Arguments passed to the program, after `--`
     #[arg(last = true)]
-    pub extra: Option<Vec<String>>,
+    pub args: Vec<String>,
 }
 
 impl RunArgs {
     pub fn process_command(&self) -> Result<()> {
+        let code = self.run()?;
+        if code != 0 {
+            std::process::exit(code);
+        }
+        Ok(())
+    }
+
+    /// Builds and runs the selected target, returning its exit code.
+    fn run(&self) -> Result<i32> {
         let config = ForgeConfig::from_file()?;
+        let target = config.run_target(self.bin.as_deref(), self.example.as_deref())?;
 
-        config.build(self.extra.as_ref())?;
-        config.run()?;
+        let tree =
+            config.build_tree(self.sanitize.as_ref(), Profile::from_release(self.release))?;
 
-        Ok(())
+        config.build_target(&tree, &target)?;
+        config.run(&tree, &target, &self.args, self.memcheck)
     }
 }
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cli::{config::ConfigArgs, init::InitArgs};
+    use crate::core::{
+        build_system::BuildSystems,
+        language::{CStandard, Language},
+        package_manager::PackageManagers,
+        test_framework::TestFrameworks,
+    };
     use serial_test::serial;
     use std::{env, fs, path::PathBuf};
 
@@ -55,7 +90,11 @@ mod tests {
 
         env::set_current_dir(&path)?;
 
-        let args = InitArgs {};
+        let args = InitArgs {
+            force: false,
+            keep_existing: false,
+            import: false,
+        };
         args.process_command()?;
 
         let config_args = ConfigArgs {
@@ -64,7 +103,14 @@ mod tests {
         };
         config_args.process_command()?;
 
-        let test_args = RunArgs { extra: None };
+        let test_args = RunArgs {
+            bin: None,
+            example: None,
+            sanitize: None,
+            release: false,
+            memcheck: false,
+            args: vec![],
+        };
         test_args.process_command()?;
         env::set_current_dir(&cwd)?;
 
@@ -78,4 +124,69 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn test_run_bin_with_args() -> anyhow::Result<()> {
+        let name = "dummy";
+        let cwd = env::current_dir()?;
+        let path = cwd.join(&name);
+
+        // Set-up
+        create_dummy_project(&path)?;
+        fs::create_dir_all(path.join("src").join("bin"))?;
+        fs::write(
+            path.join("src").join("main.c"),
+            "int main(void) { return 0; }\n",
+        )?;
+        fs::write(
+            path.join("src").join("bin").join("tool.c"),
+            "#include <stdlib.h>\nint main(int argc, char **argv) { return argc > 1 ? atoi(argv[1]) : 0; }\n",
+        )?;
+        let config = ForgeConfig::new(
+            name.to_string(),
+            path.clone(),
+            Language::C(CStandard::C11),
+            "gcc".to_string(),
+            BuildSystems::Make,
+            PackageManagers::Vcpkg,
+            TestFrameworks::CMocka,
+            String::new(),
+        );
+        config.to_file()?;
+
+        // Test
+        env::set_current_dir(&path)?;
+        let run_args = RunArgs {
+            bin: Some("tool".to_string()),
+            example: None,
+            sanitize: None,
+            release: false,
+            memcheck: false,
+            args: vec!["3".to_string()],
+        };
+        let code = run_args.run();
+        let missing = RunArgs {
+            bin: Some("missing".to_string()),
+            args: vec![],
+            ..run_args
+        }
+        .run();
+        env::set_current_dir(&cwd)?;
+
+        // Validate
+        assert_eq!(code?, 3);
+        assert!(check_file_exits(
+            &path.join("build").join("bin").join("tool")
+        ));
+        assert!(!check_file_exits(
+            &path.join("build").join("bin").join("dummy")
+        ));
+        assert!(missing.is_err());
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
 }
@@ -1,8 +1,17 @@
-use crate::{core::ForgeConfig, Result};
+use crate::{
+    core::{profile::Profile, sanitizer::SanitizerSet, test_selection::TestSelection, ForgeConfig},
+    Result,
+};
 use ansi_term::Colour::{Cyan, Green};
 use clap::Args;
+use std::path::PathBuf;
 
 fn ctest_help() -> Result<()> {
+    let mut forge_help = TestArgs::augment_args(clap::Command::new("forge test"))
+        .styles(crate::cli::CLAP_STYLING)
+        .override_usage("forge test [OPTIONS] [PATTERN] [-- <CTEST OPTIONS>...]");
+    println!("{}", forge_help.render_help().ansi());
+
     let output = std::process::Command::new("ctest").arg("--help").output()?;
     let binding = String::from_utf8_lossy(&output.stdout);
     let text = binding.as_ref();
@@ -11,7 +20,7 @@ fn ctest_help() -> Result<()> {
 
     println!(
         "{} {}\n",
-        Green.paint("Usage:"),
+        Green.paint("CTest Usage:"),
         Cyan.paint("forge test -- [OPTIONS]")
     );
 
@@ -84,7 +93,33 @@ pub struct TestArgs {
     /// Help
     #[arg(short, long)]
     pub help: bool,
-    /// CTest flags
+    /// Only run tests whose name contains PATTERN
+    pub pattern: Option<String>,
+    /// Skip tests whose name contains PATTERN
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude: Option<String>,
+    /// Only run tests with the given label (ctest label, meson suite or Boost label)
+    #[arg(long)]
+    pub label: Option<String>,
+    /// Number of tests to run in parallel
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+    /// Run the tests up to N times, stopping at the first failure
+    #[arg(long, value_name = "N")]
+    pub repeat_until_fail: Option<u32>,
+    /// Only run the tests that failed in the previous run
+    #[arg(long)]
+    pub rerun_failed: bool,
+    /// Build and test with sanitizers, e.g. `address,undefined`, `thread` or `memory`
+    #[arg(long, value_name = "LIST", value_parser = SanitizerSet::parse)]
+    pub sanitize: Option<SanitizerSet>,
+    /// Run the tests under valgrind and fail on memory errors or leaks
+    #[arg(long, conflicts_with = "sanitize")]
+    pub memcheck: bool,
+    /// Write the results to PATH (JUnit XML, or JSON for `.json`). Repeatable.
+    #[arg(long, value_name = "PATH")]
+    pub report: Vec<PathBuf>,
+    /// Test runner flags (ctest, meson test or the test binary)
     #[arg(last = true)]
     pub options: Option<Vec<String>>,
 }
@@ -95,8 +130,29 @@ impl TestArgs {
             return Ok(ctest_help()?);
         }
 
+        let selection = TestSelection {
+            pattern: self.pattern.clone(),
+            exclude: self.exclude.clone(),
+            label: self.label.clone(),
+            jobs: self.jobs,
+            repeat_until_fail: self.repeat_until_fail,
+            rerun_failed: self.rerun_failed,
+        };
+
         let config = ForgeConfig::from_file()?;
-        config.test(self.options.as_ref())?;
+        let tree = config.build_tree(self.sanitize.as_ref(), Profile::Debug)?;
+
+        // Sanitized trees are private to forge, so make sure they're up to date.
+        if tree.is_managed() {
+            config.build(&tree, None)?;
+        }
+        config.test(
+            &tree,
+            &selection,
+            self.options.as_ref(),
+            &self.report,
+            self.memcheck,
+        )?;
 
         Ok(())
     }
@@ -106,6 +162,7 @@ impl TestArgs {
 mod tests {
     use super::*;
     use crate::cli::{build::BuildArgs, config::ConfigArgs, init::InitArgs};
+    use crate::core::diagnostics::MessageFormat;
     use serial_test::serial;
     use std::{env, fs, path::PathBuf};
 
@@ -140,7 +197,11 @@ mod tests {
 
         env::set_current_dir(&path)?;
 
-        let args = InitArgs {};
+        let args = InitArgs {
+            force: false,
+            keep_existing: false,
+            import: false,
+        };
         args.process_command()?;
 
         let config_args = ConfigArgs {
@@ -149,11 +210,26 @@ mod tests {
         };
         config_args.process_command()?;
 
-        let build_args = BuildArgs { options: None };
+        let build_args = BuildArgs {
+            sanitize: None,
+            release: false,
+            message_format: MessageFormat::Human,
+            timings: None,
+            options: None,
+        };
         build_args.process_command()?;
 
         let test_args = TestArgs {
             help: false,
+            pattern: None,
+            exclude: None,
+            label: None,
+            jobs: None,
+            repeat_until_fail: None,
+            rerun_failed: false,
+            sanitize: None,
+            memcheck: false,
+            report: vec![],
             options: None,
         };
         test_args.process_command()?;
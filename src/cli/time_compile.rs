@@ -0,0 +1,26 @@
+use crate::{core::timings, Result};
+use clap::Args;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct TimeCompileArgs {
+    /// Add clang's -ftime-trace to compiles
+    #[arg(long)]
+    pub trace: bool,
+    /// Log the start and end times are appended to
+    pub log: PathBuf,
+    /// The compiler command
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+    pub command: Vec<String>,
+}
+
+impl TimeCompileArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let code = timings::time_command(&self.log, &self.command, self.trace)?;
+        if code != 0 {
+            std::process::exit(code);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    core::{version::VersionPart, ForgeConfig},
+    Result,
+};
+use clap::{Args, Subcommand};
+
+#[derive(Debug, Args)]
+pub struct VersionArgs {
+    #[command(subcommand)]
+    pub command: Option<VersionCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum VersionCommands {
+    /// Bump the project version and update the files that contain it.
+    Bump {
+        /// Part of the version to increment
+        #[arg(value_parser = VersionPart::variants())]
+        part: String,
+
+        /// Commit the changed files and create an annotated `v<version>` git tag
+        #[arg(long)]
+        tag: bool,
+    },
+}
+
+impl VersionArgs {
+    pub fn process_command(&self) -> Result<()> {
+        let mut config = ForgeConfig::from_file()?;
+
+        match &self.command {
+            None => println!("{}", config.version()),
+            Some(VersionCommands::Bump { part, tag }) => {
+                let old = config.version().clone();
+                let new = config.bump_version(part.parse()?, *tag)?;
+                println!("Bumped version {} -> {}", old, new);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        build_system::BuildSystems,
+        language::{CStandard, Language},
+        package_manager::PackageManagers,
+        test_framework::TestFrameworks,
+    };
+    use serial_test::serial;
+    use std::{env, fs, path::PathBuf};
+
+    // Utility functions
+    fn create_dummy_project(path: &PathBuf) -> anyhow::Result<()> {
+        fs::create_dir_all(path)?;
+        Ok(())
+    }
+
+    fn delete_dummy_project(path: &PathBuf) -> anyhow::Result<()> {
+        fs::remove_dir_all(path)?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_process_command_bump() -> anyhow::Result<()> {
+        let name = "dummy";
+        let cwd = env::current_dir()?;
+        let path = cwd.join(&name);
+
+        // Test
+        create_dummy_project(&path)?;
+
+        let config = ForgeConfig::new(
+            name.to_string(),
+            path.clone(),
+            Language::C(CStandard::C11),
+            "gcc".to_string(),
+            BuildSystems::Make,
+            PackageManagers::Vcpkg,
+            TestFrameworks::CMocka,
+            String::new(),
+        );
+        config.to_file()?;
+        fs::write(path.join("Makefile"), "NAME := dummy\nVERSION := 0.1.0\n")?;
+
+        env::set_current_dir(&path)?;
+        let version_args = VersionArgs {
+            command: Some(VersionCommands::Bump {
+                part: "minor".to_string(),
+                tag: false,
+            }),
+        };
+        let result = version_args.process_command();
+        env::set_current_dir(&cwd)?;
+        result?;
+
+        // Validate
+        let forge_toml = fs::read_to_string(path.join("Forge.toml"))?;
+        assert!(forge_toml.contains("version = \"0.2.0\""));
+        let header = fs::read_to_string(path.join("include").join("version.h"))?;
+        assert!(header.contains("#define DUMMY_VERSION \"0.2.0\""));
+        let makefile = fs::read_to_string(path.join("Makefile"))?;
+        assert_eq!(makefile, "NAME := dummy\nVERSION := 0.2.0\n");
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,435 @@
+use super::language::Language;
+use crate::{error, Error, Result};
+use ansi_term::Colour::{Green, Red};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use which::which;
+
+/// Benchmark results of the last run, relative to the project directory.
+pub const BENCH_DIR: &str = "build/bench";
+
+/// Saved baselines, relative to the project directory. Kept out of `build/` so `forge clean`
+/// leaves them alone.
+pub const BASELINE_DIR: &str = ".forge/bench";
+
+/// Google Benchmark for C++, and a small generated harness with the same JSON output for C.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchFramework {
+    GoogleBenchmark,
+    Harness,
+}
+
+impl BenchFramework {
+    pub fn for_language(language: &Language) -> BenchFramework {
+        match language {
+            Language::C(_) => BenchFramework::Harness,
+            Language::Cpp(_) => BenchFramework::GoogleBenchmark,
+        }
+    }
+
+    /// CMake package to `find_package`, if the framework needs one.
+    pub fn cmake_package(&self) -> Option<&'static str> {
+        match self {
+            BenchFramework::GoogleBenchmark => Some("benchmark"),
+            BenchFramework::Harness => None,
+        }
+    }
+
+    pub fn cmake_target(&self) -> &'static str {
+        match self {
+            BenchFramework::GoogleBenchmark => "benchmark::benchmark",
+            BenchFramework::Harness => "",
+        }
+    }
+
+    /// Meson `dependency()` expression, an empty dependency for the harness.
+    pub fn meson_dependency(&self) -> &'static str {
+        match self {
+            BenchFramework::GoogleBenchmark => "dependency('benchmark')",
+            BenchFramework::Harness => "declare_dependency()",
+        }
+    }
+
+    /// Linker flags for the framework when building with plain Make.
+    pub fn link_libs(&self) -> &'static str {
+        match self {
+            BenchFramework::GoogleBenchmark => "-lbenchmark -pthread",
+            BenchFramework::Harness => "",
+        }
+    }
+
+    /// Arguments making a benchmark binary write its results to `out` as JSON.
+    pub fn binary_args(&self, out: &Path) -> Vec<String> {
+        vec![
+            format!("--benchmark_out={}", out.display()),
+            "--benchmark_out_format=json".to_string(),
+        ]
+    }
+
+    // Package Managers
+    pub fn vcpkg_setup(&self, dir: &Path) -> Result<()> {
+        if *self == BenchFramework::Harness {
+            return Ok(());
+        }
+        match which("vcpkg") {
+            Ok(_) => {
+                let status = Command::new("vcpkg")
+                    .args(["add", "port", "benchmark"])
+                    .current_dir(dir)
+                    .status()?;
+
+                if !status.success() {
+                    eprintln!("Failed to retrieve Google Benchmark for vcpkg.")
+                }
+                Ok(())
+            }
+            Err(e) => Err(error!(CustomError, "{}", e)),
+        }
+    }
+
+    pub fn conan_setup(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Writes a sample benchmark to `bench/`, and the harness header for C. Returns the files
+    /// written.
+    pub fn scaffold(&self, directory: &Path, name: &str) -> Result<Vec<PathBuf>> {
+        let bench_dir = directory.join("bench");
+        fs::create_dir_all(&bench_dir)?;
+
+        let files = match self {
+            BenchFramework::GoogleBenchmark => vec![(
+                bench_dir.join(format!("{}_bench.cpp", name)),
+                GOOGLE_BENCHMARK_SAMPLE,
+            )],
+            BenchFramework::Harness => vec![
+                (bench_dir.join("bench.h"), HARNESS_HEADER),
+                (bench_dir.join(format!("{}_bench.c", name)), HARNESS_SAMPLE),
+            ],
+        };
+
+        let mut written = vec![];
+        for (path, contents) in files {
+            fs::write(&path, contents)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+/// What `forge bench` runs and compares.
+#[derive(Debug, Clone, Default)]
+pub struct BenchRun {
+    /// Only run the bench target with this name.
+    pub target: Option<String>,
+    /// Extra arguments for the benchmark binaries.
+    pub args: Vec<String>,
+    pub save_baseline: Option<String>,
+    pub baseline: Option<String>,
+    /// Slowdown in percent above which a benchmark counts as regressed.
+    pub threshold: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BenchResult {
+    pub name: String,
+    /// Wall time per iteration, in `time_unit`.
+    pub real_time: f64,
+    /// CPU time per iteration, in `time_unit`.
+    pub cpu_time: f64,
+    #[serde(default = "default_time_unit")]
+    pub time_unit: String,
+    /// `iteration` for measurements, `aggregate` for the mean/median/stddev of repetitions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_type: Option<String>,
+}
+
+fn default_time_unit() -> String {
+    "ns".to_string()
+}
+
+impl BenchResult {
+    /// Wall time per iteration in nanoseconds.
+    pub fn real_ns(&self) -> f64 {
+        let scale = match self.time_unit.as_str() {
+            "us" => 1e3,
+            "ms" => 1e6,
+            "s" => 1e9,
+            _ => 1.0,
+        };
+        self.real_time * scale
+    }
+}
+
+/// Results in Google Benchmark's JSON format, which is also the format of saved baselines.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BenchReport {
+    pub benchmarks: Vec<BenchResult>,
+}
+
+impl BenchReport {
+    pub fn from_file(path: &Path) -> Result<BenchReport> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| error!(CustomError, "Cannot read {}: {}", path.display(), e))?;
+        BenchReport::from_json(&contents)
+    }
+
+    /// Parses `--benchmark_out` JSON, keeping only the measurements.
+    pub fn from_json(json: &str) -> Result<BenchReport> {
+        let mut report: BenchReport = serde_json::from_str(json)?;
+        report
+            .benchmarks
+            .retain(|b| b.run_type.as_deref().unwrap_or("iteration") == "iteration");
+        Ok(report)
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Adds the results of the bench target `target`, prefixing their names with it.
+    pub fn extend(&mut self, target: &str, other: BenchReport) {
+        self.benchmarks
+            .extend(other.benchmarks.into_iter().map(|mut b| {
+                b.name = format!("{}/{}", target, b.name);
+                b
+            }));
+    }
+
+    /// Changes from `baseline` for every benchmark present in both reports.
+    pub fn compare(&self, baseline: &BenchReport) -> Vec<BenchChange> {
+        self.benchmarks
+            .iter()
+            .filter_map(|current| {
+                let base = baseline
+                    .benchmarks
+                    .iter()
+                    .find(|b| b.name == current.name)?;
+                Some(BenchChange {
+                    name: current.name.clone(),
+                    baseline_ns: base.real_ns(),
+                    current_ns: current.real_ns(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchChange {
+    pub name: String,
+    pub baseline_ns: f64,
+    pub current_ns: f64,
+}
+
+impl BenchChange {
+    /// Change of the wall time in percent, positive when slower.
+    pub fn percent(&self) -> f64 {
+        if self.baseline_ns == 0.0 {
+            return 0.0;
+        }
+        (self.current_ns - self.baseline_ns) / self.baseline_ns * 100.0
+    }
+
+    pub fn is_regression(&self, threshold: f64) -> bool {
+        self.percent() > threshold
+    }
+}
+
+/// Prints the comparison with a baseline and returns the number of regressions.
+pub fn print_comparison(changes: &[BenchChange], baseline: &str, threshold: f64) -> usize {
+    let width = changes
+        .iter()
+        .map(|c| c.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Benchmark".len());
+
+    println!(
+        "\n{:<width$}  {:>14}  {:>14}  {:>8}",
+        "Benchmark",
+        baseline,
+        "Current",
+        "Change",
+        width = width
+    );
+    let mut regressions = 0;
+    for c in changes {
+        let change = format!("{:+.1}%", c.percent());
+        let change = match c.is_regression(threshold) {
+            true => {
+                regressions += 1;
+                Red.paint(format!("{:>8}  regressed", change)).to_string()
+            }
+            false if c.percent() < -threshold => Green.paint(format!("{:>8}", change)).to_string(),
+            false => format!("{:>8}", change),
+        };
+        println!(
+            "{:<width$}  {:>11.1} ns  {:>11.1} ns  {}",
+            c.name,
+            c.baseline_ns,
+            c.current_ns,
+            change,
+            width = width
+        );
+    }
+    println!();
+    regressions
+}
+
+pub fn baseline_path(directory: &Path, name: &str) -> PathBuf {
+    directory.join(BASELINE_DIR).join(format!("{}.json", name))
+}
+
+const GOOGLE_BENCHMARK_SAMPLE: &str = r#"#include <benchmark/benchmark.h>
+
+#include <numeric>
+#include <vector>
+
+static void BM_Accumulate(benchmark::State &state) {
+    std::vector<int> values(static_cast<size_t>(state.range(0)), 1);
+    for (auto _ : state) {
+        benchmark::DoNotOptimize(std::accumulate(values.begin(), values.end(), 0));
+    }
+}
+BENCHMARK(BM_Accumulate)->Range(8, 8 << 10);
+
+BENCHMARK_MAIN();
+"#;
+
+const HARNESS_HEADER: &str = r#"/* Benchmark harness generated by forge. Pass --benchmark_out=<file> to write the results as
+ * Google Benchmark JSON. */
+#ifndef FORGE_BENCH_H
+#define FORGE_BENCH_H
+
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+
+typedef void (*bench_fn)(long iterations);
+
+struct bench_case {
+    const char *name;
+    bench_fn fn;
+};
+
+/* Runs each case with ten times more iterations until it takes at least 0.1s, then reports the
+ * time per iteration. */
+static int bench_main(int argc, char **argv, const struct bench_case *cases, int count)
+{
+    const char *out_path = NULL;
+    FILE *out = NULL;
+    int i;
+
+    for (i = 1; i < argc; i++) {
+        if (strncmp(argv[i], "--benchmark_out=", 16) == 0) {
+            out_path = argv[i] + 16;
+        }
+    }
+    if (out_path != NULL && (out = fopen(out_path, "w")) == NULL) {
+        perror(out_path);
+        return 1;
+    }
+
+    if (out != NULL) {
+        fprintf(out, "{\n  \"benchmarks\": [\n");
+    }
+    for (i = 0; i < count; i++) {
+        long iterations = 1;
+        double elapsed;
+        double ns;
+
+        for (;;) {
+            clock_t start = clock();
+            cases[i].fn(iterations);
+            elapsed = (double)(clock() - start) / CLOCKS_PER_SEC;
+            if (elapsed >= 0.1 || iterations >= 1000000000L) {
+                break;
+            }
+            iterations *= 10;
+        }
+        ns = elapsed * 1e9 / (double)iterations;
+        printf("%-32s %12.1f ns %12ld\n", cases[i].name, ns, iterations);
+        if (out != NULL) {
+            fprintf(out,
+                    "    {\"name\": \"%s\", \"iterations\": %ld, \"real_time\": %f, "
+                    "\"cpu_time\": %f, \"time_unit\": \"ns\"}%s\n",
+                    cases[i].name, iterations, ns, ns, i + 1 < count ? "," : "");
+        }
+    }
+    if (out != NULL) {
+        fprintf(out, "  ]\n}\n");
+        fclose(out);
+    }
+    return 0;
+}
+
+#endif
+"#;
+
+const HARNESS_SAMPLE: &str = r#"#include "bench.h"
+
+static volatile unsigned long sink;
+
+static void bench_sum(long iterations)
+{
+    long i;
+    unsigned long sum = 0;
+
+    for (i = 0; i < iterations; i++) {
+        sum += (unsigned long)i;
+    }
+    sink = sum;
+}
+
+int main(int argc, char **argv)
+{
+    const struct bench_case cases[] = {
+        {"bench_sum", bench_sum},
+    };
+    return bench_main(argc, argv, cases, (int)(sizeof(cases) / sizeof(cases[0])));
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() -> anyhow::Result<()> {
+        let baseline = r#"{"context": {}, "benchmarks": [
+            {"name": "BM_A", "run_type": "iteration", "real_time": 100.0, "cpu_time": 99.0, "time_unit": "ns"},
+            {"name": "BM_B", "run_type": "iteration", "real_time": 2.0, "cpu_time": 2.0, "time_unit": "us"},
+            {"name": "BM_B_mean", "run_type": "aggregate", "real_time": 2.0, "cpu_time": 2.0, "time_unit": "us"}
+        ]}"#;
+        let current = r#"{"benchmarks": [
+            {"name": "BM_A", "real_time": 103.0, "cpu_time": 103.0, "time_unit": "ns"},
+            {"name": "BM_B", "real_time": 2600.0, "cpu_time": 2600.0, "time_unit": "ns"},
+            {"name": "BM_C", "real_time": 1.0, "cpu_time": 1.0, "time_unit": "ns"}
+        ]}"#;
+
+        // Test
+        let mut base = BenchReport::default();
+        base.extend("bench", BenchReport::from_json(baseline)?);
+        let mut report = BenchReport::default();
+        report.extend("bench", BenchReport::from_json(current)?);
+        let changes = report.compare(&base);
+
+        // Validate
+        assert_eq!(base.benchmarks.len(), 2);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].name, "bench/BM_B");
+        assert_eq!(changes[1].baseline_ns, 2000.0);
+        assert!(!changes[0].is_regression(5.0));
+        assert!(changes[1].is_regression(5.0));
+        assert_eq!(print_comparison(&changes, "main", 5.0), 1);
+
+        Ok(())
+    }
+}
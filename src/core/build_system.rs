@@ -1,8 +1,33 @@
+use super::bench::BenchFramework;
+use super::compile_db::normalize;
+use super::compiler::CompilerFamily;
+use super::config::{BuildConfig, ProjectConfig};
+use super::diagnostics::{self, Diagnostic, MessageFormat};
+use super::memcheck::split_wrapper;
+use super::packager::{
+    archive, archive_stem, cmake_string, cpack_rules, package_artifacts, PackageFormat, PACKAGE_DIR,
+};
+use super::profile::Profile;
+use super::regen;
+use super::targets::{ident, Target, TargetKind, Targets};
+use super::test_report::{TestCase, TestReport};
+use super::test_selection::TestRun;
+use super::version::Version;
 use super::{language::Language, test_framework::TestFramework};
-use crate::Result;
+use crate::{error, Error, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::{path::PathBuf, process::Command};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+/// Default build tree, relative to the project directory.
+pub const BUILD_DIR: &str = "build";
+
+/// Generated precompiled headers, for backends taking a single header per target (Meson).
+pub const PCH_DIR: &str = "pch";
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum BuildSystems {
@@ -52,214 +77,1758 @@ impl BuildSystem {
         }
     }
 
-    pub fn init(&self) -> Result<()> {
+    /// Writes the build files. `compiler` is only needed by generators that can't detect it
+    /// themselves (Make).
+    pub fn init(
+        &self,
+        project: &ProjectConfig,
+        targets: &Targets,
+        compiler: &str,
+        build: &BuildConfig,
+    ) -> Result<()> {
+        for (file, contents) in self.generate(project, targets, compiler, build)? {
+            self.write_file(&file, &contents)?;
+        }
+        Ok(())
+    }
+
+    /// Writes one of the files from `generate`, keeping the user's regions.
+    pub fn write_file(&self, file: &Path, contents: &str) -> Result<()> {
+        let path = self.directory.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        regen::write_generated(&path, contents)
+    }
+
+    /// The build files `init` writes with their contents, relative to the project directory.
+    pub fn generate(
+        &self,
+        project: &ProjectConfig,
+        targets: &Targets,
+        compiler: &str,
+        build: &BuildConfig,
+    ) -> Result<Vec<(PathBuf, String)>> {
         match self.variant {
-            BuildSystems::CMake => CMakeBuilder::init(
+            BuildSystems::CMake => CMakeBuilder::generate(
                 &self.name,
-                &self.directory,
+                project,
+                targets,
                 &self.language,
                 &self.test_framework,
+                build,
             ),
-            BuildSystems::Meson => MesonBuilder::init(),
-            BuildSystems::Make => MakeBuilder::init(),
+            BuildSystems::Meson => MesonBuilder::generate(
+                &self.name,
+                project,
+                targets,
+                &self.language,
+                &self.test_framework,
+                build,
+            ),
+            BuildSystems::Make => MakeBuilder::generate(
+                project,
+                targets,
+                &self.language,
+                &self.test_framework,
+                compiler,
+                build,
+            ),
+        }
+    }
+
+    /// Takes the name and language from `[project]`, the source of truth for generated files.
+    pub fn sync(&mut self, project: &ProjectConfig) {
+        self.name = project.name.clone();
+        self.language = project.language.clone();
+    }
+
+    /// Moves the project to `directory`.
+    pub fn relocate(&mut self, directory: &Path) {
+        self.directory = directory.to_path_buf();
+        self.test_framework.relocate(directory);
+    }
+
+    /// Generated build file, relative to the project directory.
+    pub fn build_file(&self) -> &'static str {
+        match self.variant {
+            BuildSystems::CMake => "CMakeLists.txt",
+            BuildSystems::Meson => "meson.build",
+            BuildSystems::Make => "Makefile",
+        }
+    }
+
+    /// Files written by `init`, relative to the project directory.
+    pub fn init_files(&self) -> Vec<PathBuf> {
+        let mut files = vec![PathBuf::from(self.build_file())];
+        if self.variant == BuildSystems::CMake {
+            files.push(PathBuf::from("cmake").join(format!("{}Config.cmake.in", self.name)));
+        }
+        files
+    }
+
+    /// Rewrites the version in the build file in place, keeping the rest of the file. Returns
+    /// false if the file has no version to update.
+    pub fn set_version(&self, version: &Version) -> Result<bool> {
+        let path = self.directory.join(self.build_file());
+        let contents = fs::read_to_string(&path)?;
+
+        let mut found = false;
+        let mut lines = vec![];
+        for line in contents.split('\n') {
+            let updated = match self.variant {
+                BuildSystems::CMake => CMakeBuilder::version_line(line, version),
+                BuildSystems::Meson => MesonBuilder::version_line(line, version),
+                BuildSystems::Make => MakeBuilder::version_line(line, version),
+            };
+            found |= updated.is_some();
+            lines.push(updated.unwrap_or_else(|| line.to_string()));
+        }
+
+        if found {
+            fs::write(&path, lines.join("\n"))?;
+        }
+        Ok(found)
+    }
+
+    /// Path of the project's binary in the tree at `build_dir`.
+    pub fn binary(&self, build_dir: &Path) -> PathBuf {
+        match self.variant {
+            BuildSystems::Meson => build_dir.join(&self.name),
+            BuildSystems::CMake | BuildSystems::Make => build_dir.join("bin").join(&self.name),
+        }
+    }
+
+    /// Where `target` is built in `build_dir`.
+    pub fn target_output(&self, build_dir: &Path, target: &Target) -> PathBuf {
+        if let Some(output) = &target.output {
+            return build_dir.join(output);
+        }
+        let file = match target.kind {
+            TargetKind::Lib => format!("lib{}.a", target.name),
+            _ => target.name.clone(),
+        };
+        match (&self.variant, target.kind) {
+            (BuildSystems::Meson, _) => build_dir.join(file),
+            (_, TargetKind::Bin) => build_dir.join("bin").join(file),
+            (_, TargetKind::Example) => build_dir.join("examples").join(file),
+            (_, TargetKind::Bench) => build_dir.join("benches").join(file),
+            _ => build_dir.join(file),
+        }
+    }
+
+    /// Files and directories holding the objects and output of `target` in `build_dir`.
+    pub fn target_artifacts(&self, build_dir: &Path, target: &Target) -> Vec<PathBuf> {
+        let output = self.target_output(build_dir, target);
+        let mut artifacts = match self.variant {
+            BuildSystems::CMake => vec![build_dir
+                .join("CMakeFiles")
+                .join(format!("{}.dir", target.name))],
+            BuildSystems::Meson => vec![output.with_file_name(format!(
+                "{}.p",
+                output.file_name().unwrap_or_default().to_string_lossy()
+            ))],
+            BuildSystems::Make => target
+                .sources
+                .iter()
+                .flat_map(|s| {
+                    let object = build_dir.join("obj").join(s);
+                    ["o", "d"].map(|ext| {
+                        let mut name = object.as_os_str().to_owned();
+                        name.push(format!(".{}", ext));
+                        PathBuf::from(name)
+                    })
+                })
+                .collect(),
+        };
+        artifacts.push(output);
+        artifacts
+    }
+
+    /// Runs the backend's clean target in `build_dir`, which removes what was built but keeps
+    /// the tree configured.
+    pub fn clean_in(&self, build_dir: &Path) -> Result<()> {
+        let dir = build_dir.to_string_lossy().into_owned();
+        let (program, args) = match self.variant {
+            BuildSystems::CMake => ("cmake", vec!["--build", &dir, "--target", "clean"]),
+            BuildSystems::Meson => ("meson", vec!["compile", "-C", &dir, "--clean"]),
+            BuildSystems::Make => ("make", vec!["clean"]),
+        };
+        let build_dir_arg = format!("BUILD_DIR={}", dir);
+
+        let mut command = Command::new(program);
+        command.args(args).current_dir(&self.directory);
+        if self.variant == BuildSystems::Make {
+            command.arg(build_dir_arg);
+        }
+        if !command.status()?.success() {
+            return Err(error!(
+                CustomError,
+                "{} clean failed in {}",
+                program,
+                build_dir.display()
+            ));
         }
+        Ok(())
     }
 
-    pub fn configure(&self, compile_commands: bool, flags: &Vec<String>) -> Result<()> {
+    /// Build arguments restricting a build of `build_dir` to `target` and its dependencies.
+    pub fn target_build_args(&self, build_dir: &Path, target: &Target) -> Vec<String> {
+        match self.variant {
+            BuildSystems::CMake => vec!["--target".into(), target.name.clone()],
+            BuildSystems::Meson => vec![target.name.clone()],
+            BuildSystems::Make => vec![self
+                .target_output(build_dir, target)
+                .to_string_lossy()
+                .into_owned()],
+        }
+    }
+
+    pub fn configure(&self, compile_commands: bool, flags: &[String]) -> Result<()> {
+        self.configure_in(Path::new(BUILD_DIR), compile_commands, flags)
+    }
+
+    /// Configures the build tree at `build_dir`, relative to the project directory.
+    pub fn configure_in(
+        &self,
+        build_dir: &Path,
+        compile_commands: bool,
+        flags: &[String],
+    ) -> Result<()> {
         match self.variant {
             BuildSystems::CMake => {
-                CMakeBuilder::configure(&self.directory, compile_commands, flags)
+                CMakeBuilder::configure(&self.directory, build_dir, compile_commands, flags)
+            }
+            BuildSystems::Meson => {
+                MesonBuilder::configure(&self.directory, build_dir, compile_commands, flags)
+            }
+            BuildSystems::Make => MakeBuilder::configure(&self.directory, build_dir),
+        }
+    }
+
+    /// Directory of the `.ninja_log` recording compile times of the tree at `build_dir`, or
+    /// `None` when the compiler is timed through its launcher instead. That's Make, CMake without
+    /// the Ninja generator, and CMake with `trace`, so the launcher can add `-ftime-trace`.
+    pub fn ninja_log_dir(&self, build_dir: &Path, trace: bool) -> Option<PathBuf> {
+        let dir = self.directory.join(build_dir);
+        match self.variant {
+            BuildSystems::Meson => Some(dir),
+            BuildSystems::CMake if !trace && dir.join("build.ninja").exists() => Some(dir),
+            BuildSystems::CMake | BuildSystems::Make => None,
+        }
+    }
+
+    /// Build arguments running the compiler through `launcher`, for backends timed that way.
+    pub fn timing_args(&self, launcher: &[String]) -> Result<Vec<String>> {
+        match self.variant {
+            BuildSystems::Make => {
+                let launcher = shlex::try_join(launcher.iter().map(String::as_str))
+                    .map_err(|e| error!(CustomError, "Can't quote the timing launcher: {}", e))?;
+                Ok(vec![format!("LAUNCHER={}", launcher)])
+            }
+            BuildSystems::CMake | BuildSystems::Meson => Ok(vec![]),
+        }
+    }
+
+    /// Configure arguments for a timed build of the tree at `build_dir`, configured with
+    /// `flags`, or `None` when the tree can be timed as it is. CMake picks up `launcher` and
+    /// Meson clang's `-ftime-trace` only when configuring, and switching a tree to them and back
+    /// would rebuild it twice, so those builds get a tree of their own configured this way.
+    pub fn timing_configure_args(
+        &self,
+        build_dir: &Path,
+        flags: &[String],
+        launcher: &[String],
+        trace: bool,
+    ) -> Option<Vec<String>> {
+        let mut args = flags.to_vec();
+        match self.variant {
+            BuildSystems::CMake if self.ninja_log_dir(build_dir, trace).is_none() => {
+                args.push(format!(
+                    "-DCMAKE_{}_COMPILER_LAUNCHER={}",
+                    self.language.cmake_identifier(),
+                    launcher.join(";")
+                ));
+                Some(args)
             }
-            BuildSystems::Meson => MesonBuilder::configure(),
-            BuildSystems::Make => MakeBuilder::configure(),
+            BuildSystems::Meson if trace => {
+                // Sanitized and instrumented trees already set the option, see `toolchain_args`.
+                let option = format!("-D{}_args=[", self.language.meson_identifier());
+                match args.iter_mut().find(|a| a.starts_with(&option)) {
+                    Some(arg) => arg.insert_str(option.len(), "'-ftime-trace',"),
+                    None => args.push(format!("{}'-ftime-trace']", option)),
+                }
+                Some(args)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn has_configure_step(&self) -> bool {
+        self.variant != BuildSystems::Make
+    }
+
+    /// Whether configuring writes a `compile_commands.json` for the checker and linter.
+    pub fn has_compile_db(&self) -> bool {
+        self.variant != BuildSystems::Make
+    }
+
+    /// Whether the tree at `build_dir` has already been configured.
+    pub fn is_configured(&self, build_dir: &Path) -> bool {
+        let dir = self.directory.join(build_dir);
+        match self.variant {
+            BuildSystems::CMake => dir.join("CMakeCache.txt").exists(),
+            BuildSystems::Meson => dir.join("build.ninja").exists(),
+            BuildSystems::Make => true,
         }
     }
 
     pub fn build(&self, flags: Option<&Vec<String>>) -> Result<()> {
+        self.build_in(Path::new(BUILD_DIR), flags)
+    }
+
+    pub fn build_in(&self, build_dir: &Path, flags: Option<&Vec<String>>) -> Result<()> {
+        let output = self.compile(build_dir, flags, MessageFormat::Human)?;
+        if !output.success {
+            eprintln!("Compilation failed.");
+        }
+        Ok(())
+    }
+
+    /// Builds the tree at `build_dir` and collects the compiler diagnostics. The build output
+    /// is passed through for `Human`, and sent to stderr for `Json` so stdout only carries
+    /// forge's messages.
+    pub fn compile(
+        &self,
+        build_dir: &Path,
+        flags: Option<&Vec<String>>,
+        format: MessageFormat,
+    ) -> Result<BuildOutput> {
+        let mut command = match self.variant {
+            BuildSystems::CMake => CMakeBuilder::build_command(&self.directory, build_dir, flags),
+            BuildSystems::Meson => MesonBuilder::build_command(&self.directory, build_dir, flags),
+            BuildSystems::Make => MakeBuilder::build_command(&self.directory, build_dir, flags),
+        };
+        let (success, output) = run_captured(&mut command, format)?;
+
+        // Ninja runs the compiler in the build tree, Make in the project directory, and CMake's
+        // generators pass absolute paths.
+        let cwd = match self.variant {
+            BuildSystems::Meson => self.directory.join(build_dir),
+            BuildSystems::CMake | BuildSystems::Make => self.directory.clone(),
+        };
+        let mut diagnostics = Diagnostic::parse_build_output(&output);
+        for d in &mut diagnostics {
+            d.file = normalize(&cwd.join(&d.file));
+        }
+
+        Ok(BuildOutput {
+            success,
+            diagnostics,
+        })
+    }
+
+    /// Build arguments making the compiler report diagnostics as JSON, where the backend can
+    /// change compiler flags per build and the compiler supports it.
+    pub fn diagnostics_args(&self, family: CompilerFamily, format: MessageFormat) -> Vec<String> {
+        match (&self.variant, family.json_diagnostics_flag(), format) {
+            (BuildSystems::Make, Some(flag), MessageFormat::Json) => {
+                vec![format!("DIAGNOSTICS={}", flag)]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Configure arguments selecting the build type of `profile`. Empty for the default debug
+    /// tree, which is configured with `forge config`.
+    pub fn profile_args(&self, profile: Profile) -> Vec<String> {
+        match (profile, &self.variant) {
+            (Profile::Debug, _) => vec![],
+            (Profile::Release, BuildSystems::CMake) => vec!["-DCMAKE_BUILD_TYPE=Release".into()],
+            (Profile::Release, BuildSystems::Meson) => vec!["--buildtype=release".into()],
+            (Profile::Release, BuildSystems::Make) => vec![],
+        }
+    }
+
+    /// Configure arguments running the compiler through `launcher`, or clearing a previous one.
+    /// Meson finds ccache and sccache by itself, and the Makefile is generated with it.
+    pub fn launcher_args(&self, launcher: Option<&Path>) -> Vec<String> {
+        match self.variant {
+            BuildSystems::CMake => vec![format!(
+                "-DCMAKE_{}_COMPILER_LAUNCHER={}",
+                self.language.cmake_identifier(),
+                launcher
+                    .map(|l| l.display().to_string())
+                    .unwrap_or_default()
+            )],
+            BuildSystems::Meson | BuildSystems::Make => vec![],
+        }
+    }
+
+    /// Build arguments for `profile`, for backends without a configure step.
+    pub fn profile_build_args(&self, profile: Profile) -> Vec<String> {
+        match (profile, &self.variant) {
+            (Profile::Release, BuildSystems::Make) => {
+                vec!["OPT=-O2 -DNDEBUG".into(), "PROFILE=release".into()]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Installs the already built tree at `build_dir`.
+    pub fn install(
+        &self,
+        build_dir: &Path,
+        prefix: Option<&Path>,
+        build_flags: &[String],
+    ) -> Result<()> {
+        match self.variant {
+            BuildSystems::CMake => CMakeBuilder::install(&self.directory, build_dir, prefix),
+            BuildSystems::Meson => MesonBuilder::install(&self.directory, build_dir, prefix),
+            BuildSystems::Make => {
+                MakeBuilder::install(&self.directory, build_dir, prefix, build_flags)
+            }
+        }
+    }
+
+    /// Packages the already built tree at `build_dir` into `PACKAGE_DIR` and returns the
+    /// artifacts. CMake uses CPack; the other backends install into a staging prefix and only
+    /// support tarballs.
+    pub fn package(
+        &self,
+        project: &ProjectConfig,
+        build_dir: &Path,
+        formats: &[PackageFormat],
+        build_flags: &[String],
+    ) -> Result<Vec<PathBuf>> {
+        let out_dir = self.directory.join(PACKAGE_DIR);
+        if out_dir.exists() {
+            fs::remove_dir_all(&out_dir)?;
+        }
+        fs::create_dir_all(&out_dir)?;
+
+        match self.variant {
+            BuildSystems::CMake => {
+                CMakeBuilder::package(&self.directory, build_dir, formats)?;
+                package_artifacts(&out_dir)
+            }
+            _ => {
+                if let Some(f) = formats.iter().find(|f| **f != PackageFormat::Tgz) {
+                    return Err(error!(
+                        CustomError,
+                        "{} packages need CPack, only tgz is supported for {:?}", f, self.variant
+                    ));
+                }
+                let stem = archive_stem(project);
+                // Meson only accepts an absolute prefix.
+                let stage = out_dir.canonicalize()?.join("stage");
+                self.install(build_dir, Some(&stage.join(&stem)), build_flags)?;
+                Ok(vec![archive(&stage, &stem, &out_dir)?])
+            }
+        }
+    }
+
+    /// Configure arguments selecting `compiler` and adding extra compile and link flags.
+    pub fn toolchain_args(
+        &self,
+        compiler: &str,
+        compile_flags: &[String],
+        link_flags: &[String],
+    ) -> Vec<String> {
         match self.variant {
-            BuildSystems::CMake => CMakeBuilder::build(&self.directory, flags),
-            BuildSystems::Meson => MesonBuilder::build(),
-            BuildSystems::Make => MakeBuilder::build(),
+            BuildSystems::CMake => {
+                let lang = self.language.cmake_identifier();
+                vec![
+                    format!("-DCMAKE_{}_COMPILER={}", lang, compiler),
+                    format!("-DCMAKE_{}_FLAGS={}", lang, compile_flags.join(" ")),
+                    format!("-DCMAKE_EXE_LINKER_FLAGS={}", link_flags.join(" ")),
+                    format!("-DCMAKE_SHARED_LINKER_FLAGS={}", link_flags.join(" ")),
+                ]
+            }
+            BuildSystems::Meson => {
+                let lang = self.language.meson_identifier();
+                let list = |flags: &[String]| {
+                    let quoted: Vec<String> = flags.iter().map(|f| format!("'{}'", f)).collect();
+                    format!("[{}]", quoted.join(","))
+                };
+                vec![
+                    format!("-D{}_args={}", lang, list(compile_flags)),
+                    format!("-D{}_link_args={}", lang, list(link_flags)),
+                ]
+            }
+            BuildSystems::Make => vec![],
         }
     }
+
+    /// Build arguments adding extra compile and link flags, for backends without a configure
+    /// step. The Makefile leaves `CFLAGS`/`CXXFLAGS` and `LDFLAGS` to the caller.
+    pub fn toolchain_build_args(
+        &self,
+        compile_flags: &[String],
+        link_flags: &[String],
+    ) -> Vec<String> {
+        match self.variant {
+            BuildSystems::Make => {
+                let cflags = match self.language {
+                    Language::C(_) => "CFLAGS",
+                    Language::Cpp(_) => "CXXFLAGS",
+                };
+                vec![
+                    format!("{}={}", cflags, compile_flags.join(" ")),
+                    format!("LDFLAGS={}", link_flags.join(" ")),
+                ]
+            }
+            BuildSystems::CMake | BuildSystems::Meson => vec![],
+        }
+    }
+
+    /// Runs the test suite with the backend's test runner, writing JUnit XML results to
+    /// `run.junit`. Returns whether all selected tests passed.
+    pub fn test(&self, run: &TestRun, targets: &Targets) -> Result<bool> {
+        let junit = &run.junit;
+        let selection = &run.selection;
+
+        // Previous results are only needed by backends that can't rerun failures themselves.
+        let previous = match junit.exists() && selection.rerun_failed {
+            true => Some(TestReport::from_junit_file(junit)?),
+            false => None,
+        };
+        let failed = match &previous {
+            Some(report) => report.failed(),
+            None => vec![],
+        };
+
+        if previous.is_some() && failed.is_empty() && self.variant != BuildSystems::CMake {
+            println!("No failed tests to rerun.");
+            return Ok(true);
+        }
+
+        if junit.exists() {
+            fs::remove_file(junit)?;
+        }
+        if let Some(parent) = junit.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        match self.variant {
+            BuildSystems::CMake => CMakeBuilder::test(&self.directory, run),
+            BuildSystems::Meson => MesonBuilder::test(&self.directory, run, &failed),
+            BuildSystems::Make => MakeBuilder::test(
+                &self.directory,
+                &targets.tests,
+                &self.test_framework,
+                run,
+                &failed,
+            ),
+        }
+    }
+}
+
+/// Utility function to be used by default to system link the compile_commands.json to project
+/// root
+fn link_command_compiler(path: &Path, build_dir: &Path) -> Result<()> {
+    let compile_commands = build_dir.join("compile_commands.json");
+    let ln_status = Command::new("ln")
+        .arg("-sf")
+        .arg(compile_commands)
+        .arg("compile_commands.json")
+        .current_dir(path)
+        .status()?;
+    if !ln_status.success() {
+        eprintln!("Failed to create symlink for the compile_commands.json");
+    }
+    Ok(())
+}
+
+/// Result of a build, with the diagnostics the compiler printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildOutput {
+    pub success: bool,
+    /// Absolute paths, in the order they were printed.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Runs `command` and returns whether it succeeded together with its combined output. Lines
+/// are echoed as they arrive, JSON diagnostics excepted.
+fn run_captured(command: &mut Command, format: MessageFormat) -> Result<(bool, String)> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (sender, receiver) = mpsc::channel();
+    let readers = [
+        stdout.map(|s| spawn_reader(s, false, sender.clone())),
+        stderr.map(|s| spawn_reader(s, true, sender)),
+    ];
+
+    let mut output = String::new();
+    for (is_stderr, line) in receiver {
+        let json = diagnostics::is_json_line(&line);
+        match (format, is_stderr) {
+            (MessageFormat::Human, _) if json => (),
+            (MessageFormat::Human, false) => println!("{}", line),
+            (MessageFormat::Human, true) | (MessageFormat::Json, _) if !json => {
+                eprintln!("{}", line)
+            }
+            _ => (),
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+
+    Ok((child.wait()?.success(), output))
+}
+
+fn spawn_reader<R: Read + Send + 'static>(
+    stream: R,
+    is_stderr: bool,
+    sender: mpsc::Sender<(bool, String)>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines().map_while(|l| l.ok()) {
+            if sender.send((is_stderr, line)).is_err() {
+                break;
+            }
+        }
+    })
 }
 
 pub struct CMakeBuilder {}
 
 impl CMakeBuilder {
-    fn init(
+    fn generate(
         name: &String,
-        path: &PathBuf,
+        project: &ProjectConfig,
+        targets: &Targets,
         language: &Language,
         test_framework: &TestFramework,
-    ) -> Result<()> {
+        build: &BuildConfig,
+    ) -> Result<Vec<(PathBuf, String)>> {
         let lang = language.cmake_identifier();
         let standard = language.version();
-        let src_suffix = language.src_suffix();
         let test_pkg = test_framework.as_str();
         let test_targets = test_framework.cmake_target();
 
-        let mut contents = vec![];
+        let settings = regen::region("settings");
+        let user_targets = regen::region("targets");
+        let mut contents = vec![regen::HEADER];
 
         contents.push("# General");
-        contents.push("cmake_minimum_required(VERSION 3.14)");
-        let project = format!("project({} VERSION 1.0 LANGUAGES {})\n", name, lang);
-        contents.push(&project);
+        // Precompiled headers and unity builds need CMake 3.16.
+        match build.pch.is_empty() && !build.unity {
+            true => contents.push("cmake_minimum_required(VERSION 3.14)"),
+            false => contents.push("cmake_minimum_required(VERSION 3.16)"),
+        }
+        let mut metadata = String::new();
+        if !project.description.is_empty() {
+            metadata += &format!(" DESCRIPTION {}", cmake_string(&project.description));
+        }
+        if !project.homepage.is_empty() {
+            metadata += &format!(" HOMEPAGE_URL {}", cmake_string(&project.homepage));
+        }
+        let project_cmd = format!(
+            "project({} VERSION {}{} LANGUAGES {})\n",
+            name, project.version, metadata, lang
+        );
+        contents.push(&project_cmd);
         let set = format!("set(CMAKE_{}_STANDARD {})", lang, standard);
         contents.push(&set);
-        let required = format!("set(CMAKE_{}_STANDARD_REQUIRED ON)\n", lang);
+        let required = format!("set(CMAKE_{}_STANDARD_REQUIRED ON)", lang);
         contents.push(&required);
+        contents.push("include(GNUInstallDirs)");
+        contents.push("include(CMakePackageConfigHelpers)\n");
+        contents.push(&settings);
+
+        contents.push("# Warnings, applied to the project's own targets only");
+        let warnings = build.warnings.cmake_variable(language);
+        contents.push(&warnings);
+        let profiles = build.profiles.cmake_rules();
+        if !build.profiles.is_default() {
+            contents.push(&profiles);
+        }
 
-        contents.push("# Library");
-        let lib = format!("add_library({}Lib src/lib.{})", name, src_suffix);
-        let dir = format!(
-            "target_include_directories({}Lib PUBLIC include/ libs/)\n",
-            name
-        );
-        contents.push(&lib);
-        contents.push(&dir);
-
-        contents.push("# Binary");
-        let exec = format!("add_executable({} src/main.{})", name, src_suffix);
-        let exc_link = format!("target_link_libraries({} PRIVATE {}Lib)\n", name, name);
-        contents.push(&exec);
-        contents.push(&exc_link);
-
-        contents.push("# Testing");
-        contents.push("enable_testing()");
-        let find = format!("find_package({} REQUIRED)\n", test_pkg);
-        contents.push(&find);
-        let test_exec = format!(
-            "add_executable({}Tests test/test_lib.{} test/test_main.{} )",
-            name, src_suffix, src_suffix
-        );
-        contents.push(&test_exec);
-        let link_test = format!(
-            "target_link_libraries({}Tests PRIVATE
-                {}
-                {}Lib
-            )",
-            name, test_targets, name
+        let mut rules = vec![];
+        if let Some(lib) = &targets.lib {
+            rules.push(CMakeBuilder::target_rules(lib, test_targets));
+        }
+        for target in targets.bins.iter().chain(&targets.examples) {
+            rules.push(CMakeBuilder::target_rules(target, test_targets));
+        }
+        // CTest, rather than just enable_testing(), writes the DartConfiguration.tcl that
+        // `ctest -T memcheck` needs for `forge test --memcheck`.
+        let testing = match targets.tests.is_empty() {
+            true => "# Testing\ninclude(CTest)\n".to_string(),
+            false => format!(
+                "# Testing\ninclude(CTest)\nfind_package({} REQUIRED)\n",
+                test_pkg
+            ),
+        };
+        rules.push(testing);
+        for test in &targets.tests {
+            rules.push(CMakeBuilder::target_rules(test, test_targets));
+        }
+        let bench = BenchFramework::for_language(language);
+        if !targets.benches.is_empty() {
+            let mut benchmarks = "# Benchmarks, only built by `forge bench`\n".to_string();
+            if let Some(package) = bench.cmake_package() {
+                benchmarks += &format!("find_package({} REQUIRED)\n", package);
+            }
+            rules.push(benchmarks);
+        }
+        for target in &targets.benches {
+            rules.push(CMakeBuilder::target_rules(target, bench.cmake_target()));
+        }
+        let speedups = CMakeBuilder::speedup_rules(targets, build);
+        rules.push(speedups);
+        for rule in rules.iter().filter(|r| !r.is_empty()) {
+            contents.push(rule);
+        }
+
+        let mut installed: Vec<&str> = targets.lib.iter().map(|l| l.name.as_str()).collect();
+        installed.extend(targets.bins.iter().map(|b| b.name.as_str()));
+        let install = CMakeBuilder::install_rules(name, &installed);
+        contents.push(&install);
+        let cpack = cpack_rules(project);
+        contents.push(&cpack);
+        contents.push(&user_targets);
+
+        Ok(vec![
+            (
+                PathBuf::from("cmake").join(format!("{}Config.cmake.in", name)),
+                CMakeBuilder::package_config(name),
+            ),
+            (PathBuf::from("CMakeLists.txt"), contents.join("\n")),
+        ])
+    }
+
+    /// Precompiled headers and unity builds for every project target, empty when neither is
+    /// enabled.
+    fn speedup_rules(targets: &Targets, build: &BuildConfig) -> String {
+        if build.pch.is_empty() && !build.unity {
+            return String::new();
+        }
+
+        let mut contents = vec!["# Precompiled headers and unity builds".to_string()];
+        for target in targets.all() {
+            if !build.pch.is_empty() {
+                contents.push(format!(
+                    "target_precompile_headers({} PRIVATE {})",
+                    target.name,
+                    build.pch.join(" ")
+                ));
+            }
+            if build.unity {
+                contents.push(format!(
+                    "set_target_properties({} PROPERTIES UNITY_BUILD ON)",
+                    target.name
+                ));
+            }
+        }
+        contents.push(String::new());
+        contents.join("\n")
+    }
+
+    /// `project(<name> VERSION x.y.z ...)` with the version replaced.
+    fn version_line(line: &str, version: &Version) -> Option<String> {
+        if !line.trim_start().starts_with("project(") {
+            return None;
+        }
+        let start = line.find(" VERSION ")? + " VERSION ".len();
+        let end = line[start..]
+            .find(|c: char| c.is_whitespace() || c == ')')
+            .map_or(line.len(), |i| start + i);
+        Some(format!("{}{}{}", &line[..start], version, &line[end..]))
+    }
+
+    /// Rules for one target. The library's include dirs and definitions are public, so they
+    /// reach the targets linking it and the installed package. Tests and benchmarks also link
+    /// `framework_targets`.
+    fn target_rules(target: &Target, framework_targets: &str) -> String {
+        let sources: Vec<String> = target
+            .sources
+            .iter()
+            .map(|s| format!("    {}", s.display()))
+            .collect();
+        let (add, scope) = match target.kind {
+            TargetKind::Lib => ("add_library", "PUBLIC"),
+            _ => ("add_executable", "PRIVATE"),
+        };
+        let heading = match target.kind {
+            TargetKind::Lib => "# Library".to_string(),
+            TargetKind::Bin => format!("# Binary {}", target.name),
+            TargetKind::Test => format!("# Test {}", target.name),
+            TargetKind::Example => format!("# Example {}", target.name),
+            TargetKind::Bench => format!("# Benchmark {}", target.name),
+        };
+        let exclude = match target.kind {
+            TargetKind::Bench => " EXCLUDE_FROM_ALL",
+            _ => "",
+        };
+
+        let mut contents = vec![heading];
+        contents.push(format!(
+            "{}({}{}\n{}\n)",
+            add,
+            target.name,
+            exclude,
+            sources.join("\n")
+        ));
+
+        let include_dirs: Vec<String> = match target.kind {
+            TargetKind::Lib => {
+                let mut dirs: Vec<String> = target
+                    .include_dirs
+                    .iter()
+                    .map(|d| format!("    $<BUILD_INTERFACE:${{CMAKE_CURRENT_SOURCE_DIR}}/{}>", d))
+                    .collect();
+                dirs.push("    $<INSTALL_INTERFACE:${CMAKE_INSTALL_INCLUDEDIR}>".to_string());
+                dirs
+            }
+            _ => target
+                .include_dirs
+                .iter()
+                .map(|d| format!("    {}", d))
+                .collect(),
+        };
+        if !include_dirs.is_empty() {
+            contents.push(format!(
+                "target_include_directories({} {}\n{}\n)",
+                target.name,
+                scope,
+                include_dirs.join("\n")
+            ));
+        }
+        if !target.defines.is_empty() {
+            contents.push(format!(
+                "target_compile_definitions({} {} {})",
+                target.name,
+                scope,
+                target.defines.join(" ")
+            ));
+        }
+
+        let mut links: Vec<&str> = vec![];
+        if matches!(target.kind, TargetKind::Test | TargetKind::Bench)
+            && !framework_targets.is_empty()
+        {
+            links.push(framework_targets);
+        }
+        links.extend(target.link_targets.iter().map(String::as_str));
+        links.extend(target.link_libs.iter().map(String::as_str));
+        if !links.is_empty() {
+            contents.push(format!(
+                "target_link_libraries({} {} {})",
+                target.name,
+                scope,
+                links.join(" ")
+            ));
+        }
+        contents.push(format!(
+            "target_compile_options({} PRIVATE ${{FORGE_WARNINGS}})",
+            target.name
+        ));
+
+        match target.kind {
+            TargetKind::Bin => contents.push(format!(
+                "set_target_properties({} PROPERTIES RUNTIME_OUTPUT_DIRECTORY \"${{CMAKE_BINARY_DIR}}/bin\")",
+                target.name
+            )),
+            TargetKind::Example => contents.push(format!(
+                "set_target_properties({} PROPERTIES RUNTIME_OUTPUT_DIRECTORY \"${{CMAKE_BINARY_DIR}}/examples\")",
+                target.name
+            )),
+            TargetKind::Bench => contents.push(format!(
+                "set_target_properties({} PROPERTIES RUNTIME_OUTPUT_DIRECTORY \"${{CMAKE_BINARY_DIR}}/benches\")",
+                target.name
+            )),
+            TargetKind::Test => contents.push(format!(
+                "add_test(NAME {} COMMAND {})",
+                target.name, target.name
+            )),
+            TargetKind::Lib => (),
+        }
+
+        contents.push(String::new());
+        contents.join("\n")
+    }
+
+    /// Install and export rules so other projects can `find_package(<name>)` the library.
+    fn install_rules(name: &str, installed: &[&str]) -> String {
+        if installed.is_empty() {
+            return String::new();
+        }
+        let dest = format!("${{CMAKE_INSTALL_LIBDIR}}/cmake/{}", name);
+        let mut contents = vec![];
+
+        contents.push("# Install".to_string());
+        contents.push(format!("install(TARGETS {}", installed.join(" ")));
+        contents.push(format!("    EXPORT {}Targets", name));
+        contents.push("    ARCHIVE DESTINATION ${CMAKE_INSTALL_LIBDIR}".to_string());
+        contents.push("    LIBRARY DESTINATION ${CMAKE_INSTALL_LIBDIR}".to_string());
+        contents.push("    RUNTIME DESTINATION ${CMAKE_INSTALL_BINDIR}".to_string());
+        contents.push("    INCLUDES DESTINATION ${CMAKE_INSTALL_INCLUDEDIR}".to_string());
+        contents.push(")".to_string());
+        contents.push(
+            "install(DIRECTORY include/ DESTINATION ${CMAKE_INSTALL_INCLUDEDIR})".to_string(),
         );
-        contents.push(&link_test);
-        let test_dir = format!(
-            "target_include_directories({}Tests PRIVATE 
-                include/ 
-                src/
-            )",
+        contents.push(format!("install(EXPORT {}Targets", name));
+        contents.push(format!("    FILE {}Targets.cmake", name));
+        contents.push(format!("    NAMESPACE {}::", name));
+        contents.push(format!("    DESTINATION {}", dest));
+        contents.push(")".to_string());
+        contents.push(format!(
+            "configure_package_config_file(cmake/{}Config.cmake.in",
             name
-        );
-        contents.push(&test_dir);
-        let add_test = format!("add_test(NAME {}Tests COMMAND {}Tests)\n", name, name);
-        contents.push(&add_test);
+        ));
+        contents.push(format!(
+            "    ${{CMAKE_CURRENT_BINARY_DIR}}/{}Config.cmake",
+            name
+        ));
+        contents.push(format!("    INSTALL_DESTINATION {}", dest));
+        contents.push(")".to_string());
+        contents.push("write_basic_package_version_file(".to_string());
+        contents.push(format!(
+            "    ${{CMAKE_CURRENT_BINARY_DIR}}/{}ConfigVersion.cmake",
+            name
+        ));
+        contents.push("    VERSION ${PROJECT_VERSION}".to_string());
+        contents.push("    COMPATIBILITY SameMajorVersion".to_string());
+        contents.push(")".to_string());
+        contents.push("install(FILES".to_string());
+        contents.push(format!(
+            "    ${{CMAKE_CURRENT_BINARY_DIR}}/{}Config.cmake",
+            name
+        ));
+        contents.push(format!(
+            "    ${{CMAKE_CURRENT_BINARY_DIR}}/{}ConfigVersion.cmake",
+            name
+        ));
+        contents.push(format!("    DESTINATION {}", dest));
+        contents.push(")\n".to_string());
 
-        contents.push("# Compiled output file");
-        let target = format!("set_target_properties({} PROPERTIES", name);
-        contents.push(&target);
-        contents.push("    RUNTIME_OUTPUT_DIRECTORY \"${CMAKE_BINARY_DIR}/bin\"");
-        contents.push(")");
+        contents.join("\n")
+    }
 
-        let path = path.join("CMakeLists.txt");
-        match fs::write(path, contents.join("\n")) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+    fn package_config(name: &str) -> String {
+        let mut contents = vec![];
+        contents.push("@PACKAGE_INIT@\n".to_string());
+        contents.push(format!(
+            "include(\"${{CMAKE_CURRENT_LIST_DIR}}/{}Targets.cmake\")",
+            name
+        ));
+        contents.push(format!("check_required_components({})\n", name));
+        contents.join("\n")
     }
 
-    fn configure(path: &PathBuf, compile_cmds: bool, flags: &Vec<String>) -> Result<()> {
-        let mut args: Vec<String> = vec!["-S".into(), ".".into(), "-B".into(), "build".into()];
+    fn configure(
+        path: &PathBuf,
+        build_dir: &Path,
+        compile_cmds: bool,
+        flags: &[String],
+    ) -> Result<()> {
+        let mut args: Vec<String> = vec!["-S".into(), ".".into(), "-B".into()];
+        args.push(build_dir.to_string_lossy().into_owned());
 
         if compile_cmds {
             args.push("-DCMAKE_EXPORT_COMPILE_COMMANDS=ON".into());
-            args.append(&mut flags.clone());
+            args.extend_from_slice(flags);
+        } else {
+            args.extend_from_slice(flags);
+        }
+
+        let config_cmd = Command::new("cmake")
+            .args(args)
+            .current_dir(path)
+            .status()?;
+
+        if config_cmd.success() && compile_cmds {
+            link_command_compiler(path, build_dir)?;
+        }
+
+        Ok(())
+    }
+
+    fn build_command(path: &PathBuf, build_dir: &Path, flags: Option<&Vec<String>>) -> Command {
+        let mut args = vec!["--build".to_string()];
+        args.push(build_dir.to_string_lossy().into_owned());
+
+        if let Some(f) = flags {
+            args.append(&mut f.clone());
+        }
+
+        let mut command = Command::new("cmake");
+        command.args(args).current_dir(path);
+        command
+    }
+
+    fn install(path: &Path, build_dir: &Path, prefix: Option<&Path>) -> Result<()> {
+        let mut args = vec!["--install".to_string()];
+        args.push(build_dir.to_string_lossy().into_owned());
+        if let Some(p) = prefix {
+            args.extend(["--prefix".to_string(), p.to_string_lossy().into_owned()]);
+        }
+
+        let install_status = Command::new("cmake")
+            .args(args)
+            .current_dir(path)
+            .status()?;
+
+        if !install_status.success() {
+            return Err(error!(CustomError, "Install failed"));
+        }
+        Ok(())
+    }
+
+    fn package(path: &Path, build_dir: &Path, formats: &[PackageFormat]) -> Result<()> {
+        let generators: Vec<&str> = formats.iter().map(|f| f.cpack_generator()).collect();
+
+        let package_status = Command::new("cpack")
+            .arg("--config")
+            .arg(build_dir.join("CPackConfig.cmake"))
+            .arg("-G")
+            .arg(generators.join(";"))
+            .arg("-B")
+            .arg(PACKAGE_DIR)
+            .current_dir(path)
+            .status()?;
+
+        if !package_status.success() {
+            return Err(error!(CustomError, "Packaging failed"));
+        }
+        Ok(())
+    }
+
+    fn test(path: &PathBuf, run: &TestRun) -> Result<bool> {
+        let mut args: Vec<String> = vec!["--test-dir".into()];
+        args.push(run.build_dir.to_string_lossy().into_owned());
+        args.push("--output-junit".into());
+        args.push(run.junit.to_string_lossy().into_owned());
+        args.extend(run.selection.ctest_args());
+        if !run.wrapper.is_empty() {
+            let (command, options) = split_wrapper(&run.wrapper);
+            args.extend(["-T".into(), "memcheck".into()]);
+            args.extend(["--overwrite".into(), "MemoryCheckType=Valgrind".into()]);
+            args.extend([
+                "--overwrite".into(),
+                format!("MemoryCheckCommand={}", command),
+            ]);
+            args.extend([
+                "--overwrite".into(),
+                format!("MemoryCheckCommandOptions={}", options),
+            ]);
+        }
+        args.extend(run.flags.clone());
+
+        let test_status = Command::new("ctest")
+            .args(args)
+            .envs(run.env.iter().cloned())
+            .current_dir(path)
+            .status()?;
+
+        Ok(test_status.success())
+    }
+}
+
+/// Header including each `[build] pch` entry, written to `PCH_DIR`.
+fn pch_header(pch: &[String]) -> String {
+    let mut contents = vec!["// Generated by forge from `pch` in Forge.toml.".to_string()];
+    for header in pch {
+        match header.starts_with('<') {
+            true => contents.push(format!("#include {}", header)),
+            false => contents.push(format!("#include \"../{}\"", header)),
+        }
+    }
+    contents.push(String::new());
+    contents.join("\n")
+}
+
+/// `['a', 'b']`
+fn meson_list(items: &[String]) -> String {
+    let quoted: Vec<String> = items.iter().map(|i| format!("'{}'", i)).collect();
+    format!("[{}]", quoted.join(", "))
+}
+
+pub struct MesonBuilder {}
+
+impl MesonBuilder {
+    fn generate(
+        name: &String,
+        project: &ProjectConfig,
+        targets: &Targets,
+        language: &Language,
+        test_framework: &TestFramework,
+        build: &BuildConfig,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let mut files = vec![];
+        let lang = language.meson_identifier();
+        let std = match language {
+            Language::C(s) => format!("c{}", s.version()),
+            Language::Cpp(s) => format!("c++{}", s.version()),
+        };
+
+        let mut contents = vec![regen::HEADER.to_string()];
+
+        contents.push("# General".to_string());
+        contents.push(format!("project('{}', '{}',", name, lang));
+        contents.push(format!("  version: '{}',", project.version));
+        if !project.license.is_empty() {
+            contents.push(format!("  license: '{}',", project.license));
+        }
+        let mut options = vec![format!("{}_std={}", lang, std)];
+        if build.unity {
+            options.push("unity=on".to_string());
+        }
+        contents.push(format!("  default_options: {})\n", meson_list(&options)));
+        contents.push(regen::region("settings"));
+
+        contents.push("# Warnings, applied to the project's own targets only".to_string());
+        contents.push(format!(
+            "warnings = {}\n",
+            meson_list(&build.warnings.meson_options())
+        ));
+        if !build.profiles.is_default() {
+            contents.push(build.profiles.meson_rules(lang));
+        }
+
+        // Meson takes a single header per target, so the entries are wrapped in one.
+        let pch_file = match build.pch.is_empty() {
+            true => None,
+            false => {
+                let file = PathBuf::from(PCH_DIR).join(format!(
+                    "{}_pch.{}",
+                    name,
+                    language.header_suffix()
+                ));
+                let name = file.to_string_lossy().into_owned();
+                files.push((file, pch_header(&build.pch)));
+                Some(name)
+            }
+        };
+        let pch = pch_file.as_deref();
+
+        if let Some(lib) = &targets.lib {
+            contents.push(MesonBuilder::target_rules(lib, lang, pch));
+            contents.push(
+                "install_subdir('include', install_dir: get_option('includedir'), strip_directory: true)\n"
+                    .to_string(),
+            );
+        }
+        for target in targets.bins.iter().chain(&targets.examples) {
+            contents.push(MesonBuilder::target_rules(target, lang, pch));
+        }
+        if !targets.tests.is_empty() {
+            contents.push("# Testing".to_string());
+            contents.push(format!(
+                "test_dep = {}\n",
+                test_framework.meson_dependency()
+            ));
+        }
+        for test in &targets.tests {
+            contents.push(MesonBuilder::target_rules(test, lang, pch));
+        }
+        if !targets.benches.is_empty() {
+            contents.push("# Benchmarks, only built by `forge bench`".to_string());
+            contents.push(format!(
+                "bench_dep = {}\n",
+                BenchFramework::for_language(language).meson_dependency()
+            ));
+        }
+        for target in &targets.benches {
+            contents.push(MesonBuilder::target_rules(target, lang, pch));
+        }
+
+        contents.push(regen::region("targets"));
+
+        files.push((PathBuf::from("meson.build"), contents.join("\n")));
+        Ok(files)
+    }
+
+    fn configure(
+        path: &Path,
+        build_dir: &Path,
+        compile_cmds: bool,
+        flags: &[String],
+    ) -> Result<()> {
+        let mut args: Vec<String> = vec!["setup".into()];
+        if path.join(build_dir).join("build.ninja").exists() {
+            args.push("--reconfigure".into());
+        }
+        args.push(build_dir.to_string_lossy().into_owned());
+        args.extend(flags.iter().filter(|f| !f.is_empty()).cloned());
+
+        let config_cmd = Command::new("meson")
+            .args(args)
+            .current_dir(path)
+            .status()?;
+
+        // Meson always writes compile_commands.json, so only the link is optional.
+        if config_cmd.success() && compile_cmds {
+            link_command_compiler(path, build_dir)?;
+        }
+
+        Ok(())
+    }
+
+    fn build_command(path: &Path, build_dir: &Path, flags: Option<&Vec<String>>) -> Command {
+        let mut args = vec!["compile".to_string(), "-C".to_string()];
+        args.push(build_dir.to_string_lossy().into_owned());
+
+        if let Some(f) = flags {
+            args.append(&mut f.clone());
+        }
+
+        let mut command = Command::new("meson");
+        command.args(args).current_dir(path);
+        command
+    }
+
+    fn test(path: &PathBuf, run: &TestRun, failed: &[&TestCase]) -> Result<bool> {
+        let selection = &run.selection;
+        let tests: Vec<String> = if !failed.is_empty() {
+            failed.iter().map(|c| c.name.clone()).collect()
+        } else if selection.is_filtered() {
+            let tests = selection.filter(&MesonBuilder::list_tests(path, &run.build_dir)?);
+            if tests.is_empty() {
+                return Err(error!(CustomError, "No tests match the given filters"));
+            }
+            tests
         } else {
-            args.append(&mut flags.clone());
+            vec![]
+        };
+
+        let mut args: Vec<String> = vec!["test".into(), "-C".into()];
+        args.push(run.build_dir.to_string_lossy().into_owned());
+        args.extend(selection.meson_args(&tests));
+        if !run.wrapper.is_empty() {
+            args.push(format!("--wrapper={}", run.wrapper.join(" ")));
+        }
+        args.extend(run.flags.clone());
+
+        let success = selection.repeat(|| {
+            let status = Command::new("meson")
+                .args(&args)
+                .envs(run.env.iter().cloned())
+                .current_dir(path)
+                .status()?;
+            Ok(status.success())
+        })?;
+
+        let log = path
+            .join(&run.build_dir)
+            .join("meson-logs")
+            .join("testlog.junit.xml");
+        if log.exists() {
+            fs::copy(log, &run.junit)?;
+        }
+
+        Ok(success)
+    }
+
+    /// Rules for one target. The library is wrapped in a dependency carrying its include dirs
+    /// and definitions to the targets using it.
+    fn target_rules(target: &Target, lang: &str, pch: Option<&str>) -> String {
+        let var = target.ident();
+        let sources: Vec<String> = target
+            .sources
+            .iter()
+            .map(|s| s.to_string_lossy().into_owned())
+            .collect();
+        let defines: Vec<String> = target.defines.iter().map(|d| format!("-D{}", d)).collect();
+
+        let mut contents = vec![];
+        let (function, heading) = match target.kind {
+            TargetKind::Lib => ("static_library", "# Library".to_string()),
+            TargetKind::Bin => ("executable", format!("# Binary {}", target.name)),
+            TargetKind::Test => ("executable", format!("# Test {}", target.name)),
+            TargetKind::Example => ("executable", format!("# Example {}", target.name)),
+            TargetKind::Bench => ("executable", format!("# Benchmark {}", target.name)),
+        };
+        contents.push(heading);
+        if !target.include_dirs.is_empty() {
+            contents.push(format!(
+                "{}_inc = include_directories({})",
+                var,
+                meson_list(&target.include_dirs)
+            ));
+        }
+        contents.push(format!(
+            "{} = {}('{}', {},",
+            var,
+            function,
+            target.name,
+            meson_list(&sources)
+        ));
+        if !target.include_dirs.is_empty() {
+            contents.push(format!("  include_directories: {}_inc,", var));
+        }
+
+        let mut dependencies: Vec<String> = target
+            .link_targets
+            .iter()
+            .map(|t| format!("{}_dep", ident(t)))
+            .collect();
+        match target.kind {
+            TargetKind::Test => dependencies.push("test_dep".to_string()),
+            TargetKind::Bench => dependencies.push("bench_dep".to_string()),
+            _ => (),
+        }
+        if !dependencies.is_empty() {
+            contents.push(format!("  dependencies: [{}],", dependencies.join(", ")));
+        }
+        if !defines.is_empty() {
+            contents.push(format!("  {}_args: {},", lang, meson_list(&defines)));
+        }
+        if !target.link_libs.is_empty() {
+            let libs: Vec<String> = target
+                .link_libs
+                .iter()
+                .map(|l| format!("-l{}", l))
+                .collect();
+            contents.push(format!("  link_args: {},", meson_list(&libs)));
+        }
+        if let Some(pch) = pch {
+            contents.push(format!("  {}_pch: '{}',", lang, pch));
+        }
+        if target.kind == TargetKind::Bench {
+            contents.push("  build_by_default: false,".to_string());
+        }
+        let install = matches!(target.kind, TargetKind::Lib | TargetKind::Bin);
+        contents.push(format!(
+            "  override_options: warnings,\n  install: {})",
+            install
+        ));
+
+        match target.kind {
+            TargetKind::Lib => {
+                let include = match target.include_dirs.is_empty() {
+                    true => String::new(),
+                    false => format!(", include_directories: {}_inc", var),
+                };
+                let args = match defines.is_empty() {
+                    true => String::new(),
+                    false => format!(", compile_args: {}", meson_list(&defines)),
+                };
+                contents.push(format!(
+                    "{}_dep = declare_dependency(link_with: {}{}{})",
+                    var, var, include, args
+                ));
+            }
+            TargetKind::Test => contents.push(format!("test('{}', {})", target.name, var)),
+            TargetKind::Bench => contents.push(format!("benchmark('{}', {})", target.name, var)),
+            _ => (),
+        }
+
+        contents.push(String::new());
+        contents.join("\n")
+    }
+
+    /// The `version: 'x.y.z',` argument of `project()` with the version replaced.
+    fn version_line(line: &str, version: &Version) -> Option<String> {
+        let start = line.find("version: '")? + "version: '".len();
+        if !line[..start].trim_start().starts_with("version:") {
+            return None;
+        }
+        let end = start + line[start..].find('\'')?;
+        Some(format!("{}{}{}", &line[..start], version, &line[end..]))
+    }
+
+    /// Meson fixes the prefix at configure time, so a different prefix reconfigures the tree.
+    fn install(path: &Path, build_dir: &Path, prefix: Option<&Path>) -> Result<()> {
+        if let Some(p) = prefix {
+            let status = Command::new("meson")
+                .arg("configure")
+                .arg(build_dir)
+                .arg(format!("-Dprefix={}", p.display()))
+                .current_dir(path)
+                .status()?;
+            if !status.success() {
+                return Err(error!(CustomError, "Failed to set the install prefix"));
+            }
+        }
+
+        let install_status = Command::new("meson")
+            .args(["install", "-C"])
+            .arg(build_dir)
+            .current_dir(path)
+            .status()?;
+
+        if !install_status.success() {
+            return Err(error!(CustomError, "Install failed"));
+        }
+        Ok(())
+    }
+
+    fn list_tests(path: &PathBuf, build_dir: &Path) -> Result<Vec<String>> {
+        let output = Command::new("meson")
+            .args(["test", "--list", "-C"])
+            .arg(build_dir)
+            .current_dir(path)
+            .output()?;
+
+        // Entries are printed as `<project>:<suite> / <name>` or just `<name>`.
+        let names = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|l| l.rsplit(" / ").next().unwrap_or(l).trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        Ok(names)
+    }
+}
+
+pub struct MakeBuilder {}
+
+impl MakeBuilder {
+    fn generate(
+        project: &ProjectConfig,
+        targets: &Targets,
+        language: &Language,
+        test_framework: &TestFramework,
+        compiler: &str,
+        build: &BuildConfig,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let (cc, cflags, std) = match language {
+            Language::C(s) => ("CC", "CFLAGS", format!("-std=c{}", s.version())),
+            Language::Cpp(s) => ("CXX", "CXXFLAGS", format!("-std=c++{}", s.version())),
+        };
+        if !build.pch.is_empty() || build.unity {
+            return Err(error!(
+                CustomError,
+                "The Make backend doesn't support `pch` or `unity` in [build], use CMake or Meson"
+            ));
+        }
+        let src_suffix = language.src_suffix();
+        let family = CompilerFamily::from_compiler(compiler);
+
+        let mut contents = vec![regen::HEADER.to_string()];
+
+        contents.push("# General".to_string());
+        contents.push(format!("NAME := {}", project.name));
+        contents.push(format!("VERSION := {}", project.version));
+        contents.push(format!("{} := {}", cc, compiler));
+        contents.push("BUILD_DIR ?= build".to_string());
+        contents.push("PREFIX ?= /usr/local".to_string());
+        contents.push("OPT ?= -g".to_string());
+        contents.push("DIAGNOSTICS ?=".to_string());
+        match build.cache {
+            Some(cache) => contents.push(format!("LAUNCHER ?= {}", cache.as_str())),
+            None => contents.push("LAUNCHER ?=".to_string()),
+        }
+        contents.push(format!("STD := {}", std));
+        contents.push(format!("TEST_LIBS := {}", test_framework.link_libs()));
+        if !targets.benches.is_empty() {
+            contents.push(format!(
+                "BENCH_LIBS := {}",
+                BenchFramework::for_language(language).link_libs()
+            ));
+        }
+        contents.push(String::new());
+
+        contents.push("# Warnings, applied to the project's own objects only".to_string());
+        contents.push(format!(
+            "WARNINGS := {}\n",
+            build.warnings.flags(family).join(" ")
+        ));
+        if !build.profiles.is_default() {
+            contents.push(build.profiles.make_rules());
+        }
+        contents.push(regen::region("settings"));
+
+        // Benchmarks are only built by `forge bench`.
+        let all: Vec<String> = targets
+            .all()
+            .iter()
+            .filter(|t| t.kind != TargetKind::Bench)
+            .map(|t| format!("$({}_OUT)", t.ident()))
+            .collect();
+        contents.push("# Targets".to_string());
+        contents.push(".PHONY: all clean install".to_string());
+        contents.push(".DEFAULT_GOAL := all\n".to_string());
+
+        for target in targets.all() {
+            contents.push(MakeBuilder::target_rules(target, targets, cc));
+        }
+        contents.push(format!("all: {}\n", all.join(" ")));
+
+        contents.push(format!(
+            "$(BUILD_DIR)/obj/%.{}.o: %.{}",
+            src_suffix, src_suffix
+        ));
+        contents.push("\t@mkdir -p $(@D)".to_string());
+        contents.push(format!(
+            "\t$(LAUNCHER) $({}) $(STD) $(OPT) $(LTO) $(WARNINGS) $(DIAGNOSTICS) $(CPPFLAGS) $({}) -MMD -MP -c $< -o $@\n",
+            cc, cflags
+        ));
+
+        let installed: Vec<&Target> = targets.lib.iter().chain(&targets.bins).collect();
+        let outputs: Vec<String> = installed
+            .iter()
+            .map(|t| format!("$({}_OUT)", t.ident()))
+            .collect();
+        // DESTDIR stages the install for packaging, as `cmake --install` and `meson install` do.
+        contents.push(format!("install: {}", outputs.join(" ")));
+        contents.push(
+            "\tinstall -d $(DESTDIR)$(PREFIX)/bin $(DESTDIR)$(PREFIX)/lib $(DESTDIR)$(PREFIX)/include"
+                .to_string(),
+        );
+        for target in &targets.bins {
+            contents.push(format!(
+                "\tinstall -m 755 $({}_OUT) $(DESTDIR)$(PREFIX)/bin",
+                target.ident()
+            ));
+        }
+        if let Some(lib) = &targets.lib {
+            contents.push(format!(
+                "\tinstall -m 644 $({}_OUT) $(DESTDIR)$(PREFIX)/lib",
+                lib.ident()
+            ));
+        }
+        contents.push("\tcp -R include/. $(DESTDIR)$(PREFIX)/include\n".to_string());
+
+        contents.push("clean:".to_string());
+        contents.push(format!(
+            "\trm -rf $(BUILD_DIR)/obj $(BUILD_DIR)/bin $(BUILD_DIR)/examples $(BUILD_DIR)/benches {}\n",
+            all.join(" ")
+        ));
+        let deps: Vec<String> = targets
+            .all()
+            .iter()
+            .map(|t| format!("$({}_OBJS:.o=.d)", t.ident()))
+            .collect();
+        contents.push(format!("-include {}\n", deps.join(" ")));
+        contents.push(regen::region("targets"));
+
+        Ok(vec![(PathBuf::from("Makefile"), contents.join("\n"))])
+    }
+
+    /// Variables and rules for one target. Make has no usage requirements, so the library's
+    /// include dirs and definitions are repeated on the objects of the targets linking it.
+    fn target_rules(target: &Target, targets: &Targets, cc: &str) -> String {
+        let var = target.ident();
+        let sources: Vec<String> = target
+            .sources
+            .iter()
+            .map(|s| s.to_string_lossy().into_owned())
+            .collect();
+        let (heading, output) = match target.kind {
+            TargetKind::Lib => (
+                "# Library".to_string(),
+                format!("$(BUILD_DIR)/lib{}.a", target.name),
+            ),
+            TargetKind::Bin => (
+                format!("# Binary {}", target.name),
+                format!("$(BUILD_DIR)/bin/{}", target.name),
+            ),
+            TargetKind::Test => (
+                format!("# Test {}", target.name),
+                format!("$(BUILD_DIR)/{}", target.name),
+            ),
+            TargetKind::Example => (
+                format!("# Example {}", target.name),
+                format!("$(BUILD_DIR)/examples/{}", target.name),
+            ),
+            TargetKind::Bench => (
+                format!("# Benchmark {}", target.name),
+                format!("$(BUILD_DIR)/benches/{}", target.name),
+            ),
+        };
+
+        let mut flags: Vec<String> = vec![];
+        let linked = target
+            .link_targets
+            .iter()
+            .filter_map(|l| targets.find(l))
+            .filter(|l| l.kind == TargetKind::Lib);
+        for t in std::iter::once(target).chain(linked) {
+            flags.extend(t.include_dirs.iter().map(|d| format!("-I{}", d)));
+            flags.extend(t.defines.iter().map(|d| format!("-D{}", d)));
         }
 
-        let config_cmd = Command::new("cmake")
-            .args(args)
-            .current_dir(path)
-            .status()?;
+        let mut contents = vec![heading];
+        contents.push(format!("{}_SRCS := {}", var, sources.join(" ")));
+        contents.push(format!(
+            "{}_OBJS := $({}_SRCS:%=$(BUILD_DIR)/obj/%.o)",
+            var, var
+        ));
+        contents.push(format!("{}_OUT := {}", var, output));
+        if !flags.is_empty() {
+            contents.push(format!("$({}_OBJS): CPPFLAGS += {}", var, flags.join(" ")));
+        }
 
-        if config_cmd.success() && compile_cmds {
-            CMakeBuilder::link_command_compiler(path)?;
+        if target.kind == TargetKind::Lib {
+            contents.push(format!("$({}_OUT): $({}_OBJS)", var, var));
+            contents.push("\t@mkdir -p $(@D)".to_string());
+            contents.push("\t$(AR) rcs $@ $^\n".to_string());
+            return contents.join("\n");
         }
 
-        Ok(())
+        let mut prerequisites = vec![format!("$({}_OBJS)", var)];
+        prerequisites.extend(
+            target
+                .link_targets
+                .iter()
+                .map(|l| format!("$({}_OUT)", ident(l))),
+        );
+        let mut libs: Vec<String> = target
+            .link_libs
+            .iter()
+            .map(|l| format!("-l{}", l))
+            .collect();
+        match target.kind {
+            TargetKind::Test => libs.push("$(TEST_LIBS)".to_string()),
+            TargetKind::Bench => libs.push("$(BENCH_LIBS)".to_string()),
+            _ => (),
+        }
+        libs.push("$(LDLIBS)".to_string());
+
+        contents.push(format!("$({}_OUT): {}", var, prerequisites.join(" ")));
+        contents.push("\t@mkdir -p $(@D)".to_string());
+        contents.push(format!(
+            "\t$({}) $(LTO) $(LINKER) $(LDFLAGS) $^ {} -o $@\n",
+            cc,
+            libs.join(" ")
+        ));
+        contents.join("\n")
     }
 
-    /// Utility function to be used by default to system link the compile_commands.json to project
-    /// root
-    fn link_command_compiler(path: &PathBuf) -> Result<()> {
-        let ln_status = Command::new("ln")
-            .args([
-                "-sf",
-                "build/compile_commands.json",
-                "compile_commands.json",
-            ])
-            .current_dir(path)
-            .status()?;
-        if !ln_status.success() {
-            eprintln!("Failed to create symlink for the compile_commands.json");
+    fn version_line(line: &str, version: &Version) -> Option<String> {
+        match line.starts_with("VERSION :=") {
+            true => Some(format!("VERSION := {}", version)),
+            false => None,
         }
+    }
+
+    /// Make has no configure step, the tree only has to exist.
+    fn configure(path: &Path, build_dir: &Path) -> Result<()> {
+        fs::create_dir_all(path.join(build_dir))?;
         Ok(())
     }
 
-    fn build(path: &PathBuf, flags: Option<&Vec<String>>) -> Result<()> {
-        let mut args = vec!["--build".to_string(), "build".to_string()];
+    fn build_command(path: &Path, build_dir: &Path, flags: Option<&Vec<String>>) -> Command {
+        let mut args = vec![format!("BUILD_DIR={}", build_dir.display())];
 
         if let Some(f) = flags {
             args.append(&mut f.clone());
         }
 
-        let build_status = Command::new("cmake")
-            .args(args)
-            .current_dir(path)
-            .status()?;
-
-        if !build_status.success() {
-            eprintln!("Compilation failed.");
-        }
-        Ok(())
+        let mut command = Command::new("make");
+        command.args(args).current_dir(path);
+        command
     }
-}
 
-pub struct MesonBuilder {}
-
-impl MesonBuilder {
-    fn init() -> Result<()> {
-        Ok(())
-    }
+    fn install(
+        path: &Path,
+        build_dir: &Path,
+        prefix: Option<&Path>,
+        build_flags: &[String],
+    ) -> Result<()> {
+        let mut args = vec!["install".to_string()];
+        args.push(format!("BUILD_DIR={}", build_dir.display()));
+        if let Some(p) = prefix {
+            args.push(format!("PREFIX={}", p.display()));
+        }
+        args.extend(build_flags.iter().cloned());
 
-    fn configure() -> Result<()> {
-        Ok(())
-    }
+        let install_status = Command::new("make").args(args).current_dir(path).status()?;
 
-    fn build() -> Result<()> {
+        if !install_status.success() {
+            return Err(error!(CustomError, "Install failed"));
+        }
         Ok(())
     }
-}
 
-pub struct MakeBuilder {}
+    /// Make has no test runner, so each test binary is invoked directly with framework flags.
+    /// With several binaries their JUnit results are merged into `run.junit`.
+    fn test(
+        path: &PathBuf,
+        tests: &[Target],
+        test_framework: &TestFramework,
+        run: &TestRun,
+        failed: &[&TestCase],
+    ) -> Result<bool> {
+        let selection = &run.selection;
+        if selection.jobs.is_some() {
+            println!("Ignoring --jobs, the test binaries run serially.");
+        }
+        if tests.is_empty() {
+            println!("No tests to run.");
+            return Ok(true);
+        }
 
-impl MakeBuilder {
-    fn init() -> Result<()> {
-        Ok(())
-    }
+        let stem = run
+            .junit
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut success = true;
+        let mut junits = vec![];
+
+        for test in tests {
+            let junit = match tests.len() {
+                1 => run.junit.clone(),
+                _ => run
+                    .junit
+                    .with_file_name(format!("{}-{}.xml", stem, test.name)),
+            };
+
+            let mut args = test_framework.binary_args(selection, failed, &junit)?;
+            args.extend(run.flags.clone());
+
+            let bin = path.join(&run.build_dir).join(&test.name);
+            let mut env = test_framework.binary_env(&junit);
+            env.extend(run.env.iter().cloned());
+
+            let mut command: Vec<String> = run.wrapper.clone();
+            command.push(bin.to_string_lossy().into_owned());
+            command.extend(args);
+
+            success &= selection.repeat(|| {
+                let status = Command::new(&command[0])
+                    .args(&command[1..])
+                    .envs(env.iter().cloned())
+                    .current_dir(path)
+                    .status()?;
+                Ok(status.success())
+            })?;
+            junits.push(junit);
+        }
 
-    fn configure() -> Result<()> {
-        Ok(())
-    }
+        if tests.len() > 1 {
+            let mut report = TestReport::default();
+            for junit in junits.iter().filter(|j| j.exists()) {
+                report
+                    .cases
+                    .extend(TestReport::from_junit_file(junit)?.cases);
+                fs::remove_file(junit)?;
+            }
+            if !report.cases.is_empty() {
+                fs::write(&run.junit, report.to_junit())?;
+            }
+        }
 
-    fn build() -> Result<()> {
-        Ok(())
+        Ok(success)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{language::CStandard, test_framework::TestFrameworks};
+    use crate::core::{
+        compiler_cache::CompilerCache,
+        diagnostics::Severity,
+        language::{CStandard, CppStandard},
+        profile::{Linker, Lto, ProfileConfig},
+        sanitizer::SanitizerSet,
+        targets::TargetsConfig,
+        test_framework::TestFrameworks,
+        warnings::WarningLevel,
+    };
     use serial_test::serial;
     use std::{
         env,
@@ -286,6 +1855,19 @@ mod tests {
         Ok(())
     }
 
+    /// Creates the conventional sources that don't exist yet and resolves the targets.
+    fn dummy_targets(path: &PathBuf, project: &ProjectConfig) -> anyhow::Result<Targets> {
+        let ext = project.language.src_suffix();
+        for file in ["src/lib", "src/main", "test/test_main"] {
+            let file = path.join(format!("{}.{}", file, ext));
+            fs::create_dir_all(file.parent().unwrap())?;
+            if !file.exists() {
+                fs::write(file, "")?;
+            }
+        }
+        Ok(Targets::resolve(path, project, &TargetsConfig::default())?)
+    }
+
     #[test]
     fn test_build_system_from_str() {
         let s = "CMake";
@@ -318,12 +1900,525 @@ mod tests {
         create_dummy_project(&path)?;
 
         // Test
+        let mut project = ProjectConfig::new(name.clone(), language.clone());
+        project.version = Version::new(2, 3, 1);
+        project.description = "A dummy project".to_string();
         let build_system = BuildSystem::new(name, variant, path.clone(), test_framework, language);
-        build_system.init()?;
+        let targets = dummy_targets(&path, &project)?;
+        build_system.init(&project, &targets, "gcc", &BuildConfig::default())?;
 
         // Validate
         let file_check = check_file_exits(&path.join("CMakeLists.txt"));
         assert!(file_check);
+        let contents = fs::read_to_string(path.join("CMakeLists.txt"))?;
+        assert!(contents
+            .contains("project(dummy VERSION 2.3.1 DESCRIPTION \"A dummy project\" LANGUAGES C)"));
+        assert!(contents.contains("include(CPack)"));
+        assert!(contents.contains("# Testing\ninclude(CTest)\n"));
+        assert!(build_system.set_version(&Version::new(2, 4, 0))?);
+        let contents = fs::read_to_string(path.join("CMakeLists.txt"))?;
+        assert!(contents.contains("project(dummy VERSION 2.4.0 DESCRIPTION"));
+        assert!(contents.contains("install(EXPORT dummyTargets"));
+        assert!(contents.contains("write_basic_package_version_file("));
+        assert!(check_file_exits(
+            &path.join("cmake").join("dummyConfig.cmake.in")
+        ));
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_meson_builder_init() -> anyhow::Result<()> {
+        let cwd = env::current_dir()?;
+        let name = "dummy".to_string();
+        let path = cwd.join(&name);
+        let language = Language::C(CStandard::C99);
+        let test_framework = TestFramework::new(TestFrameworks::CMocka, path.clone());
+        let mut build = BuildConfig::default();
+        build.warnings.level = WarningLevel::Strict;
+
+        // Set-up
+        create_dummy_project(&path)?;
+
+        // Test
+        let project = ProjectConfig::new(name.clone(), language.clone());
+        let build_system = BuildSystem::new(
+            name,
+            BuildSystems::Meson,
+            path.clone(),
+            test_framework,
+            language,
+        );
+        let targets = dummy_targets(&path, &project)?;
+        build_system.init(&project, &targets, "gcc", &build)?;
+
+        // Validate
+        let contents = fs::read_to_string(path.join("meson.build"))?;
+        assert!(contents.contains("default_options: ['c_std=c99']"));
+        assert!(contents.contains("warnings = ['warning_level=2', 'werror=false']"));
+        assert_eq!(contents.matches("override_options: warnings").count(), 3);
+        assert!(contents.contains("test_dep = dependency('cmocka')"));
+        assert_eq!(
+            build_system.binary(Path::new("build")),
+            PathBuf::from("build").join("dummy")
+        );
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_compiler_launcher() -> anyhow::Result<()> {
+        let cwd = env::current_dir()?;
+        let name = "dummy".to_string();
+        let path = cwd.join(&name);
+        let language = Language::C(CStandard::C11);
+        let mut build = BuildConfig::default();
+        build.cache = Some(CompilerCache::Ccache);
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let project = ProjectConfig::new(name.clone(), language.clone());
+        let targets = dummy_targets(&path, &project)?;
+        let backend = |variant| {
+            BuildSystem::new(
+                name.clone(),
+                variant,
+                path.clone(),
+                TestFramework::new(TestFrameworks::CMocka, path.clone()),
+                language.clone(),
+            )
+        };
+
+        // Test
+        backend(BuildSystems::Make).init(&project, &targets, "gcc", &build)?;
+        let cmake = backend(BuildSystems::CMake);
+
+        // Validate
+        let makefile = fs::read_to_string(path.join("Makefile"))?;
+        assert!(makefile.contains("LAUNCHER ?= ccache\n"));
+        assert!(makefile.contains("\t$(LAUNCHER) $(CC) $(STD)"));
+        assert_eq!(
+            cmake.launcher_args(Some(Path::new("/usr/bin/ccache"))),
+            vec!["-DCMAKE_C_COMPILER_LAUNCHER=/usr/bin/ccache"]
+        );
+        assert_eq!(
+            cmake.launcher_args(None),
+            vec!["-DCMAKE_C_COMPILER_LAUNCHER="]
+        );
+        assert!(backend(BuildSystems::Meson).launcher_args(None).is_empty());
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_make_release_lto() -> anyhow::Result<()> {
+        let cwd = env::current_dir()?;
+        let name = "dummy".to_string();
+        let path = cwd.join(&name);
+        let language = Language::C(CStandard::C11);
+        let mut build = BuildConfig::default();
+        build.profiles.release = ProfileConfig {
+            lto: Lto::Full,
+            linker: Linker::Gold,
+        };
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let project = ProjectConfig::new(name.clone(), language.clone());
+        let targets = dummy_targets(&path, &project)?;
+        fs::write(
+            path.join("src").join("main.c"),
+            "int main(void) { return 0; }\n",
+        )?;
+
+        // Test
+        let build_system = BuildSystem::new(
+            name,
+            BuildSystems::Make,
+            path.clone(),
+            TestFramework::new(TestFrameworks::CMocka, path.clone()),
+            language,
+        );
+        build_system.init(&project, &targets, "gcc", &build)?;
+        let mut flags = build_system.profile_build_args(Profile::Release);
+        flags.push("build/release/bin/dummy".to_string());
+        let output = build_system.compile(
+            &Profile::Release.build_dir(),
+            Some(&flags),
+            MessageFormat::Human,
+        )?;
+
+        // Validate
+        let makefile = fs::read_to_string(path.join("Makefile"))?;
+        assert!(
+            makefile.contains("ifeq ($(PROFILE),release)\nLTO := -flto\nLINKER := -fuse-ld=gold\n")
+        );
+        assert!(makefile.contains("ifeq ($(PROFILE),debug)\nLTO := \nLINKER := \n"));
+        assert!(output.success);
+        assert!(check_file_exits(&path.join("build/release/bin/dummy")));
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmake_timing_launcher() {
+        let path = PathBuf::from("dummy");
+        let build_system = BuildSystem::new(
+            "dummy".to_string(),
+            BuildSystems::CMake,
+            path.clone(),
+            TestFramework::new(TestFrameworks::GTest, path.clone()),
+            Language::Cpp(CppStandard::Cpp17),
+        );
+        let launcher: Vec<String> = ["forge", "time-compile", "compile.log", "ccache"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+
+        let flags = vec!["-DCMAKE_BUILD_TYPE=Release".to_string()];
+
+        // Test
+        let log_dir = build_system.ninja_log_dir(Path::new("build"), false);
+        let args = build_system.timing_configure_args(Path::new("build"), &flags, &launcher, false);
+
+        // Validate
+        assert_eq!(log_dir, None);
+        assert_eq!(
+            args,
+            Some(vec![
+                "-DCMAKE_BUILD_TYPE=Release".to_string(),
+                "-DCMAKE_CXX_COMPILER_LAUNCHER=forge;time-compile;compile.log;ccache".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_meson_trace_configure_args() {
+        let path = PathBuf::from("dummy");
+        let build_system = BuildSystem::new(
+            "dummy".to_string(),
+            BuildSystems::Meson,
+            path.clone(),
+            TestFramework::new(TestFrameworks::GTest, path.clone()),
+            Language::C(CStandard::C11),
+        );
+        let sanitized = build_system.toolchain_args(
+            "clang",
+            &["-fsanitize=address".to_string()],
+            &["-fsanitize=address".to_string()],
+        );
+
+        // Test
+        let timed = build_system.timing_configure_args(Path::new("build"), &[], &[], false);
+        let traced = build_system.timing_configure_args(Path::new("build"), &[], &[], true);
+        let both = build_system.timing_configure_args(Path::new("build"), &sanitized, &[], true);
+
+        // Validate
+        assert_eq!(timed, None);
+        assert_eq!(traced, Some(vec!["-Dc_args=['-ftime-trace']".to_string()]));
+        assert_eq!(
+            both.unwrap()[0],
+            "-Dc_args=['-ftime-trace','-fsanitize=address']"
+        );
+    }
+
+    #[test]
+    fn test_make_toolchain_build_args() -> anyhow::Result<()> {
+        let path = PathBuf::from("dummy");
+        let set = SanitizerSet::parse("address")?;
+        let build_system = BuildSystem::new(
+            "dummy".to_string(),
+            BuildSystems::Make,
+            path.clone(),
+            TestFramework::new(TestFrameworks::CMocka, path.clone()),
+            Language::C(CStandard::C11),
+        );
+
+        // Test
+        let flags = build_system.toolchain_build_args(&set.compile_flags(), &set.link_flags());
+        let command = MakeBuilder::build_command(&path, &set.build_dir(), Some(&flags));
+
+        // Validate
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            vec![
+                "BUILD_DIR=build/sanitize-address",
+                "CFLAGS=-fsanitize=address -fno-omit-frame-pointer -g",
+                "LDFLAGS=-fsanitize=address",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_make_clean() -> anyhow::Result<()> {
+        let cwd = env::current_dir()?;
+        let name = "dummy".to_string();
+        let path = cwd.join(&name);
+        let language = Language::C(CStandard::C11);
+        let build_dir = Profile::Debug.build_dir();
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let project = ProjectConfig::new(name.clone(), language.clone());
+        let targets = dummy_targets(&path, &project)?;
+        fs::write(
+            path.join("src").join("main.c"),
+            "int main(void) { return 0; }\n",
+        )?;
+        let build_system = BuildSystem::new(
+            name,
+            BuildSystems::Make,
+            path.clone(),
+            TestFramework::new(TestFrameworks::CMocka, path.clone()),
+            language,
+        );
+        build_system.init(&project, &targets, "gcc", &BuildConfig::default())?;
+        let flags = vec!["build/bin/dummy".to_string()];
+        build_system.compile(&build_dir, Some(&flags), MessageFormat::Human)?;
+
+        // Test
+        let bin = targets.find("dummy").unwrap();
+        let artifacts = build_system.target_artifacts(&build_dir, bin);
+
+        // Validate
+        assert_eq!(
+            artifacts,
+            vec![
+                build_dir.join("obj/src/main.c.o"),
+                build_dir.join("obj/src/main.c.d"),
+                build_dir.join("bin/dummy"),
+            ]
+        );
+        assert!(artifacts.iter().all(|a| check_file_exits(&path.join(a))));
+
+        // Test
+        build_system.clean_in(&build_dir)?;
+
+        // Validate
+        assert!(!check_file_exits(&path.join("build/bin/dummy")));
+        assert!(!check_file_exits(&path.join("build/obj")));
+        assert!(check_file_exits(&path.join("Makefile")));
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_pch_and_unity() -> anyhow::Result<()> {
+        let cwd = env::current_dir()?;
+        let name = "dummy".to_string();
+        let path = cwd.join(&name);
+        let language = Language::Cpp(CppStandard::Cpp17);
+        let mut build = BuildConfig::default();
+        build.pch = vec!["<vector>".to_string(), "include/common.hpp".to_string()];
+        build.unity = true;
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let project = ProjectConfig::new(name.clone(), language.clone());
+        let targets = dummy_targets(&path, &project)?;
+        let backend = |variant| {
+            BuildSystem::new(
+                name.clone(),
+                variant,
+                path.clone(),
+                TestFramework::new(TestFrameworks::GTest, path.clone()),
+                language.clone(),
+            )
+        };
+
+        // Test
+        backend(BuildSystems::CMake).init(&project, &targets, "g++", &build)?;
+        backend(BuildSystems::Meson).init(&project, &targets, "g++", &build)?;
+        let make = backend(BuildSystems::Make).init(&project, &targets, "g++", &build);
+
+        // Validate
+        let cmake = fs::read_to_string(path.join("CMakeLists.txt"))?;
+        assert!(cmake.contains("cmake_minimum_required(VERSION 3.16)"));
+        assert!(cmake
+            .contains("target_precompile_headers(dummyLib PRIVATE <vector> include/common.hpp)"));
+        assert!(cmake.contains("set_target_properties(dummy PROPERTIES UNITY_BUILD ON)"));
+
+        let meson = fs::read_to_string(path.join("meson.build"))?;
+        assert!(meson.contains("default_options: ['cpp_std=c++17', 'unity=on']"));
+        assert_eq!(meson.matches("cpp_pch: 'pch/dummy_pch.hpp'").count(), 3);
+        let header = fs::read_to_string(path.join(PCH_DIR).join("dummy_pch.hpp"))?;
+        assert!(header.contains("#include <vector>\n#include \"../include/common.hpp\"\n"));
+
+        assert!(make.is_err());
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_make_builder_build() -> anyhow::Result<()> {
+        let cwd = env::current_dir()?;
+        let name = "dummy".to_string();
+        let path = cwd.join(&name);
+        let language = Language::C(CStandard::C99);
+        let test_framework = TestFramework::new(TestFrameworks::CMocka, path.clone());
+        let mut build = BuildConfig::default();
+        build.warnings.warnings_as_errors = true;
+
+        // Set-up
+        create_dummy_project(&path)?;
+        fs::create_dir_all(path.join("src"))?;
+        fs::create_dir_all(path.join("include"))?;
+        fs::create_dir_all(path.join("libs"))?;
+        fs::write(
+            path.join("include").join("lib.h"),
+            "int add(int a, int b);\n",
+        )?;
+        fs::write(
+            path.join("src").join("lib.c"),
+            "#include \"lib.h\"\nint add(int a, int b) { return a + b; }\n",
+        )?;
+        fs::write(
+            path.join("src").join("main.c"),
+            "#include \"lib.h\"\nint main(void) { return add(1, -1); }\n",
+        )?;
+
+        // Test
+        let project = ProjectConfig::new(name.clone(), language.clone());
+        let build_system = BuildSystem::new(
+            name,
+            BuildSystems::Make,
+            path.clone(),
+            test_framework,
+            language,
+        );
+        let targets = dummy_targets(&path, &project)?;
+        build_system.init(&project, &targets, "gcc", &build)?;
+        build_system.configure(false, &vec![])?;
+        build_system.build(Some(&vec!["build/bin/dummy".to_string()]))?;
+
+        // Validate
+        let contents = fs::read_to_string(path.join("Makefile"))?;
+        assert!(contents.contains("WARNINGS := -Wall -Werror"));
+        assert!(contents.contains("TEST_LIBS := -lcmocka"));
+        assert!(contents.contains("\t@mkdir -p $(@D)\n\t$(AR) rcs $@ $^\n"));
+        assert!(check_file_exits(&build_system.binary(&path.join("build"))));
+
+        let prefix = path.join("prefix");
+        build_system.install(Path::new("build"), Some(&prefix), &[])?;
+        assert!(check_file_exits(&prefix.join("bin").join("dummy")));
+        assert!(check_file_exits(&prefix.join("lib").join("libdummyLib.a")));
+        assert!(check_file_exits(&prefix.join("include").join("lib.h")));
+
+        let status = Command::new("make")
+            .args(["install", "PREFIX=/usr", "DESTDIR=stage"])
+            .current_dir(&path)
+            .status()?;
+        assert!(status.success());
+        assert!(check_file_exits(
+            &path.join("stage").join("usr").join("bin").join("dummy")
+        ));
+
+        let tarball =
+            build_system.package(&project, Path::new("build"), &[PackageFormat::Tgz], &[])?;
+        assert_eq!(
+            tarball,
+            vec![path
+                .join(PACKAGE_DIR)
+                .join(format!("{}.tar.gz", archive_stem(&project)))]
+        );
+        assert!(build_system
+            .package(&project, Path::new("build"), &[PackageFormat::Deb], &[])
+            .is_err());
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_make_builder_compile_diagnostics() -> anyhow::Result<()> {
+        let cwd = env::current_dir()?;
+        let name = "dummy".to_string();
+        let path = cwd.join(&name);
+        let language = Language::C(CStandard::C99);
+        let test_framework = TestFramework::new(TestFrameworks::CMocka, path.clone());
+
+        // Set-up
+        create_dummy_project(&path)?;
+        fs::create_dir_all(path.join("src"))?;
+        fs::create_dir_all(path.join("include"))?;
+        fs::create_dir_all(path.join("libs"))?;
+        fs::write(
+            path.join("include").join("lib.h"),
+            "int add(int a, int b);\n",
+        )?;
+        fs::write(
+            path.join("src").join("lib.c"),
+            "#include \"lib.h\"\nint add(int a, int b) { int unused; return a + b; }\n",
+        )?;
+        fs::write(
+            path.join("src").join("main.c"),
+            "#include \"lib.h\"\nint main(void) { return add(1, -1); }\n",
+        )?;
+
+        // Test
+        let project = ProjectConfig::new(name.clone(), language.clone());
+        let build_system = BuildSystem::new(
+            name,
+            BuildSystems::Make,
+            path.clone(),
+            test_framework,
+            language,
+        );
+        let targets = dummy_targets(&path, &project)?;
+        build_system.init(&project, &targets, "gcc", &BuildConfig::default())?;
+        let mut flags = build_system.diagnostics_args(CompilerFamily::Gcc, MessageFormat::Json);
+        flags.push("build/bin/dummy".to_string());
+        let warned = build_system.compile(Path::new("build"), Some(&flags), MessageFormat::Json)?;
+        fs::write(
+            path.join("src").join("main.c"),
+            "#include \"lib.h\"\nint main(void) { return add(1, -1) }\n",
+        )?;
+        let flags = vec!["build/bin/dummy".to_string()];
+        let failed =
+            build_system.compile(Path::new("build"), Some(&flags), MessageFormat::Human)?;
+
+        // Validate
+        assert!(warned.success);
+        assert_eq!(warned.diagnostics.len(), 1);
+        assert_eq!(warned.diagnostics[0].file, path.join("src").join("lib.c"));
+        assert_eq!(warned.diagnostics[0].line, 2);
+        assert_eq!(
+            warned.diagnostics[0].check.as_deref(),
+            Some("-Wunused-variable")
+        );
+        assert!(!failed.success);
+        assert_eq!(failed.diagnostics[0].file, path.join("src").join("main.c"));
+        assert_eq!(failed.diagnostics[0].severity, Severity::Error);
 
         // Clean-up
         delete_dummy_project(&path)?;
@@ -345,8 +2440,10 @@ mod tests {
 
         // Set-up
         create_dummy_project(&path)?;
+        let project = ProjectConfig::new(name.clone(), language.clone());
         let build_system = BuildSystem::new(name, variant, path.clone(), test_framework, language);
-        build_system.init()?;
+        let targets = dummy_targets(&path, &project)?;
+        build_system.init(&project, &targets, "gcc", &BuildConfig::default())?;
 
         // Test
         let flags = vec!["".to_string()];
@@ -376,8 +2473,10 @@ mod tests {
 
         // Set-up
         create_dummy_project(&path)?;
+        let project = ProjectConfig::new(name.clone(), language.clone());
         let build_system = BuildSystem::new(name, variant, path.clone(), test_framework, language);
-        build_system.init()?;
+        let targets = dummy_targets(&path, &project)?;
+        build_system.init(&project, &targets, "gcc", &BuildConfig::default())?;
 
         // Test
         let flags = vec!["".to_string()];
@@ -408,8 +2507,11 @@ mod tests {
         // Set-up
         create_dummy_project(&path)?;
 
+        let project = ProjectConfig::new(name.clone(), language.clone());
+
         let build_system = BuildSystem::new(name, variant, path.clone(), test_framework, language);
-        build_system.init()?;
+        let targets = dummy_targets(&path, &project)?;
+        build_system.init(&project, &targets, "gcc", &BuildConfig::default())?;
 
         let flags = vec!["".to_string()];
         build_system.configure(true, &flags)?;
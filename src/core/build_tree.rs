@@ -0,0 +1,44 @@
+use super::build_system::BUILD_DIR;
+use super::diagnostics::MessageFormat;
+use super::profile::Profile;
+use std::path::PathBuf;
+
+/// A build directory together with what it takes to configure it and run binaries from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildTree {
+    /// Relative to the project directory.
+    pub dir: PathBuf,
+    pub profile: Profile,
+    /// Configure arguments for trees forge sets up itself. Empty for the default tree, which is
+    /// configured with `forge config`.
+    pub configure_flags: Vec<String>,
+    /// Arguments added to every build of this tree, for backends without a configure step.
+    pub build_flags: Vec<String>,
+    /// Environment for binaries run from this tree.
+    pub env: Vec<(String, String)>,
+    /// How compiler diagnostics are reported when building this tree.
+    pub message_format: MessageFormat,
+    /// Record compile times and print this many of the slowest files.
+    pub timings: Option<usize>,
+}
+
+impl Default for BuildTree {
+    fn default() -> BuildTree {
+        BuildTree {
+            dir: PathBuf::from(BUILD_DIR),
+            profile: Profile::Debug,
+            configure_flags: vec![],
+            build_flags: vec![],
+            env: vec![],
+            message_format: MessageFormat::default(),
+            timings: None,
+        }
+    }
+}
+
+impl BuildTree {
+    /// Trees forge sets up and builds itself, before running tests from them.
+    pub fn is_managed(&self) -> bool {
+        !self.configure_flags.is_empty() || !self.build_flags.is_empty()
+    }
+}
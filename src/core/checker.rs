@@ -0,0 +1,334 @@
+use super::compile_db::{normalize, run_parallel, CompileCommand, CompileDb};
+use super::diagnostics::{self, Diagnostic, Severity};
+use crate::{error, Error, Result};
+use ansi_term::Colour::{Green, Red, Yellow};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Compiler arguments that only matter for producing objects or dependency files. The ones in
+/// `WITH_VALUE` take the next argument too.
+const OUTPUT_ARGS: [&str; 4] = ["-c", "-MD", "-MMD", "-MP"];
+const WITH_VALUE: [&str; 4] = ["-o", "-MF", "-MT", "-MQ"];
+
+pub struct Checker {
+    directory: PathBuf,
+    build_dir: PathBuf,
+    /// Absolute paths of the project's translation units.
+    sources: Vec<PathBuf>,
+}
+
+impl Checker {
+    pub fn new(directory: PathBuf, build_dir: PathBuf, sources: Vec<PathBuf>) -> Checker {
+        Checker {
+            directory,
+            build_dir,
+            sources,
+        }
+    }
+
+    /// Runs every project translation unit through the compiler with `-fsyntax-only`, or only
+    /// those in `only` when given. Returns the number of files checked and the diagnostics.
+    pub fn check(&self, only: Option<&[PathBuf]>, jobs: usize) -> Result<(usize, Vec<Diagnostic>)> {
+        let db_path = self
+            .directory
+            .join(&self.build_dir)
+            .join("compile_commands.json");
+        let db = CompileDb::from_file(&db_path)?;
+        let commands: Vec<&CompileCommand> = db
+            .project_commands(&self.sources)
+            .into_iter()
+            .filter(|c| only.is_none_or(|files| files.contains(&c.path())))
+            .collect();
+
+        let outputs = run_parallel(&commands, jobs, |command| {
+            let args = syntax_only_args(&command.args()?);
+            let output = Command::new(&args[0])
+                .args(&args[1..])
+                .current_dir(&command.directory)
+                .output()?;
+
+            // Paths in the output are relative to the directory the compiler ran in.
+            let mut found = Diagnostic::parse_output(&String::from_utf8_lossy(&output.stderr));
+            for d in &mut found {
+                d.file = normalize(&command.directory.join(&d.file));
+            }
+            if !output.status.success() && !found.iter().any(|d| d.severity == Severity::Error) {
+                return Err(error!(
+                    CustomError,
+                    "Checking {} failed:\n{}",
+                    command.file.display(),
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+            Ok(found)
+        });
+
+        let mut found = vec![];
+        for output in outputs {
+            found.extend(output?);
+        }
+        let diagnostics = diagnostics::project_diagnostics(&self.directory, &self.build_dir, found);
+        Ok((commands.len(), diagnostics))
+    }
+
+    /// Translation units touched by the uncommitted changes in git: the changed sources and
+    /// the sources including a changed header.
+    pub fn changed_sources(&self) -> Result<Vec<PathBuf>> {
+        let mut changed = git_files(
+            &self.directory,
+            &["diff", "--name-only", "--relative", "HEAD"],
+        )
+        .or_else(|_| {
+            git_files(
+                &self.directory,
+                &["diff", "--name-only", "--relative", "--cached"],
+            )
+        })?;
+        changed.extend(git_files(
+            &self.directory,
+            &["ls-files", "--others", "--exclude-standard"],
+        )?);
+
+        let headers: Vec<String> = changed
+            .iter()
+            .filter(|f| !is_translation_unit(f))
+            .filter_map(|f| f.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .collect();
+
+        let db_path = self
+            .directory
+            .join(&self.build_dir)
+            .join("compile_commands.json");
+        let db = CompileDb::from_file(&db_path)?;
+        let mut sources = vec![];
+        for command in db.project_commands(&self.sources) {
+            let path = command.path();
+            let touched = changed.contains(&path)
+                || (!headers.is_empty()
+                    && includes_any(&fs::read_to_string(&path).unwrap_or_default(), &headers));
+            if touched {
+                sources.push(path);
+            }
+        }
+        Ok(sources)
+    }
+}
+
+/// The compile command turned into a syntax-only check, without outputs or dependency files.
+pub fn syntax_only_args(args: &[String]) -> Vec<String> {
+    let mut result = vec![];
+    let mut skip_next = false;
+
+    for arg in args {
+        if skip_next {
+            skip_next = false;
+            continue;
+        }
+        if WITH_VALUE.contains(&arg.as_str()) {
+            skip_next = true;
+            continue;
+        }
+        // `-ofile` and `-MFfile` spellings.
+        let joined = WITH_VALUE
+            .iter()
+            .any(|a| arg.len() > a.len() && arg.starts_with(a));
+        if OUTPUT_ARGS.contains(&arg.as_str()) || joined {
+            continue;
+        }
+        result.push(arg.clone());
+    }
+
+    result.push("-fsyntax-only".to_string());
+    result
+}
+
+/// Whether `source` has an `#include` of a file named like one of `headers`.
+fn includes_any(source: &str, headers: &[String]) -> bool {
+    source
+        .lines()
+        .filter_map(|l| l.trim_start().strip_prefix('#'))
+        .filter_map(|l| l.trim_start().strip_prefix("include"))
+        .filter_map(|l| l.trim().get(1..))
+        .filter_map(|l| l.split(['"', '>']).next())
+        .filter_map(|include| Path::new(include).file_name())
+        .any(|name| headers.iter().any(|h| name.to_string_lossy() == *h))
+}
+
+fn is_translation_unit(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ["c", "cc", "cpp", "cxx"].contains(&e))
+}
+
+/// Paths printed by `git <args>`, made absolute.
+fn git_files(directory: &Path, args: &[&str]) -> Result<Vec<PathBuf>> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(directory)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(error!(
+            CustomError,
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| normalize(&directory.join(l)))
+        .collect())
+}
+
+/// Prints the diagnostics grouped by file, then a one-line summary.
+pub fn print_summary(checked: usize, diagnostics: &[Diagnostic]) {
+    let mut current: Option<&Path> = None;
+    for d in diagnostics {
+        if current != Some(d.file.as_path()) {
+            println!("\n{}", d.file.display());
+            current = Some(d.file.as_path());
+        }
+        let severity = match d.severity {
+            Severity::Error => Red.paint(d.severity.as_str()),
+            _ => Yellow.paint(d.severity.as_str()),
+        };
+        let check = d.check.as_deref().map(|c| format!(" [{}]", c));
+        println!(
+            "  {}:{}: {}: {}{}",
+            d.line,
+            d.column,
+            severity,
+            d.message,
+            check.unwrap_or_default()
+        );
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let summary = format!(
+        "{} error(s), {} warning(s) in {} file(s) checked",
+        errors,
+        diagnostics.len() - errors,
+        checked
+    );
+    match errors {
+        0 => println!("\ncheck: {}\n", Green.paint(summary)),
+        _ => println!("\ncheck: {}\n", Red.paint(summary)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::env;
+
+    #[test]
+    fn test_syntax_only_args() {
+        let args: Vec<String> = "cc -Iinclude -DX=1 -MMD -MP -MF obj/a.d -o obj/a.o -c src/a.c"
+            .split(' ')
+            .map(String::from)
+            .collect();
+
+        // Test
+        let actual = syntax_only_args(&args);
+
+        // Validate
+        assert_eq!(
+            actual,
+            vec!["cc", "-Iinclude", "-DX=1", "src/a.c", "-fsyntax-only"]
+        );
+        assert!(includes_any(
+            "#include \"net/socket.h\"\n",
+            &["socket.h".to_string()]
+        ));
+        assert!(includes_any(
+            "  #  include <lib.h>\n",
+            &["lib.h".to_string()]
+        ));
+        assert!(!includes_any(
+            "#include \"lib.hpp\"\n",
+            &["lib.h".to_string()]
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_changed() -> anyhow::Result<()> {
+        let path = env::current_dir()?.join("dummy");
+        fs::create_dir_all(path.join("src"))?;
+        fs::create_dir_all(path.join("include"))?;
+        fs::create_dir_all(path.join("build"))?;
+        fs::write(
+            path.join("include").join("lib.h"),
+            "int add(int a, int b);\n",
+        )?;
+        fs::write(
+            path.join("src").join("ok.c"),
+            "int ok(void) { return 0; }\n",
+        )?;
+        fs::write(
+            path.join("src").join("lib.c"),
+            "#include \"lib.h\"\nint add(int a, int b) { return a + b }\n",
+        )?;
+        let commands: Vec<String> = ["ok.c", "lib.c"]
+            .iter()
+            .map(|f| {
+                format!(
+                    "{{\"directory\": \"{}\", \"command\": \"gcc -Iinclude -o build/{}.o -c src/{}\", \"file\": \"src/{}\"}}",
+                    path.display(),
+                    f,
+                    f,
+                    f
+                )
+            })
+            .collect();
+        fs::write(
+            path.join("build").join("compile_commands.json"),
+            format!("[{}]", commands.join(",")),
+        )?;
+        for args in [
+            vec!["init", "-q"],
+            vec!["add", "src", "include"],
+            vec![
+                "-c",
+                "user.name=forge",
+                "-c",
+                "user.email=forge@localhost",
+                "commit",
+                "-qm",
+                "init",
+            ],
+        ] {
+            Command::new("git").args(args).current_dir(&path).status()?;
+        }
+        fs::write(
+            path.join("include").join("lib.h"),
+            "int add(int a, int b);\n\n",
+        )?;
+
+        // Test
+        let sources = ["ok.c", "lib.c"].map(|f| path.join("src").join(f)).to_vec();
+        let checker = Checker::new(path.clone(), PathBuf::from("build"), sources);
+        let changed = checker.changed_sources()?;
+        let (checked, diagnostics) = checker.check(Some(&changed), 2)?;
+
+        // Validate
+        assert_eq!(changed, vec![path.join("src").join("lib.c")]);
+        assert_eq!(checked, 1);
+        assert_eq!(diagnostics[0].file, PathBuf::from("src/lib.c"));
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(!path.join("build").join("lib.c.o").exists());
+
+        // Clean-up
+        fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+}
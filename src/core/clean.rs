@@ -0,0 +1,106 @@
+use super::compile_db::normalize;
+use super::profile::Profile;
+use crate::{error, Error, Result};
+use std::fs;
+use std::path::Path;
+
+/// What `forge clean` removes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CleanScope {
+    /// Every build tree and the clangd cache.
+    Build,
+    /// What was built in one profile's tree, using the backend's clean target.
+    Profile(Profile),
+    /// The objects and output of one target, in every tree.
+    Target(String),
+    /// Dependencies installed by the package manager into the project.
+    Deps,
+    /// Everything above.
+    All,
+}
+
+/// Size in bytes of a file, or of everything below a directory. Symlinks aren't followed.
+pub fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_usage(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Removes a file, symlink or directory.
+pub fn remove(path: &Path) -> Result<()> {
+    match fs::symlink_metadata(path)?.is_dir() {
+        true => fs::remove_dir_all(path)?,
+        false => fs::remove_file(path)?,
+    }
+    Ok(())
+}
+
+/// Fails unless `path` is strictly inside the project at `root`, so a bad path never removes
+/// anything else.
+pub fn check_inside(root: &Path, path: &Path) -> Result<()> {
+    let inside = normalize(path)
+        .strip_prefix(normalize(root))
+        .is_ok_and(|p| !p.as_os_str().is_empty());
+    match inside {
+        true => Ok(()),
+        false => Err(error!(
+            CustomError,
+            "Refusing to remove {}, it is outside the project at {}",
+            path.display(),
+            root.display()
+        )),
+    }
+}
+
+/// Human readable size, e.g. `12.3 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        // Validate
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_check_inside() {
+        let root = Path::new("/home/dev/dummy");
+
+        // Validate
+        assert!(check_inside(root, &root.join("build")).is_ok());
+        assert!(check_inside(root, &root.join("build/../vcpkg_installed")).is_ok());
+        assert!(check_inside(root, root).is_err());
+        assert!(check_inside(root, &root.join("..")).is_err());
+        assert!(check_inside(root, Path::new("/home/dev/other/build")).is_err());
+    }
+}
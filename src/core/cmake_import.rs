@@ -0,0 +1,711 @@
+use super::compiler::CompilerFamily;
+use super::language::{CStandard, CppStandard, Language};
+use super::targets::{TargetConfig, TargetsConfig};
+use super::test_framework::TestFrameworks;
+use super::version::Version;
+use crate::{error, Error, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use which::which;
+
+/// File-api objects requested from CMake.
+const QUERIES: [&str; 3] = ["codemodel-v2", "cmakeFiles-v1", "toolchains-v1"];
+
+/// Modules shipped with CMake that look like find modules but aren't packages.
+const HELPER_MODULES: [&str; 2] = ["PackageHandleStandardArgs", "PackageMessage"];
+
+/// What `forge init --import` learned about an existing CMake project.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CMakeProject {
+    pub name: String,
+    pub version: Version,
+    pub language: Option<Language>,
+    pub compiler: Option<String>,
+    pub targets: TargetsConfig,
+    /// Packages loaded with `find_package`, by module or config file name.
+    pub dependencies: Vec<String>,
+    /// Libraries after the first, which forge can't represent.
+    pub skipped: Vec<String>,
+}
+
+impl CMakeProject {
+    /// Configures the project at `directory` in a scratch build directory and reads what CMake
+    /// reports about it. The project's own files are left untouched.
+    pub fn import(directory: &Path) -> Result<CMakeProject> {
+        let cmake_lists = directory.join("CMakeLists.txt");
+        if !cmake_lists.is_file() {
+            return Err(error!(
+                CustomError,
+                "no CMakeLists.txt in {}",
+                directory.display()
+            ));
+        }
+        which("cmake").map_err(|_| error!(CustomError, "cmake is not installed"))?;
+
+        let scratch = env::temp_dir().join(format!("forge-import-{}", process::id()));
+        write_queries(&scratch)?;
+        let output = Command::new("cmake")
+            .arg("-S")
+            .arg(directory)
+            .arg("-B")
+            .arg(&scratch)
+            .output()?;
+        let project = match output.status.success() {
+            true => CMakeProject::from_reply(&scratch),
+            false => Err(error!(
+                CustomError,
+                "cmake failed to configure {}:\n{}",
+                directory.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        };
+        fs::remove_dir_all(&scratch)?;
+
+        let mut project = project?;
+        if let Some(version) = project_version(&fs::read_to_string(cmake_lists)?) {
+            project.version = version;
+        }
+        Ok(project)
+    }
+
+    /// Reads the file-api reply in `build_dir`.
+    pub fn from_reply(build_dir: &Path) -> Result<CMakeProject> {
+        let reply_dir = build_dir.join(".cmake/api/v1/reply");
+        let index = fs::read_dir(&reply_dir)
+            .map_err(|_| error!(CustomError, "cmake wrote no file-api reply"))?
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("index-") && name.ends_with(".json"))
+            .max()
+            .ok_or_else(|| error!(CustomError, "cmake wrote no file-api reply"))?;
+        let index: ReplyIndex = read_json(&reply_dir.join(index))?;
+        let object = |kind: &str| {
+            index
+                .objects
+                .iter()
+                .find(|o| o.kind == kind)
+                .map(|o| reply_dir.join(&o.json_file))
+                .ok_or_else(|| error!(CustomError, "the file-api reply has no {} object", kind))
+        };
+
+        let codemodel: Codemodel = read_json(&object("codemodel")?)?;
+        let Some(configuration) = codemodel.configurations.first() else {
+            return Err(error!(CustomError, "the codemodel has no configuration"));
+        };
+        let source_dir = PathBuf::from(&codemodel.paths.source);
+        let mut targets = vec![];
+        for target in &configuration.targets {
+            targets.push(read_json::<TargetObject>(
+                &reply_dir.join(&target.json_file),
+            )?);
+        }
+
+        let mut project = CMakeProject {
+            name: configuration
+                .projects
+                .first()
+                .map(|p| p.name.clone())
+                .unwrap_or_default(),
+            language: language(&targets),
+            ..CMakeProject::default()
+        };
+        project.add_targets(&targets, &source_dir);
+
+        let cmake_files: CMakeFiles = read_json(&object("cmakeFiles")?)?;
+        for input in cmake_files.inputs.iter().filter(|i| i.is_external) {
+            if let Some(package) = package_name(&input.path) {
+                if !project.dependencies.contains(&package) {
+                    project.dependencies.push(package);
+                }
+            }
+        }
+
+        let toolchains: Toolchains = read_json(&object("toolchains")?)?;
+        let main_language = match project.language {
+            Some(Language::Cpp(_)) => "CXX",
+            _ => "C",
+        };
+        project.compiler = toolchains
+            .toolchains
+            .into_iter()
+            .find(|t| t.language == main_language)
+            .and_then(|t| t.compiler.path);
+
+        Ok(project)
+    }
+
+    /// Maps the library and executable targets to Forge.toml tables. Executables are tests or
+    /// benchmarks by name or location and examples by location, everything else is a binary.
+    fn add_targets(&mut self, targets: &[TargetObject], source_dir: &Path) {
+        let names: HashMap<&str, &str> = targets
+            .iter()
+            .filter(|t| t.kind() != TargetType::Other)
+            .map(|t| (t.id.as_str(), t.name.as_str()))
+            .collect();
+
+        for target in targets {
+            let kind = target.kind();
+            if kind == TargetType::Other {
+                continue;
+            }
+            let config = target.config(source_dir, &names);
+            let is = |word: &str, dirs: &[&str]| {
+                has_word(&target.name, word)
+                    || config
+                        .sources
+                        .iter()
+                        .any(|s| dirs.iter().any(|d| s.starts_with(&format!("{}/", d))))
+            };
+            match kind {
+                TargetType::Library if self.targets.lib.is_none() => {
+                    self.targets.lib = Some(config)
+                }
+                TargetType::Library => self.skipped.push(target.name.clone()),
+                _ if is("test", &["test", "tests"]) => self.targets.tests.push(config),
+                _ if is("bench", &["bench", "benches"]) => self.targets.benches.push(config),
+                _ if config.sources.iter().any(|s| s.starts_with("example")) => {
+                    self.targets.examples.push(config)
+                }
+                _ => self.targets.bins.push(config),
+            }
+        }
+    }
+
+    /// The compiler CMake picked, or the system's default for the project's language.
+    pub fn compiler_path(&self) -> String {
+        let default = match self.language {
+            Some(Language::C(_)) => "cc",
+            _ => "c++",
+        };
+        self.compiler.clone().unwrap_or_else(|| default.to_string())
+    }
+
+    /// VS Code IntelliSense mode matching the compiler and this machine.
+    pub fn intellisense_mode(&self) -> String {
+        CompilerFamily::from_compiler(&self.compiler_path()).intellisense_mode()
+    }
+
+    /// The test framework the project finds, or the usual one for its language.
+    pub fn test_framework(&self) -> TestFrameworks {
+        for dependency in &self.dependencies {
+            match dependency.to_lowercase().as_str() {
+                "gtest" => return TestFrameworks::GTest,
+                "cmocka" => return TestFrameworks::CMocka,
+                "boost" => return TestFrameworks::Boost,
+                _ => (),
+            }
+        }
+        match self.language {
+            Some(Language::C(_)) => TestFrameworks::CMocka,
+            _ => TestFrameworks::GTest,
+        }
+    }
+}
+
+/// Whether one of the words of `name`, separated by `_` or `-`, is `word` or its plural. So
+/// `core_tests` is a test and `attestation` isn't.
+fn has_word(name: &str, word: &str) -> bool {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|part| part == word || part.strip_suffix('s') == Some(word))
+}
+
+/// Asks CMake to write the `QUERIES` objects the next time `build_dir` is configured.
+pub fn write_queries(build_dir: &Path) -> Result<()> {
+    let query_dir = build_dir.join(".cmake/api/v1/query");
+    fs::create_dir_all(&query_dir)?;
+    for query in QUERIES {
+        fs::write(query_dir.join(query), "")?;
+    }
+    Ok(())
+}
+
+/// Version from the `project(... VERSION x.y.z ...)` line, padded to three parts.
+pub fn project_version(contents: &str) -> Option<Version> {
+    let line = contents
+        .lines()
+        .find(|l| l.trim_start().to_lowercase().starts_with("project("))?;
+    let start = line.find(" VERSION ")? + " VERSION ".len();
+    let version: Vec<&str> = line[start..]
+        .split(|c: char| c.is_whitespace() || c == ')')
+        .next()?
+        .split('.')
+        .collect();
+    let mut parts = version.iter().take(3).map(|p| p.parse::<u64>().ok());
+    Some(Version::new(
+        parts.next()??,
+        parts.next().unwrap_or(Some(0))?,
+        parts.next().unwrap_or(Some(0))?,
+    ))
+}
+
+/// Package of a `Find<Name>.cmake` module or a `<Name>Config.cmake`/`<name>-config.cmake` file.
+fn package_name(path: &str) -> Option<String> {
+    let file = Path::new(path).file_name()?.to_str()?;
+    let name = file
+        .strip_prefix("Find")
+        .and_then(|f| f.strip_suffix(".cmake"))
+        .filter(|name| !HELPER_MODULES.contains(name))
+        .or_else(|| file.strip_suffix("Config.cmake"))
+        .or_else(|| file.strip_suffix("-config.cmake"))?;
+    Some(name.to_string()).filter(|n| !n.is_empty())
+}
+
+/// C++ when any target compiles C++, with the newest standard any target asks for.
+fn language(targets: &[TargetObject]) -> Option<Language> {
+    let groups: Vec<&CompileGroup> = targets.iter().flat_map(|t| &t.compile_groups).collect();
+    let standard = |language: &str| {
+        groups
+            .iter()
+            .filter(|g| g.language == language)
+            .filter_map(|g| g.standard())
+            .max_by_key(|s| standard_year(s))
+    };
+
+    if groups.iter().any(|g| g.language == "CXX") {
+        let standard =
+            standard("CXX").map_or(CppStandard::Cpp17, |s| CppStandard::from_version(&s));
+        Some(Language::Cpp(standard))
+    } else if groups.iter().any(|g| g.language == "C") {
+        let standard = standard("C").map_or(CStandard::C11, |s| CStandard::from_version(&s));
+        Some(Language::C(standard))
+    } else {
+        None
+    }
+}
+
+/// Sort key for two-digit standards, where `98` and `89` come before `11`.
+fn standard_year(standard: &str) -> u32 {
+    let year: u32 = standard.parse().unwrap_or(0);
+    match year {
+        70.. => 1900 + year,
+        _ => 2000 + year,
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplyIndex {
+    objects: Vec<ReplyObject>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplyObject {
+    kind: String,
+    json_file: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Codemodel {
+    paths: SourcePaths,
+    configurations: Vec<Configuration>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcePaths {
+    source: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Configuration {
+    #[serde(default)]
+    projects: Vec<CodemodelProject>,
+    #[serde(default)]
+    targets: Vec<TargetRef>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CodemodelProject {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TargetRef {
+    json_file: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TargetType {
+    Library,
+    Executable,
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TargetObject {
+    name: String,
+    id: String,
+    #[serde(rename = "type")]
+    target_type: String,
+    #[serde(default)]
+    sources: Vec<Source>,
+    #[serde(default)]
+    compile_groups: Vec<CompileGroup>,
+    #[serde(default)]
+    dependencies: Vec<Dependency>,
+    #[serde(default)]
+    link: Option<Link>,
+    #[serde(default)]
+    artifacts: Vec<Artifact>,
+}
+
+impl TargetObject {
+    fn kind(&self) -> TargetType {
+        match self.target_type.as_str() {
+            "STATIC_LIBRARY" | "SHARED_LIBRARY" | "OBJECT_LIBRARY" => TargetType::Library,
+            "EXECUTABLE" => TargetType::Executable,
+            _ => TargetType::Other,
+        }
+    }
+
+    /// Forge.toml table for the target. Sources and include dirs outside the project, like
+    /// generated files and packages' headers, are left out.
+    fn config(&self, source_dir: &Path, names: &HashMap<&str, &str>) -> TargetConfig {
+        let mut config = TargetConfig {
+            name: self.name.clone(),
+            output: self.artifacts.first().map(|a| a.path.clone()),
+            ..TargetConfig::default()
+        };
+        config.sources = self
+            .sources
+            .iter()
+            .filter(|s| s.compile_group_index.is_some() && !s.is_generated)
+            .filter(|s| Path::new(&s.path).is_relative())
+            .map(|s| s.path.clone())
+            .collect();
+
+        for group in &self.compile_groups {
+            for include in &group.includes {
+                if let Ok(dir) = Path::new(&include.path).strip_prefix(source_dir) {
+                    push_unique(&mut config.include_dirs, dir.to_string_lossy().into_owned());
+                }
+            }
+            for define in &group.defines {
+                push_unique(&mut config.defines, define.define.clone());
+            }
+        }
+
+        for dependency in &self.dependencies {
+            if let Some(name) = names.get(dependency.id.as_str()) {
+                push_unique(&mut config.link, name.to_string());
+            }
+        }
+        let fragments = self.link.iter().flat_map(|l| &l.command_fragments);
+        for fragment in fragments.filter(|f| f.role.as_deref() == Some("libraries")) {
+            if let Some(lib) = fragment.fragment.trim().strip_prefix("-l") {
+                push_unique(&mut config.link, lib.to_string());
+            }
+        }
+        config
+    }
+}
+
+fn push_unique(values: &mut Vec<String>, value: String) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Source {
+    path: String,
+    #[serde(default)]
+    compile_group_index: Option<usize>,
+    #[serde(default)]
+    is_generated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompileGroup {
+    language: String,
+    #[serde(default)]
+    language_standard: Option<LanguageStandard>,
+    #[serde(default)]
+    compile_command_fragments: Vec<Fragment>,
+    #[serde(default)]
+    includes: Vec<Include>,
+    #[serde(default)]
+    defines: Vec<Define>,
+}
+
+impl CompileGroup {
+    /// Numeric standard, e.g. `17`, from the codemodel or else the `-std=` flag.
+    fn standard(&self) -> Option<String> {
+        if let Some(standard) = &self.language_standard {
+            return Some(standard.standard.clone());
+        }
+        self.compile_command_fragments
+            .iter()
+            .flat_map(|f| f.fragment.split_whitespace())
+            .find_map(|flag| flag.strip_prefix("-std="))
+            .map(|std| {
+                std.trim_start_matches(|c: char| !c.is_ascii_digit())
+                    .to_string()
+            })
+            .filter(|std| !std.is_empty())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LanguageStandard {
+    standard: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Fragment {
+    fragment: String,
+    #[serde(default)]
+    role: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Include {
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Define {
+    define: String,
+}
+
+/// File a target builds, relative to the build tree unless it is outside it.
+#[derive(Debug, Deserialize)]
+struct Artifact {
+    path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct Dependency {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Link {
+    #[serde(default)]
+    command_fragments: Vec<Fragment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CMakeFiles {
+    #[serde(default)]
+    inputs: Vec<CMakeInput>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CMakeInput {
+    path: String,
+    #[serde(default)]
+    is_external: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Toolchains {
+    #[serde(default)]
+    toolchains: Vec<Toolchain>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Toolchain {
+    language: String,
+    compiler: ToolchainCompiler,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolchainCompiler {
+    #[serde(default)]
+    path: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    fn write_reply(dir: &Path, file: &str, value: Value) -> anyhow::Result<()> {
+        fs::write(dir.join(file), serde_json::to_string(&value)?)?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_from_reply() -> anyhow::Result<()> {
+        let path = env::current_dir()?.join("dummy");
+        let source = path.to_string_lossy().into_owned();
+        let reply = path.join("build/.cmake/api/v1/reply");
+
+        // Set-up
+        fs::create_dir_all(&reply)?;
+        write_reply(
+            &reply,
+            "index-2024-01-01T00-00-00-0000.json",
+            json!({
+                "objects": [
+                    {"kind": "codemodel", "jsonFile": "codemodel.json"},
+                    {"kind": "cmakeFiles", "jsonFile": "cmakeFiles.json"},
+                    {"kind": "toolchains", "jsonFile": "toolchains.json"}
+                ]
+            }),
+        )?;
+        write_reply(
+            &reply,
+            "codemodel.json",
+            json!({
+                "paths": {"source": source, "build": format!("{}/build", source)},
+                "configurations": [{
+                    "projects": [{"name": "parser"}],
+                    "targets": [
+                        {"jsonFile": "core.json"},
+                        {"jsonFile": "tool.json"},
+                        {"jsonFile": "tests.json"},
+                        {"jsonFile": "attestation.json"},
+                        {"jsonFile": "docs.json"}
+                    ]
+                }]
+            }),
+        )?;
+        write_reply(
+            &reply,
+            "core.json",
+            json!({
+                "name": "core", "id": "core::@1", "type": "STATIC_LIBRARY",
+                "sources": [
+                    {"path": "src/core.cpp", "compileGroupIndex": 0},
+                    {"path": "include/core.hpp"},
+                    {"path": "/tmp/generated.cpp", "compileGroupIndex": 0, "isGenerated": true}
+                ],
+                "compileGroups": [{
+                    "language": "CXX",
+                    "languageStandard": {"standard": "17"},
+                    "includes": [
+                        {"path": format!("{}/include", source)},
+                        {"path": "/usr/include/fmt"}
+                    ],
+                    "defines": [{"define": "CORE_EXPORTS"}]
+                }]
+            }),
+        )?;
+        write_reply(
+            &reply,
+            "tool.json",
+            json!({
+                "name": "tool", "id": "tool::@1", "type": "EXECUTABLE",
+                "artifacts": [{"path": "tools/tool"}],
+                "sources": [{"path": "tools/main.cpp", "compileGroupIndex": 0}],
+                "compileGroups": [{
+                    "language": "CXX",
+                    "compileCommandFragments": [{"fragment": "-g -std=gnu++20"}]
+                }],
+                "dependencies": [{"id": "core::@1"}, {"id": "docs::@1"}],
+                "link": {"commandFragments": [
+                    {"fragment": "libcore.a", "role": "libraries"},
+                    {"fragment": "-lpthread", "role": "libraries"}
+                ]}
+            }),
+        )?;
+        write_reply(
+            &reply,
+            "tests.json",
+            json!({
+                "name": "core_tests", "id": "core_tests::@1", "type": "EXECUTABLE",
+                "sources": [{"path": "test/core_test.cpp", "compileGroupIndex": 0}],
+                "compileGroups": [{"language": "CXX"}],
+                "dependencies": [{"id": "core::@1"}]
+            }),
+        )?;
+        write_reply(
+            &reply,
+            "attestation.json",
+            json!({
+                "name": "attestation", "id": "attestation::@1", "type": "EXECUTABLE",
+                "sources": [{"path": "tools/attestation.cpp", "compileGroupIndex": 0}],
+                "compileGroups": [{"language": "CXX"}]
+            }),
+        )?;
+        write_reply(
+            &reply,
+            "docs.json",
+            json!({
+                "name": "docs", "id": "docs::@1", "type": "UTILITY"
+            }),
+        )?;
+        write_reply(
+            &reply,
+            "cmakeFiles.json",
+            json!({
+                "inputs": [
+                    {"path": "CMakeLists.txt"},
+                    {"path": "cmake/FindLocal.cmake"},
+                    {"path": "/usr/share/cmake/Modules/FindGTest.cmake", "isExternal": true, "isCMake": true},
+                    {"path": "/usr/share/cmake/Modules/FindPackageHandleStandardArgs.cmake", "isExternal": true, "isCMake": true},
+                    {"path": "/usr/lib/cmake/fmt/fmt-config.cmake", "isExternal": true},
+                    {"path": "/usr/lib/cmake/fmt/fmt-config-version.cmake", "isExternal": true}
+                ]
+            }),
+        )?;
+        write_reply(
+            &reply,
+            "toolchains.json",
+            json!({
+                "toolchains": [
+                    {"language": "C", "compiler": {"path": "/usr/bin/cc"}},
+                    {"language": "CXX", "compiler": {"path": "/usr/bin/c++"}}
+                ]
+            }),
+        )?;
+
+        // Test
+        let project = CMakeProject::from_reply(&path.join("build"))?;
+
+        // Validate
+        assert_eq!(project.name, "parser");
+        assert_eq!(project.language, Some(Language::Cpp(CppStandard::Cpp20)));
+        assert_eq!(project.compiler.as_deref(), Some("/usr/bin/c++"));
+        assert!(project.intellisense_mode().contains("-gcc-"));
+        let clang = CMakeProject {
+            compiler: Some("/usr/bin/clang++".to_string()),
+            ..CMakeProject::default()
+        };
+        assert!(clang.intellisense_mode().contains("-clang-"));
+        assert_eq!(project.dependencies, vec!["GTest", "fmt"]);
+        assert_eq!(project.test_framework(), TestFrameworks::GTest);
+        let lib = project.targets.lib.as_ref().unwrap();
+        assert_eq!(lib.sources, vec!["src/core.cpp"]);
+        assert_eq!(lib.include_dirs, vec!["include"]);
+        assert_eq!(lib.defines, vec!["CORE_EXPORTS"]);
+        assert_eq!(project.targets.bins.len(), 2);
+        assert_eq!(project.targets.bins[0].link, vec!["core", "pthread"]);
+        assert_eq!(
+            project.targets.bins[0].output,
+            Some(PathBuf::from("tools/tool"))
+        );
+        assert_eq!(project.targets.bins[1].name, "attestation");
+        assert_eq!(project.targets.tests.len(), 1);
+        assert_eq!(project.targets.tests[0].name, "core_tests");
+        assert_eq!(
+            project_version(
+                "cmake_minimum_required(VERSION 3.16)\nproject(parser VERSION 1.4 LANGUAGES CXX)\n"
+            ),
+            Some(Version::new(1, 4, 0))
+        );
+
+        // Clean-up
+        fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+}
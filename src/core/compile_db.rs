@@ -0,0 +1,195 @@
+use crate::{error, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// One entry of a `compile_commands.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CompileCommand {
+    pub directory: PathBuf,
+    pub file: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+}
+
+impl CompileCommand {
+    /// Absolute path of the translation unit.
+    pub fn path(&self) -> PathBuf {
+        normalize(&self.directory.join(&self.file))
+    }
+
+    /// The compiler invocation, split into arguments.
+    pub fn args(&self) -> Result<Vec<String>> {
+        if let Some(args) = &self.arguments {
+            return Ok(args.clone());
+        }
+
+        let command = self.command.as_deref().unwrap_or_default();
+        shlex::split(command).ok_or_else(|| {
+            error!(
+                CustomError,
+                "Can't parse the compile command for {}",
+                self.file.display()
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileDb {
+    pub commands: Vec<CompileCommand>,
+}
+
+impl CompileDb {
+    pub fn from_file(path: &Path) -> Result<CompileDb> {
+        if !path.exists() {
+            return Err(error!(
+                CustomError,
+                "{} does not exist, configure the project first",
+                path.display()
+            ));
+        }
+
+        let contents = fs::read_to_string(path)?;
+        CompileDb::from_json(&contents)
+    }
+
+    pub fn from_json(json: &str) -> Result<CompileDb> {
+        let commands: Vec<CompileCommand> = serde_json::from_str(json)?;
+        Ok(CompileDb { commands })
+    }
+
+    /// Entries for `sources`, the absolute paths of the project's translation units, skipping
+    /// dependencies and generated files. Each file appears once.
+    pub fn project_commands(&self, sources: &[PathBuf]) -> Vec<&CompileCommand> {
+        let mut seen = vec![];
+
+        self.commands
+            .iter()
+            .filter(|c| sources.contains(&c.path()))
+            .filter(|c| {
+                let path = c.path();
+                match seen.contains(&path) {
+                    true => false,
+                    false => {
+                        seen.push(path);
+                        true
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Resolves `.` and `..` without touching the filesystem, so paths from the database can be
+/// compared with the project directory.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+/// Number of workers used when the user doesn't pass `--jobs`.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Runs `f` over `items` on up to `jobs` threads. Results keep the order of `items`.
+pub fn run_parallel<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= items.len() {
+                    break;
+                }
+                let result = f(&items[i]);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_commands() -> anyhow::Result<()> {
+        let json = r#"[
+  {"directory": "/home/dev/dummy/build", "command": "/usr/bin/c++ -I/home/dev/dummy/include -o main.o -c /home/dev/dummy/src/main.cpp", "file": "/home/dev/dummy/src/main.cpp"},
+  {"directory": "/home/dev/dummy/build", "arguments": ["/usr/bin/c++", "-c", "../test/test_lib.cpp"], "file": "../test/test_lib.cpp"},
+  {"directory": "/home/dev/dummy/build", "command": "/usr/bin/c++ -c /home/dev/dummy/src/main.cpp", "file": "/home/dev/dummy/src/main.cpp"},
+  {"directory": "/home/dev/dummy/build", "command": "/usr/bin/c++ -c gtest-all.cc", "file": "/home/dev/dummy/build/_deps/gtest-all.cc"}
+]"#;
+        let db = CompileDb::from_json(json)?;
+
+        // Test
+        let sources =
+            ["src/main.cpp", "test/test_lib.cpp"].map(|s| Path::new("/home/dev/dummy").join(s));
+        let commands = db.project_commands(&sources);
+
+        // Validate
+        assert_eq!(commands.len(), 2);
+        assert_eq!(
+            commands[1].path(),
+            PathBuf::from("/home/dev/dummy/test/test_lib.cpp")
+        );
+        assert_eq!(
+            commands[0].args()?,
+            vec![
+                "/usr/bin/c++",
+                "-I/home/dev/dummy/include",
+                "-o",
+                "main.o",
+                "-c",
+                "/home/dev/dummy/src/main.cpp"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_parallel() {
+        let items: Vec<u32> = (0..50).collect();
+
+        // Test
+        let results = run_parallel(&items, 4, |i| i * 2);
+
+        // Validate
+        assert_eq!(results, (0..50).map(|i| i * 2).collect::<Vec<u32>>());
+    }
+}
@@ -1,6 +1,99 @@
+use super::language::Language;
+use crate::{error, Error, Result};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::{env, fs};
 use which::which;
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CompilerFamily {
+    Gcc,
+    Clang,
+}
+
+impl CompilerFamily {
+    /// Infers the family from the compiler's file name, e.g. `clang++` or `/usr/bin/gcc-13`.
+    pub fn from_compiler(compiler: &str) -> CompilerFamily {
+        let name = Path::new(compiler)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(compiler);
+
+        if name.contains("clang") {
+            CompilerFamily::Clang
+        } else {
+            CompilerFamily::Gcc
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompilerFamily::Gcc => "gcc",
+            CompilerFamily::Clang => "clang",
+        }
+    }
+
+    /// VS Code's `intelliSenseMode` for this family on the host, e.g. `linux-gcc-x64`.
+    pub fn intellisense_mode(&self) -> String {
+        let os = match env::consts::OS {
+            "macos" => "macos",
+            "windows" => "windows",
+            _ => "linux",
+        };
+        let arch = match env::consts::ARCH {
+            "x86" => "x86",
+            "aarch64" => "arm64",
+            "arm" => "arm",
+            _ => "x64",
+        };
+        format!("{}-{}-{}", os, self.as_str(), arch)
+    }
+
+    /// Flag making the compiler print its diagnostics as JSON. clang has no equivalent, its
+    /// text output is parsed instead.
+    pub fn json_diagnostics_flag(&self) -> Option<&'static str> {
+        match self {
+            CompilerFamily::Gcc => Some("-fdiagnostics-format=json"),
+            CompilerFamily::Clang => None,
+        }
+    }
+}
+
+/// Compiles and links an empty program with `flags`. Returns the compiler's errors when it
+/// can't.
+pub fn probe(compiler: &str, language: &Language, flags: &[String]) -> Result<Option<String>> {
+    let lang = match language {
+        Language::C(_) => "c",
+        Language::Cpp(_) => "c++",
+    };
+    let output = env::temp_dir().join(format!("forge-probe-{}", std::process::id()));
+
+    let mut probe = Command::new(compiler)
+        .args(["-x", lang, "-"])
+        .args(flags)
+        .arg("-o")
+        .arg(&output)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| error!(CustomError, "Failed to run {}: {}", compiler, e))?;
+
+    if let Some(mut stdin) = probe.stdin.take() {
+        stdin.write_all(b"int main(void) { return 0; }\n")?;
+    }
+    let result = probe.wait_with_output()?;
+    let _ = fs::remove_file(&output);
+
+    match result.status.success() {
+        true => Ok(None),
+        false => Ok(Some(String::from_utf8_lossy(&result.stderr).into_owned())),
+    }
+}
+
 pub fn detect_compilers() -> HashMap<String, String> {
     let options = ["clang", "clang++", "gcc", "g++"];
 
@@ -19,6 +112,26 @@ mod tests {
     use super::*;
     use which::which;
 
+    #[test]
+    fn test_compiler_family_from_compiler() {
+        assert_eq!(
+            CompilerFamily::from_compiler("clang"),
+            CompilerFamily::Clang
+        );
+        assert_eq!(
+            CompilerFamily::from_compiler("/usr/bin/clang++-18"),
+            CompilerFamily::Clang
+        );
+        assert_eq!(CompilerFamily::from_compiler("g++"), CompilerFamily::Gcc);
+        assert_eq!(
+            CompilerFamily::from_compiler("/usr/local/bin/gcc-13"),
+            CompilerFamily::Gcc
+        );
+        assert!(CompilerFamily::Clang
+            .intellisense_mode()
+            .contains("-clang-"));
+    }
+
     #[test]
     fn test_detect_compilers() {
         let options = ["clang", "clang++", "gcc", "g++"];
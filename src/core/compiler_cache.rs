@@ -0,0 +1,186 @@
+use crate::{error, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use which::which;
+
+/// Compiler launcher caching object files between builds, `[build] cache` in Forge.toml.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompilerCache {
+    Ccache,
+    Sccache,
+}
+
+impl CompilerCache {
+    pub fn variants() -> Vec<&'static str> {
+        vec!["ccache", "sccache"]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompilerCache::Ccache => "ccache",
+            CompilerCache::Sccache => "sccache",
+        }
+    }
+
+    /// Location of the executable, or an error when it isn't installed.
+    pub fn path(&self) -> Result<PathBuf> {
+        which(self.as_str()).map_err(|_| error!(CustomError, "{} is not installed", self.as_str()))
+    }
+
+    /// Hits and misses since the statistics were last cleared.
+    pub fn stats(&self) -> Result<CacheStats> {
+        let args: &[&str] = match self {
+            CompilerCache::Ccache => &["--print-stats"],
+            CompilerCache::Sccache => &["--show-stats", "--stats-format=json"],
+        };
+        let output = Command::new(self.path()?).args(args).output()?;
+        if !output.status.success() {
+            return Err(error!(
+                CustomError,
+                "{} failed: {}",
+                self.as_str(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match self {
+            CompilerCache::Ccache => Ok(CacheStats::from_ccache(&stdout)),
+            CompilerCache::Sccache => CacheStats::from_sccache(&stdout),
+        }
+    }
+
+    /// Empties the cache and resets its statistics. sccache has no command for it, so its
+    /// server is stopped and the local cache directory removed.
+    pub fn clear(&self) -> Result<()> {
+        let path = self.path()?;
+        match self {
+            CompilerCache::Ccache => {
+                for arg in ["--clear", "--zero-stats"] {
+                    Command::new(&path).arg(arg).status()?;
+                }
+            }
+            CompilerCache::Sccache => {
+                // Fails when no server is running, which is fine.
+                Command::new(&path).arg("--stop-server").output()?;
+                if let Some(dir) = sccache_dir() {
+                    if dir.exists() {
+                        fs::remove_dir_all(dir)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Local sccache directory, `SCCACHE_DIR` or the default under the user's cache directory.
+fn sccache_dir() -> Option<PathBuf> {
+    if let Ok(dir) = env::var("SCCACHE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let cache = env::var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|h| PathBuf::from(h).join(".cache")))
+        .ok()?;
+    Some(cache.join("sccache"))
+}
+
+pub fn detect_caches() -> HashMap<String, String> {
+    CompilerCache::variants()
+        .iter()
+        .filter_map(|&o| {
+            which(o)
+                .ok()
+                .map(|p| (o.to_string(), p.to_string_lossy().into_owned()))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Parses `ccache --print-stats`, one `key<TAB>value` pair per line.
+    pub fn from_ccache(output: &str) -> CacheStats {
+        let mut stats = CacheStats::default();
+        for line in output.lines() {
+            let mut parts = line.split('\t');
+            let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let value: u64 = value.trim().parse().unwrap_or(0);
+            match key {
+                "direct_cache_hit" | "preprocessed_cache_hit" => stats.hits += value,
+                "cache_miss" => stats.misses += value,
+                _ => (),
+            }
+        }
+        stats
+    }
+
+    /// Parses `sccache --show-stats --stats-format=json`, summing the counts of every language.
+    pub fn from_sccache(json: &str) -> Result<CacheStats> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let sum = |key: &str| -> u64 {
+            value["stats"][key]["counts"]
+                .as_object()
+                .map(|counts| counts.values().filter_map(|c| c.as_u64()).sum())
+                .unwrap_or(0)
+        };
+        Ok(CacheStats {
+            hits: sum("cache_hits"),
+            misses: sum("cache_misses"),
+        })
+    }
+
+    /// Percentage of cacheable compilations served from the cache.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 * 100.0 / total as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stats() -> anyhow::Result<()> {
+        let ccache = "stats_updated_timestamp\t1700000000\n\
+                      direct_cache_hit\t30\n\
+                      preprocessed_cache_hit\t10\n\
+                      cache_miss\t10\n\
+                      called_for_link\t4\n";
+        let sccache = r#"{"stats": {"compile_requests": 12, "cache_hits": {"counts": {"C/C++": 9}, "adv_counts": {}}, "cache_misses": {"counts": {"C/C++": 3}, "adv_counts": {}}}}"#;
+
+        // Test
+        let ccache = CacheStats::from_ccache(ccache);
+        let sccache = CacheStats::from_sccache(sccache)?;
+
+        // Validate
+        assert_eq!(
+            ccache,
+            CacheStats {
+                hits: 40,
+                misses: 10
+            }
+        );
+        assert_eq!(ccache.hit_rate(), 80.0);
+        assert_eq!(sccache, CacheStats { hits: 9, misses: 3 });
+        assert_eq!(sccache.hit_rate(), 75.0);
+        assert_eq!(CacheStats::default().hit_rate(), 0.0);
+
+        Ok(())
+    }
+}
@@ -1,18 +1,79 @@
-use super::build_system::{BuildSystem, BuildSystems};
+use super::bench::{self, BenchFramework, BenchReport, BenchRun, BENCH_DIR};
+use super::build_system::{BuildSystem, BuildSystems, BUILD_DIR};
+use super::build_tree::BuildTree;
+use super::checker::{self, Checker};
+use super::clean::{self, CleanScope};
+use super::cmake_import::CMakeProject;
+use super::compile_db::normalize;
+use super::compiler::CompilerFamily;
+use super::compiler_cache::{self, CompilerCache};
+use super::coverage::{Coverage, CoverageReport, COVERAGE_DIR};
+use super::diagnostics::{self, BuildMessage, MessageFormat, Severity};
+use super::doc::{self, Doc, DOXYFILE};
+use super::init_plan::InitPlan;
 use super::language::Language;
+use super::linter::{self, LintBackend, LintPreset, Linter};
+use super::memcheck::{Memcheck, MemcheckConfig};
 use super::package_manager::{PackageManager, PackageManagers};
+use super::packager::PackageFormat;
+use super::profile::{Profile, ProfilesConfig};
+use super::regen;
+use super::sanitizer::SanitizerSet;
 use super::scaffolder::Scaffolder;
+use super::targets::{Target, TargetKind, Targets, TargetsConfig};
 use super::test_framework::{TestFramework, TestFrameworks};
-use crate::Result;
+use super::test_report::{TestReport, TestStatus};
+use super::test_selection::{TestRun, TestSelection};
+use super::timings::{TimingReport, TimingSource, TIMINGS_DIR, WRAPPER_LOG};
+use super::version::{self, Version, VersionPart, VERSION_HEADER};
+use super::warnings::WarningsConfig;
+use crate::{error, Error, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Instant;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ProjectConfig {
-    name: String,
-    language: Language,
+    pub name: String,
+    pub language: Language,
+    #[serde(default)]
+    pub version: Version,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    #[serde(default)]
+    pub maintainer: String,
+    #[serde(default)]
+    pub license: String,
+    #[serde(default)]
+    pub homepage: String,
+}
+
+impl ProjectConfig {
+    pub fn new(name: String, language: Language) -> ProjectConfig {
+        ProjectConfig {
+            name,
+            language,
+            version: Version::default(),
+            description: String::new(),
+            authors: vec![],
+            maintainer: String::new(),
+            license: String::new(),
+            homepage: String::new(),
+        }
+    }
+
+    /// Package maintainer, falling back to the first author and then the project name.
+    pub fn maintainer(&self) -> &str {
+        match (self.maintainer.is_empty(), self.authors.first()) {
+            (false, _) => &self.maintainer,
+            (true, Some(author)) => author,
+            (true, None) => &self.name,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -24,11 +85,43 @@ pub struct ToolsConfig {
     intellisense_mode: String,
 }
 
+/// `[build]` section of Forge.toml.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BuildConfig {
+    #[serde(default)]
+    pub warnings: WarningsConfig,
+    /// Compiler launcher caching objects between builds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CompilerCache>,
+    /// Headers precompiled for every project target, either system headers like `<vector>` or
+    /// paths relative to the project directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pch: Vec<String>,
+    /// Compile each target's sources as a few combined translation units.
+    #[serde(default)]
+    pub unity: bool,
+    /// The build file is the user's, e.g. in an imported project, and forge never regenerates it.
+    #[serde(default)]
+    pub keep_build_file: bool,
+    /// `[build.debug]` and `[build.release]`.
+    #[serde(flatten)]
+    pub profiles: ProfilesConfig,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ForgeConfig {
     directory: PathBuf,
     project: ProjectConfig,
     tools: ToolsConfig,
+    #[serde(default)]
+    build: BuildConfig,
+    #[serde(default)]
+    memcheck: MemcheckConfig,
+    /// Packages the project finds with `find_package`, recorded by `forge init --import`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<String>,
+    #[serde(flatten)]
+    targets: TargetsConfig,
 }
 
 impl ForgeConfig {
@@ -46,10 +139,7 @@ impl ForgeConfig {
 
         ForgeConfig {
             directory: directory.clone(),
-            project: ProjectConfig {
-                name: name.clone(),
-                language: language.clone(),
-            },
+            project: ProjectConfig::new(name.clone(), language.clone()),
             tools: ToolsConfig {
                 compiler_path,
                 test_framework: test_framework.clone(),
@@ -68,85 +158,1030 @@ impl ForgeConfig {
                 ),
                 intellisense_mode,
             },
+            build: BuildConfig::default(),
+            memcheck: MemcheckConfig::default(),
+            dependencies: vec![],
+            targets: TargetsConfig::default(),
+        }
+    }
+
+    /// Config for the existing CMake project at `directory`, from what CMake reports about its
+    /// targets, standard and packages. The build file is kept as it is.
+    pub fn import(directory: PathBuf) -> Result<ForgeConfig> {
+        let imported = CMakeProject::import(&directory)?;
+        let Some(language) = imported.language.clone() else {
+            return Err(error!(
+                CustomError,
+                "{} has no C or C++ targets to import",
+                directory.display()
+            ));
+        };
+        let package_manager = match ["conanfile.txt", "conanfile.py"]
+            .iter()
+            .any(|f| directory.join(f).exists())
+        {
+            true => PackageManagers::Conan,
+            false => PackageManagers::Vcpkg,
+        };
+        let name = match imported.name.is_empty() {
+            true => directory
+                .file_name()
+                .map_or("default".to_string(), |n| n.to_string_lossy().into_owned()),
+            false => imported.name.clone(),
+        };
+        for library in &imported.skipped {
+            println!(
+                "Skipped library {}, forge projects have a single library",
+                library
+            );
         }
+
+        let mut config = ForgeConfig::new(
+            name,
+            directory,
+            language,
+            imported.compiler_path(),
+            BuildSystems::CMake,
+            package_manager,
+            imported.test_framework(),
+            imported.intellisense_mode(),
+        );
+        config.project.version = imported.version;
+        config.build.keep_build_file = true;
+        config.dependencies = imported.dependencies;
+        config.targets = imported.targets;
+        Ok(config)
     }
 
     pub fn from_file() -> Result<ForgeConfig> {
         let contents = fs::read_to_string("Forge.toml")?;
-        let config: ForgeConfig = toml::from_str(&contents)?;
+        let mut config: ForgeConfig = toml::from_str(&contents)?;
+        // Forge.toml records where the project was created, a moved or cloned checkout is
+        // somewhere else. The project is wherever its Forge.toml is.
+        config.relocate(&std::env::current_dir()?);
+        config.tools.build_system.sync(&config.project);
         Ok(config)
     }
 
+    fn relocate(&mut self, directory: &Path) {
+        self.directory = directory.to_path_buf();
+        self.tools.test_framework.relocate(directory);
+        self.tools.package_manager.relocate(directory);
+        self.tools.build_system.relocate(directory);
+    }
+
     pub fn to_file(&self) -> Result<()> {
         let toml_str = toml::to_string_pretty(self)?;
         fs::write(self.directory.join("Forge.toml"), toml_str)?;
         Ok(())
     }
 
-    pub fn init(&self) -> Result<()> {
-        let scaffolder = Scaffolder::new(
+    fn scaffolder(&self) -> Scaffolder {
+        Scaffolder::new(
             self.project.name.clone(),
             self.directory.clone(),
             self.project.language.clone(),
-        );
+        )
+    }
+
+    /// Files written by `init`, relative to the project directory.
+    pub fn init_files(&self) -> Vec<PathBuf> {
+        let mut files = self.scaffolder().files();
+        files.push(PathBuf::from(VERSION_HEADER));
+        files.extend(self.tools.package_manager.manifest_files());
+        files.push(PathBuf::from("Forge.toml"));
+        files.extend(self.tools.build_system.init_files());
+        files
+    }
 
-        scaffolder.build()?;
-        self.tools.package_manager.init()?;
-        self.tools.package_manager.config()?;
-        self.tools.build_system.init()?;
+    /// Scaffolds the project and writes its files, leaving the ones `plan` keeps alone.
+    pub fn init(&self, plan: &InitPlan) -> Result<()> {
+        self.scaffolder().build(plan)?;
+        if plan.writes(Path::new(VERSION_HEADER)) {
+            version::write_header(&self.directory, &self.project)?;
+        }
+        let package_manager = &self.tools.package_manager;
+        if package_manager
+            .manifest_files()
+            .iter()
+            .all(|f| plan.writes(f))
+        {
+            package_manager.init()?;
+            package_manager.config()?;
+        }
+        // Forge.toml goes first so the build file isn't immediately stale.
+        if plan.writes(Path::new("Forge.toml")) {
+            self.to_file()?;
+        }
+        let build_system = &self.tools.build_system;
+        let generated = build_system.generate(
+            &self.project,
+            &self.targets()?,
+            &self.tools.compiler_path,
+            &self.build,
+        )?;
+        for (file, contents) in generated.iter().filter(|(f, _)| plan.writes(f)) {
+            build_system.write_file(file, contents)?;
+        }
         // self.tools.build_system.config()?;
+
+        Ok(())
+    }
+
+    /// Rewrites the build file and `version.h` from Forge.toml, keeping the user's guarded
+    /// regions. A build file without regions is only replaced with `force`, after a backup.
+    pub fn regen(&self, force: bool) -> Result<()> {
+        let build_file = self.directory.join(self.tools.build_system.build_file());
+        if self.build.keep_build_file {
+            return Err(error!(
+                CustomError,
+                "Forge.toml sets keep_build_file, so forge doesn't generate {}",
+                build_file.display()
+            ));
+        }
+
+        if let Ok(contents) = fs::read_to_string(&build_file) {
+            if !regen::is_managed(&contents) {
+                let backup = build_file.with_extension("bak");
+                if !force {
+                    return Err(error!(
+                        CustomError,
+                        "{} has no forge:begin/forge:end regions, pass --force to replace it \
+                         (the old file is kept as {})",
+                        build_file.display(),
+                        backup.display()
+                    ));
+                }
+                fs::copy(&build_file, &backup)?;
+                println!("Backed up {} to {}", build_file.display(), backup.display());
+            }
+        }
+
+        version::write_header(&self.directory, &self.project)?;
+        self.tools.build_system.init(
+            &self.project,
+            &self.targets()?,
+            &self.tools.compiler_path,
+            &self.build,
+        )
+    }
+
+    /// The configured targets plus the ones discovered from the project layout. A build file
+    /// forge doesn't generate only has the configured ones.
+    pub fn targets(&self) -> Result<Targets> {
+        match self.build.keep_build_file {
+            true => Targets::configured(&self.directory, &self.project, &self.targets),
+            false => Targets::resolve(&self.directory, &self.project, &self.targets),
+        }
+    }
+
+    /// Target run by `forge run`: the binary `bin`, the example `example`, or else the project's
+    /// default binary.
+    pub fn run_target(&self, bin: Option<&str>, example: Option<&str>) -> Result<Target> {
+        let targets = self.targets()?;
+        let (kind, name, candidates) = match (bin, example) {
+            (_, Some(e)) => (TargetKind::Example, Some(e), &targets.examples),
+            (b, None) => (TargetKind::Bin, b, &targets.bins),
+        };
+
+        let target =
+            match name {
+                Some(n) => candidates.iter().find(|t| t.name == n),
+                None => candidates.iter().find(|t| t.name == self.project.name).or(
+                    match candidates.len() {
+                        1 => candidates.first(),
+                        _ => None,
+                    },
+                ),
+            };
+        if let Some(t) = target {
+            return Ok(t.clone());
+        }
+
+        let names: Vec<&str> = candidates.iter().map(|t| t.name.as_str()).collect();
+        let available = match names.is_empty() {
+            true => "none".to_string(),
+            false => names.join(", "),
+        };
+        Err(match name {
+            Some(n) => error!(
+                CustomError,
+                "No {} named `{}`, available: {}",
+                kind.as_str(),
+                n,
+                available
+            ),
+            None => error!(
+                CustomError,
+                "Could not choose a binary to run, use --bin with one of: {}", available
+            ),
+        })
+    }
+
+    /// Regenerates a forge-managed build file before a build, picking up new sources and
+    /// Forge.toml changes. Other build files are left alone.
+    fn regen_if_needed(&self) -> Result<()> {
+        if self.build.keep_build_file {
+            return Ok(());
+        }
+        let forge_toml = self.directory.join("Forge.toml");
+        let build_file = self.directory.join(self.tools.build_system.build_file());
+
+        match fs::read_to_string(&build_file) {
+            Ok(contents) if !regen::is_managed(&contents) => {
+                if forge_toml.exists() && regen::is_stale(&forge_toml, &build_file)? {
+                    println!(
+                        "Forge.toml is newer than {}, run `forge regen --force` to regenerate it",
+                        build_file.display()
+                    );
+                }
+                Ok(())
+            }
+            _ => self.regen(false),
+        }
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.project.version
+    }
+
+    /// Bumps `part` of the project version and rewrites Forge.toml, `version.h` and the version
+    /// in the build file. With `tag`, the changed files are committed and tagged `v<version>`.
+    pub fn bump_version(&mut self, part: VersionPart, tag: bool) -> Result<Version> {
+        let version = self.project.version.bump(part);
+        self.project.version = version.clone();
+
         self.to_file()?;
+        version::write_header(&self.directory, &self.project)?;
+        let build_file = self.tools.build_system.build_file();
+        if !self.tools.build_system.set_version(&version)? {
+            println!("No version found in {}, left unchanged", build_file);
+        }
+
+        if tag {
+            let files = ["Forge.toml", VERSION_HEADER, build_file];
+            let name = format!("v{}", version);
+            let message = format!("Release {}", name);
+
+            let commands: [Vec<&str>; 3] = [
+                [&["add", "--"][..], &files[..]].concat(),
+                [&["commit", "-m", &message, "--"][..], &files[..]].concat(),
+                vec!["tag", "-a", &name, "-m", &message],
+            ];
+            for args in commands {
+                let status = Command::new("git")
+                    .args(&args)
+                    .current_dir(&self.directory)
+                    .status()?;
+                if !status.success() {
+                    return Err(error!(CustomError, "git {} failed", args[0]));
+                }
+            }
+        }
+
+        Ok(version)
+    }
+
+    /// Removes the artifacts in `scope` and reports the space reclaimed. With `dry_run` only
+    /// lists what would go.
+    pub fn clean(&self, scope: &CleanScope, dry_run: bool) -> Result<u64> {
+        let reclaimed = match scope {
+            // What the clean target removes is only known afterwards.
+            CleanScope::Profile(profile) if dry_run => return self.clean_profile(*profile, true),
+            CleanScope::Profile(profile) => self.clean_profile(*profile, false)?,
+            _ => {
+                let mut reclaimed = 0;
+                for path in self.clean_paths(scope)? {
+                    let full = self.directory.join(&path);
+                    if fs::symlink_metadata(&full).is_err() {
+                        continue;
+                    }
+                    clean::check_inside(&self.directory, &full)?;
+                    let size = clean::disk_usage(&full);
+                    match dry_run {
+                        true => println!(
+                            "Would remove {} ({})",
+                            path.display(),
+                            clean::format_size(size)
+                        ),
+                        false => {
+                            clean::remove(&full)?;
+                            println!("Removed {} ({})", path.display(), clean::format_size(size));
+                        }
+                    }
+                    reclaimed += size;
+                }
+                reclaimed
+            }
+        };
+
+        match dry_run {
+            true => println!("clean: would reclaim {}", clean::format_size(reclaimed)),
+            false => println!("clean: reclaimed {}", clean::format_size(reclaimed)),
+        }
+        Ok(reclaimed)
+    }
+
+    /// Paths removed for `scope`, relative to the project directory.
+    fn clean_paths(&self, scope: &CleanScope) -> Result<Vec<PathBuf>> {
+        let mut paths = vec![];
+        match scope {
+            CleanScope::Build | CleanScope::All => {
+                paths.push(PathBuf::from(BUILD_DIR));
+                paths.push(PathBuf::from(".cache"));
+                // Only the link into the build tree, a file the user put there is kept.
+                let db = PathBuf::from("compile_commands.json");
+                if fs::symlink_metadata(self.directory.join(&db))
+                    .is_ok_and(|m| m.file_type().is_symlink())
+                {
+                    paths.push(db);
+                }
+                if *scope == CleanScope::All {
+                    paths.extend(self.tools.package_manager.deps_dirs());
+                }
+            }
+            CleanScope::Deps => paths.extend(self.tools.package_manager.deps_dirs()),
+            CleanScope::Target(name) => {
+                let targets = self.targets()?;
+                let Some(target) = targets.find(name) else {
+                    let names: Vec<&str> = targets.all().iter().map(|t| t.name.as_str()).collect();
+                    return Err(error!(
+                        CustomError,
+                        "no target named `{}`, the targets are: {}",
+                        name,
+                        names.join(", ")
+                    ));
+                };
+                for profile in [Profile::Debug, Profile::Release] {
+                    paths.extend(
+                        self.tools
+                            .build_system
+                            .target_artifacts(&profile.build_dir(), target),
+                    );
+                }
+            }
+            CleanScope::Profile(_) => (),
+        }
+        Ok(paths)
+    }
+
+    /// Runs the backend's clean target in the profile's tree, keeping its configuration.
+    fn clean_profile(&self, profile: Profile, dry_run: bool) -> Result<u64> {
+        let build_dir = profile.build_dir();
+        let builder = &self.tools.build_system;
+        let full = self.directory.join(&build_dir);
+        if !full.exists() || (builder.has_configure_step() && !builder.is_configured(&build_dir)) {
+            println!(
+                "The {} profile hasn't been built, nothing to clean.",
+                profile
+            );
+            return Ok(0);
+        }
+
+        let before = clean::disk_usage(&full);
+        if dry_run {
+            println!(
+                "Would run the build system's clean target in {} ({} in the tree)",
+                build_dir.display(),
+                clean::format_size(before)
+            );
+            return Ok(0);
+        }
+        builder.clean_in(&build_dir)?;
+        Ok(before.saturating_sub(clean::disk_usage(&full)))
+    }
+
+    pub fn configure_builder(&self, compile_commands: bool, flags: &[String]) -> Result<()> {
+        self.check_profile(Profile::Debug)?;
+        let mut flags = flags.to_vec();
+        flags.extend(self.launcher_args()?);
+        self.tools
+            .build_system
+            .configure(compile_commands, &flags)?;
+        Ok(())
+    }
+
+    /// Checks that the compiler and linker can handle the profile's LTO and linker settings.
+    fn check_profile(&self, profile: Profile) -> Result<()> {
+        let config = self.build.profiles.get(profile);
+        match config.is_default() {
+            true => Ok(()),
+            false => config.check_support(&self.tools.compiler_path, &self.project.language),
+        }
+    }
+
+    /// Configure arguments for the compiler cache in `[build] cache`.
+    fn launcher_args(&self) -> Result<Vec<String>> {
+        let launcher = match self.build.cache {
+            Some(cache) => Some(cache.path()?),
+            None => None,
+        };
+        Ok(self.tools.build_system.launcher_args(launcher.as_deref()))
+    }
+
+    /// The compiler cache enabled in Forge.toml.
+    pub fn compiler_cache(&self) -> Result<CompilerCache> {
+        self.build.cache.ok_or_else(|| {
+            let found: Vec<String> = compiler_cache::detect_caches().into_keys().collect();
+            let hint = match found.is_empty() {
+                true => String::new(),
+                false => format!(" (found {})", found.join(", ")),
+            };
+            error!(
+                CustomError,
+                "No compiler cache enabled, set `cache = \"ccache\"` or `\"sccache\"` in [build]{}",
+                hint
+            )
+        })
+    }
+
+    /// The tree a command builds in. Sanitized builds get a dedicated tree per sanitizer set.
+    pub fn build_tree(
+        &self,
+        sanitizers: Option<&SanitizerSet>,
+        profile: Profile,
+    ) -> Result<BuildTree> {
+        let build_system = &self.tools.build_system;
+        let mut tree = BuildTree {
+            dir: profile.build_dir(),
+            profile,
+            configure_flags: build_system.profile_args(profile),
+            build_flags: build_system.profile_build_args(profile),
+            env: vec![],
+            message_format: MessageFormat::default(),
+            timings: None,
+        };
+
+        if let Some(set) = sanitizers {
+            set.check_support(&self.tools.compiler_path, &self.project.language)?;
+
+            let dir = set.build_dir().to_string_lossy().into_owned();
+            tree.dir = PathBuf::from(format!("{}{}", dir, profile.suffix()));
+            tree.configure_flags.extend(build_system.toolchain_args(
+                &self.tools.compiler_path,
+                &set.compile_flags(),
+                &set.link_flags(),
+            ));
+            tree.build_flags
+                .extend(build_system.toolchain_build_args(&set.compile_flags(), &set.link_flags()));
+            tree.env = set.env();
+        }
+
+        Ok(tree)
+    }
+
+    pub fn build(&self, tree: &BuildTree, flags: Option<&Vec<String>>) -> Result<()> {
+        let build_system = &self.tools.build_system;
+        self.regen_if_needed()?;
+
+        let family = CompilerFamily::from_compiler(&self.tools.compiler_path);
+        // clang's -ftime-trace adds the slowest headers to the report.
+        let trace = family == CompilerFamily::Clang;
+        let timing_tree = match tree.timings {
+            Some(_) => self.timing_tree(tree, trace)?,
+            None => None,
+        };
+        let tree = timing_tree.as_ref().unwrap_or(tree);
+
+        // Make has no configure step, so its settings are checked on every build.
+        let configure = tree.is_managed() && !build_system.is_configured(&tree.dir);
+        if configure || !build_system.has_configure_step() {
+            self.check_profile(tree.profile)?;
+        }
+        if configure {
+            // The tree's own flags come last, so a timing launcher replaces the cache's.
+            let mut flags = self.launcher_args()?;
+            flags.extend(tree.configure_flags.iter().cloned());
+            build_system.configure_in(&tree.dir, false, &flags)?;
+        }
+
+        let timing = match tree.timings {
+            Some(_) => Some(TimingSource::start(
+                build_system.ninja_log_dir(&tree.dir, trace).as_deref(),
+                &self.directory,
+            )?),
+            None => None,
+        };
+
+        let mut build_flags = tree.build_flags.clone();
+        if let Some(TimingSource::Wrapper { path }) = &timing {
+            build_flags.extend(build_system.timing_args(&self.timing_launcher(path, trace)?)?);
+        }
+        build_flags.extend(build_system.diagnostics_args(family, tree.message_format));
+        build_flags.extend(flags.cloned().unwrap_or_default());
+        let flags = match build_flags.is_empty() {
+            true => None,
+            false => Some(&build_flags),
+        };
+        let started = Instant::now();
+        let output = build_system.compile(&tree.dir, flags, tree.message_format)?;
+        let diagnostics =
+            diagnostics::project_diagnostics(&self.directory, &tree.dir, output.diagnostics);
+
+        match tree.message_format {
+            MessageFormat::Human => diagnostics::print_build_summary(&diagnostics),
+            MessageFormat::Json => {
+                for message in &diagnostics {
+                    let line = serde_json::to_string(&BuildMessage::CompilerMessage { message })?;
+                    println!("{}", line);
+                }
+                let finished = BuildMessage::BuildFinished {
+                    success: output.success,
+                };
+                println!("{}", serde_json::to_string(&finished)?);
+            }
+        }
+
+        if let (Some(source), Some(top)) = (&timing, tree.timings) {
+            let report = TimingReport::new(
+                &self.directory,
+                &self.directory.join(&tree.dir),
+                &source.entries()?,
+                &self.targets()?,
+                started.elapsed().as_millis() as u64,
+            );
+            let html = report.write(&self.directory.join(TIMINGS_DIR))?;
+            if tree.message_format == MessageFormat::Human {
+                report.print(top);
+                println!("Wrote {}", html.display());
+            }
+        }
+
+        if !output.success {
+            return Err(error!(CustomError, "Compilation failed."));
+        }
+        Ok(())
+    }
+
+    /// Tree under `build/timings/tree` mirroring `tree`, for timed builds that need configure
+    /// settings `tree` doesn't have. `None` when `tree` can be timed as it is.
+    fn timing_tree(&self, tree: &BuildTree, trace: bool) -> Result<Option<BuildTree>> {
+        let log = self.directory.join(TIMINGS_DIR).join(WRAPPER_LOG);
+        let launcher = self.timing_launcher(&log, trace)?;
+        let args = self.tools.build_system.timing_configure_args(
+            &tree.dir,
+            &tree.configure_flags,
+            &launcher,
+            trace,
+        );
+
+        Ok(args.map(|configure_flags| {
+            let relative = tree.dir.strip_prefix(BUILD_DIR).unwrap_or(&tree.dir);
+            BuildTree {
+                dir: Path::new(TIMINGS_DIR).join("tree").join(relative),
+                configure_flags,
+                ..tree.clone()
+            }
+        }))
+    }
+
+    /// Compiler launcher timing each compile into `log`, keeping the compiler cache. With
+    /// `trace` it adds `-ftime-trace` to each compile.
+    fn timing_launcher(&self, log: &Path, trace: bool) -> Result<Vec<String>> {
+        let exe = std::env::current_exe()?;
+        let mut words = vec![
+            exe.to_string_lossy().into_owned(),
+            "time-compile".to_string(),
+        ];
+        if trace {
+            words.push("--trace".to_string());
+        }
+        words.push(log.to_string_lossy().into_owned());
+        if let Some(cache) = self.build.cache {
+            words.push(cache.as_str().to_string());
+        }
+        Ok(words)
+    }
+
+    /// Builds only `target` and the targets it links.
+    pub fn build_target(&self, tree: &BuildTree, target: &Target) -> Result<()> {
+        let args = self.tools.build_system.target_build_args(&tree.dir, target);
+        self.build(tree, Some(&args))
+    }
+
+    /// Builds the release profile and installs it to `prefix`, or the backend's default prefix.
+    pub fn install(&self, prefix: Option<&Path>) -> Result<()> {
+        let tree = self.build_tree(None, Profile::Release)?;
+        self.build(&tree, None)?;
+
+        let prefix = match prefix {
+            Some(p) if p.is_relative() => Some(std::env::current_dir()?.join(p)),
+            Some(p) => Some(p.to_path_buf()),
+            None => None,
+        };
+        self.tools
+            .build_system
+            .install(&tree.dir, prefix.as_deref(), &tree.build_flags)
+    }
+
+    /// Builds the release profile and packages it in each of `formats`.
+    pub fn package(&self, formats: &[PackageFormat]) -> Result<()> {
+        let tree = self.build_tree(None, Profile::Release)?;
+        self.build(&tree, None)?;
+
+        let artifacts = self.tools.build_system.package(
+            &self.project,
+            &tree.dir,
+            formats,
+            &tree.build_flags,
+        )?;
+
+        println!();
+        for artifact in artifacts {
+            println!("Packaged {}", artifact.display());
+        }
+        Ok(())
+    }
+
+    /// Runs `target` from `tree` with `args` in the current directory, and returns its exit code.
+    pub fn run(
+        &self,
+        tree: &BuildTree,
+        target: &Target,
+        args: &[String],
+        memcheck: bool,
+    ) -> Result<i32> {
+        let bin = self
+            .tools
+            .build_system
+            .target_output(&self.directory.join(&tree.dir), target);
+
+        let mut command = vec![];
+        let memcheck = match memcheck {
+            true => Some(self.memcheck()?),
+            false => None,
+        };
+        if let Some(m) = &memcheck {
+            command = m.wrapper("run");
+        }
+        command.push(bin.to_string_lossy().into_owned());
+        command.extend(args.iter().cloned());
+
+        let run_cmd = Command::new(&command[0])
+            .args(&command[1..])
+            .envs(tree.env.iter().cloned())
+            .status()?;
+
+        if let Some(m) = &memcheck {
+            Self::check_memcheck(m)?;
+        }
+
+        // Killed by a signal, there is no code to forward.
+        Ok(run_cmd.code().unwrap_or(1))
+    }
+
+    pub fn test(
+        &self,
+        tree: &BuildTree,
+        selection: &TestSelection,
+        flags: Option<&Vec<String>>,
+        reports: &[PathBuf],
+        memcheck: bool,
+    ) -> Result<()> {
+        let mut run = TestRun::new(&self.directory, tree.dir.clone(), selection.clone());
+        run.env = tree.env.clone();
+        if let Some(f) = flags {
+            run.flags = f.clone();
+        }
+
+        if !memcheck {
+            return self.run_tests(&run, reports);
+        }
+
+        let memcheck = self.memcheck()?;
+        run.wrapper = memcheck.wrapper("test");
+        let tests = self.run_tests(&run, reports);
+        Self::check_memcheck(&memcheck)?;
+        tests
+    }
+
+    /// Builds the bench targets in release and runs them, writing their JSON results to
+    /// `build/bench/`. Scaffolds `bench/` and adds the framework on first use.
+    pub fn bench(&self, run: &BenchRun) -> Result<()> {
+        let framework = BenchFramework::for_language(&self.project.language);
+        if !self.directory.join("bench").exists() {
+            for file in framework.scaffold(&self.directory, &self.project.name)? {
+                println!("Created {}", file.display());
+            }
+            self.tools.package_manager.bench_setup(framework)?;
+        }
+
+        let targets = self.targets()?;
+        let benches: Vec<&Target> = targets
+            .benches
+            .iter()
+            .filter(|b| run.target.as_ref().is_none_or(|t| &b.name == t))
+            .collect();
+        if benches.is_empty() {
+            return Err(match &run.target {
+                Some(t) => error!(CustomError, "No bench named `{}`", t),
+                None => error!(CustomError, "No benchmarks found in bench/"),
+            });
+        }
+
+        let tree = self.build_tree(None, Profile::Release)?;
+        for bench in &benches {
+            self.build_target(&tree, bench)?;
+        }
+
+        let results_dir = self.directory.join(BENCH_DIR);
+        fs::create_dir_all(&results_dir)?;
+        let mut report = BenchReport::default();
+        for bench in &benches {
+            let out = results_dir.join(format!("{}.json", bench.name));
+            let bin = self
+                .tools
+                .build_system
+                .target_output(&self.directory.join(&tree.dir), bench);
+            let status = Command::new(bin)
+                .args(framework.binary_args(&out))
+                .args(&run.args)
+                .status()?;
+            if !status.success() {
+                return Err(error!(CustomError, "Benchmark `{}` failed", bench.name));
+            }
+            report.extend(&bench.name, BenchReport::from_file(&out)?);
+        }
+
+        if let Some(name) = &run.save_baseline {
+            let path = bench::baseline_path(&self.directory, name);
+            report.to_file(&path)?;
+            println!("Saved baseline `{}` to {}", name, path.display());
+        }
+
+        if let Some(name) = &run.baseline {
+            let path = bench::baseline_path(&self.directory, name);
+            if !path.exists() {
+                return Err(error!(
+                    CustomError,
+                    "No baseline `{}`, save one with --save-baseline {}", name, name
+                ));
+            }
+            let changes = report.compare(&BenchReport::from_file(&path)?);
+            let regressions = bench::print_comparison(&changes, name, run.threshold);
+            if regressions > 0 {
+                return Err(error!(
+                    CustomError,
+                    "{} benchmark(s) regressed by more than {}%", regressions, run.threshold
+                ));
+            }
+        }
 
         Ok(())
     }
 
-    pub fn clean(&self) -> Result<()> {
-        if std::path::Path::new(".cache").exists() {
-            std::fs::remove_dir_all(".cache")?;
+    /// Generates the API documentation into `build/doc`, creating the Doxyfile on first use,
+    /// and returns the index page. With `check`, undocumented public symbols are an error.
+    pub fn doc(&self, check: bool) -> Result<PathBuf> {
+        let doc = Doc::new(self.directory.clone());
+        if doc.init(&self.project)? {
+            println!("Created {}", DOXYFILE);
         }
 
-        if std::path::Path::new("build").exists() {
-            std::fs::remove_dir_all("build")?;
-            std::fs::create_dir("build")?;
-            // self.tools.build_system.config()?;
+        let log = doc.generate()?;
+        if check {
+            let include = self.directory.join("include");
+            let include = fs::canonicalize(&include).unwrap_or(include);
+            let warnings = doc::undocumented(&log, &include);
+            for warning in &warnings {
+                println!("{}", warning);
+            }
+            if !warnings.is_empty() {
+                return Err(error!(
+                    CustomError,
+                    "{} undocumented public symbol(s) in include/",
+                    warnings.len()
+                ));
+            }
         }
 
+        Ok(doc.index())
+    }
+
+    fn memcheck(&self) -> Result<Memcheck> {
+        let memcheck = Memcheck::new(self.directory.clone(), self.memcheck.clone());
+        memcheck.prepare()?;
+        Ok(memcheck)
+    }
+
+    /// Prints the errors valgrind found and fails if there were any.
+    fn check_memcheck(memcheck: &Memcheck) -> Result<()> {
+        let summary = memcheck.summary()?;
+        summary.print_summary();
+
+        if !summary.success() {
+            return Err(error!(
+                CustomError,
+                "memcheck found {} error(s), logs are in {}",
+                summary.errors.len(),
+                memcheck.log_dir().display()
+            ));
+        }
         Ok(())
     }
 
-    pub fn configure_builder(&self, compile_commands: bool, flags: &Vec<String>) -> Result<()> {
-        self.tools.build_system.configure(compile_commands, flags)?;
+    /// Runs the tests described by `run`, prints a summary and writes the requested reports.
+    fn run_tests(&self, run: &TestRun, reports: &[PathBuf]) -> Result<()> {
+        let success = self.tools.build_system.test(run, &self.targets()?)?;
+
+        if !run.junit.exists() {
+            return match success {
+                true => Ok(()),
+                false => Err(error!(CustomError, "Tests failed")),
+            };
+        }
+
+        let report = TestReport::from_junit_file(&run.junit)?;
+        report.print_summary();
+
+        for path in reports {
+            report.write(path)?;
+            println!("Wrote test report to {}", path.display());
+        }
+
+        if !success || !report.success() {
+            return Err(error!(
+                CustomError,
+                "{} test(s) failed",
+                report.count(TestStatus::Failed)
+            ));
+        }
+
         Ok(())
     }
 
-    pub fn build(&self, flags: Option<&Vec<String>>) -> Result<()> {
-        self.tools.build_system.build(flags)?;
+    /// Lints the project's translation units, configuring first if there's no compile
+    /// database yet. `preset` rewrites `.clang-tidy`, which is otherwise only created when
+    /// missing.
+    pub fn lint(
+        &self,
+        backend: &LintBackend,
+        fix: bool,
+        jobs: usize,
+        preset: Option<&LintPreset>,
+    ) -> Result<()> {
+        let sources = self.translation_units("lint")?;
+        let linter = Linter::new(
+            self.directory.clone(),
+            PathBuf::from(BUILD_DIR),
+            self.project.language.clone(),
+        );
+
+        match preset {
+            Some(p) => linter.write_config(p)?,
+            None if !linter.config_path().exists() => {
+                println!("Creating .clang-tidy with the Recommended preset");
+                linter.write_config(&LintPreset::Recommended)?;
+            }
+            None => (),
+        }
+
+        let db = self.directory.join(BUILD_DIR).join("compile_commands.json");
+        if !db.exists() {
+            self.configure_builder(true, &[])?;
+        }
+
+        let diagnostics = linter.lint(backend, &sources, fix, jobs)?;
+        linter::print_summary(&diagnostics);
 
+        let errors = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count();
+        if errors > 0 {
+            return Err(error!(CustomError, "lint found {} error(s)", errors));
+        }
         Ok(())
     }
 
-    pub fn run(&self) -> Result<()> {
-        let bin = format!("./build/bin/{}", self.project.name);
-        let run_cmd = Command::new(&bin).current_dir(".").status()?;
+    /// Runs the project's translation units through the compiler with `-fsyntax-only`, or only
+    /// those touched by uncommitted changes with `changed`. Nothing is linked.
+    pub fn check(&self, changed: bool, jobs: usize) -> Result<()> {
+        let sources = self.translation_units("check")?;
+        let db = self.directory.join(BUILD_DIR).join("compile_commands.json");
+        if !db.exists() {
+            self.configure_builder(true, &[])?;
+        }
 
-        if !run_cmd.success() {
-            eprintln!("Failed: {:?}", run_cmd);
+        let checker = Checker::new(self.directory.clone(), PathBuf::from(BUILD_DIR), sources);
+        let only = match changed {
+            true => Some(checker.changed_sources()?),
+            false => None,
+        };
+        if only.as_ref().is_some_and(|o| o.is_empty()) {
+            println!("No changed files to check");
+            return Ok(());
         }
 
+        let (checked, diagnostics) = checker.check(only.as_deref(), jobs)?;
+        checker::print_summary(checked, &diagnostics);
+
+        let errors = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count();
+        if errors > 0 {
+            return Err(error!(CustomError, "check found {} error(s)", errors));
+        }
         Ok(())
     }
 
-    pub fn test(&self, flags: Option<&Vec<String>>) -> Result<()> {
-        let mut args: Vec<String> = vec!["--test-dir".into(), "build".into()];
+    /// Absolute paths of every target's sources, the files `command` reads from the compile
+    /// database. Make writes no compile database, so `command` isn't available there.
+    fn translation_units(&self, command: &str) -> Result<Vec<PathBuf>> {
+        if !self.tools.build_system.has_compile_db() {
+            return Err(error!(
+                CustomError,
+                "forge {} reads compile_commands.json, which Make projects don't have", command
+            ));
+        }
 
-        if let Some(f) = flags {
-            args.extend(f.clone());
+        let targets = self.targets()?;
+        Ok(targets
+            .all()
+            .iter()
+            .flat_map(|t| t.sources.iter().map(|s| normalize(&self.directory.join(s))))
+            .collect())
+    }
+
+    /// Builds an instrumented copy of the project in its own tree, runs the tests and reports
+    /// line coverage for `src/` and `include/`.
+    pub fn coverage(&self, html: bool, cobertura: bool, fail_under: Option<f64>) -> Result<()> {
+        let build_dir = PathBuf::from(COVERAGE_DIR);
+        let build_system = &self.tools.build_system;
+        let test_binaries: Vec<PathBuf> = self
+            .targets()?
+            .tests
+            .iter()
+            .map(|t| build_system.target_output(&self.directory.join(&build_dir), t))
+            .collect();
+        if test_binaries.is_empty() {
+            return Err(error!(
+                CustomError,
+                "The project has no test targets to run"
+            ));
         }
 
-        let test_cmd = Command::new("ctest").args(args).current_dir(".").status()?;
+        let family = CompilerFamily::from_compiler(&self.tools.compiler_path);
+        let coverage = Coverage::new(family, self.directory.clone(), test_binaries);
+        coverage.check_tools(html)?;
 
-        if !test_cmd.success() {
-            eprintln!("Failed: {:?}", test_cmd);
+        let flags = build_system.toolchain_args(
+            &self.tools.compiler_path,
+            &coverage.compile_flags(),
+            &coverage.link_flags(),
+        );
+        build_system.configure_in(&build_dir, false, &flags)?;
+        let build_flags =
+            build_system.toolchain_build_args(&coverage.compile_flags(), &coverage.link_flags());
+        let build_flags = match build_flags.is_empty() {
+            true => None,
+            false => Some(&build_flags),
+        };
+        build_system.build_in(&build_dir, build_flags)?;
+
+        coverage.reset()?;
+        let mut run = TestRun::new(&self.directory, build_dir, TestSelection::default());
+        run.env = coverage.test_env();
+        let tests = self.run_tests(&run, &[]);
+
+        let lcov = coverage.collect()?;
+        let report = CoverageReport::from_lcov(&fs::read_to_string(&lcov)?, &self.directory);
+        report.print_summary();
+        println!("Wrote lcov report to {}", lcov.display());
+
+        if html {
+            let index = coverage.html(&lcov)?;
+            println!("Wrote HTML report to {}", index.display());
+        }
+
+        if cobertura {
+            let path = coverage.report_dir().join("cobertura.xml");
+            fs::write(
+                &path,
+                report.to_cobertura(&self.project.name, &self.directory),
+            )?;
+            println!("Wrote Cobertura report to {}", path.display());
+        }
+
+        tests?;
+
+        if report.lines_found() == 0 {
+            return Err(error!(
+                CustomError,
+                "No coverage data was collected for src/ or include/, check the build is instrumented"
+            ));
+        }
+
+        if let Some(min) = fail_under {
+            if report.percent() < min {
+                return Err(error!(
+                    CustomError,
+                    "Line coverage {:.2}% is below the required {:.2}%",
+                    report.percent(),
+                    min
+                ));
+            }
         }
 
         Ok(())
@@ -157,8 +1192,11 @@ impl ForgeConfig {
 mod tests {
     use super::*;
     use crate::core::{
+        init_plan::ExistingFiles,
         language::{CStandard, CppStandard, Language},
         package_manager::PackageManagers,
+        profile::Lto,
+        targets::TargetConfig,
         test_framework::TestFrameworks,
     };
     use serde_json::json;
@@ -232,6 +1270,141 @@ mod tests {
         Ok(())
     }
 
+    /// A C++ CMake project config in `path`, as `forge init` writes it.
+    fn dummy_config(path: &Path) -> ForgeConfig {
+        ForgeConfig::new(
+            "dummy".to_string(),
+            path.to_path_buf(),
+            Language::Cpp(CppStandard::Cpp11),
+            "clang++".to_string(),
+            BuildSystems::CMake,
+            PackageManagers::Vcpkg,
+            TestFrameworks::GTest,
+            "testing".to_string(),
+        )
+    }
+
+    /// Writes `config` to Forge.toml and checks it reads back unchanged, returning the file.
+    fn round_trip(config: &ForgeConfig) -> anyhow::Result<String> {
+        let cwd = std::env::current_dir()?;
+        config.to_file()?;
+        let contents = fs::read_to_string(config.directory.join("Forge.toml"))?;
+
+        env::set_current_dir(&config.directory)?;
+        let new_config = ForgeConfig::from_file();
+        env::set_current_dir(&cwd)?;
+        assert_eq!(config, &new_config?);
+
+        Ok(contents)
+    }
+
+    #[test]
+    #[serial]
+    fn test_from_file_moved() -> anyhow::Result<()> {
+        let cwd = std::env::current_dir()?;
+        let path = cwd.join("dummy");
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let moved = toml::to_string_pretty(&dummy_config(Path::new("/home/dev/dummy")))?;
+        fs::write(path.join("Forge.toml"), moved)?;
+
+        // Test
+        env::set_current_dir(&path)?;
+        let config = ForgeConfig::from_file();
+        env::set_current_dir(&cwd)?;
+
+        // Validate
+        let config = config?;
+        assert_eq!(config.directory, path);
+        assert_eq!(
+            config.tools.build_system,
+            dummy_config(&path).tools.build_system
+        );
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_targets_to_file() -> anyhow::Result<()> {
+        let path = std::env::current_dir()?.join("dummy");
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let mut config = dummy_config(&path);
+        config.targets.lib = Some(TargetConfig {
+            defines: vec!["DUMMY_EXPORT".to_string()],
+            ..TargetConfig::default()
+        });
+        config.targets.bins = vec![TargetConfig {
+            name: "tool".to_string(),
+            sources: vec!["tools/*.cpp".to_string()],
+            ..TargetConfig::default()
+        }];
+
+        // Test
+        let contents = round_trip(&config)?;
+
+        // Validate
+        assert!(contents.contains("[lib]") && contents.contains("[[bin]]"));
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_profile_to_file() -> anyhow::Result<()> {
+        let path = std::env::current_dir()?.join("dummy");
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let mut config = dummy_config(&path);
+        config.build.profiles.release.lto = Lto::Full;
+
+        // Test
+        let contents = round_trip(&config)?;
+
+        // Validate
+        assert!(contents.contains("[build.release]\nlto = \"full\"\n"));
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_imported_to_file() -> anyhow::Result<()> {
+        let path = std::env::current_dir()?.join("dummy");
+
+        // Set-up
+        create_dummy_project(&path)?;
+        let mut config = dummy_config(&path);
+        config.build.keep_build_file = true;
+        config.dependencies = vec!["fmt".to_string()];
+
+        // Test
+        let contents = round_trip(&config)?;
+
+        // Validate
+        assert!(contents.contains("keep_build_file = true"));
+        assert!(contents.contains("dependencies = [\"fmt\"]"));
+        assert!(config.regen(true).is_err());
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
+
     #[test]
     #[serial]
     // #[ignore]
@@ -260,7 +1433,11 @@ mod tests {
             test_framework,
             intellisense,
         );
-        config.init()?;
+        config.init(&InitPlan::new(
+            &path,
+            config.init_files(),
+            ExistingFiles::Overwrite,
+        ))?;
 
         // Validate
         assert!(check_file_exits(&path.join("CMakeLists.txt")));
@@ -323,13 +1500,18 @@ mod tests {
             test_framework,
             intellisense,
         );
-        config.init()?;
+        config.init(&InitPlan::new(
+            &path,
+            config.init_files(),
+            ExistingFiles::Overwrite,
+        ))?;
 
         // Validate
         assert!(check_file_exits(&path.join("CMakeLists.txt")));
         assert!(check_file_exits(&path.join(".gitignore")));
         assert!(check_file_exits(&path.join(".git")));
         assert!(check_file_exits(&path.join("include").join("lib.h")));
+        assert!(check_file_exits(&path.join("include").join("version.h")));
         assert!(check_file_exits(&path.join("src").join("lib.c")));
         assert!(check_file_exits(&path.join("src").join("main.c")));
         assert!(check_file_exits(&path.join("test").join("test_lib.c")));
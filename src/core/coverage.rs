@@ -0,0 +1,470 @@
+use super::compiler::CompilerFamily;
+use crate::{error, Error, Result};
+use ansi_term::Colour::{Green, Red, Yellow};
+use quick_xml::escape::escape;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use which::which;
+
+/// Dedicated build tree for instrumented builds, relative to the project directory.
+pub const COVERAGE_DIR: &str = "build/coverage";
+
+pub struct Coverage {
+    family: CompilerFamily,
+    directory: PathBuf,
+    /// Instrumented test executables, read by `llvm-cov` to map the profiles back to sources.
+    test_binaries: Vec<PathBuf>,
+}
+
+impl Coverage {
+    pub fn new(
+        family: CompilerFamily,
+        directory: PathBuf,
+        test_binaries: Vec<PathBuf>,
+    ) -> Coverage {
+        Coverage {
+            family,
+            directory,
+            test_binaries,
+        }
+    }
+
+    pub fn compile_flags(&self) -> Vec<String> {
+        let mut flags = match self.family {
+            CompilerFamily::Gcc => vec!["--coverage".to_string()],
+            CompilerFamily::Clang => vec![
+                "-fprofile-instr-generate".to_string(),
+                "-fcoverage-mapping".to_string(),
+            ],
+        };
+        flags.extend(["-O0".to_string(), "-g".to_string()]);
+        flags
+    }
+
+    pub fn link_flags(&self) -> Vec<String> {
+        match self.family {
+            CompilerFamily::Gcc => vec!["--coverage".to_string()],
+            CompilerFamily::Clang => vec!["-fprofile-instr-generate".to_string()],
+        }
+    }
+
+    /// Environment for the instrumented test processes.
+    pub fn test_env(&self) -> Vec<(String, String)> {
+        match self.family {
+            CompilerFamily::Gcc => vec![],
+            CompilerFamily::Clang => {
+                let profile = self.profiles_dir().join("%p-%m.profraw");
+                vec![(
+                    "LLVM_PROFILE_FILE".to_string(),
+                    profile.to_string_lossy().into_owned(),
+                )]
+            }
+        }
+    }
+
+    pub fn report_dir(&self) -> PathBuf {
+        self.directory.join(COVERAGE_DIR).join("report")
+    }
+
+    fn profiles_dir(&self) -> PathBuf {
+        self.directory.join(COVERAGE_DIR).join("profiles")
+    }
+
+    pub fn check_tools(&self, html: bool) -> Result<()> {
+        let mut tools = match self.family {
+            CompilerFamily::Gcc => vec!["lcov"],
+            CompilerFamily::Clang => vec!["llvm-profdata", "llvm-cov"],
+        };
+        if html {
+            tools.push("genhtml");
+        }
+
+        for tool in tools {
+            if which(tool).is_err() {
+                return Err(error!(
+                    CustomError,
+                    "{} is required for {} coverage but was not found",
+                    tool,
+                    self.family.as_str()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes counters left over from a previous run so results only reflect this run.
+    pub fn reset(&self) -> Result<()> {
+        match self.family {
+            CompilerFamily::Gcc => remove_files(&self.directory.join(COVERAGE_DIR), "gcda")?,
+            CompilerFamily::Clang => {
+                if self.profiles_dir().exists() {
+                    fs::remove_dir_all(self.profiles_dir())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Turns the raw counters into an lcov tracefile limited to `src/` and `include/`.
+    pub fn collect(&self) -> Result<PathBuf> {
+        let report_dir = self.report_dir();
+        fs::create_dir_all(&report_dir)?;
+        let lcov = report_dir.join("coverage.info");
+
+        match self.family {
+            CompilerFamily::Gcc => {
+                let raw = report_dir.join("coverage.raw.info");
+                run(Command::new("lcov")
+                    .arg("--capture")
+                    .arg("--quiet")
+                    .arg("--directory")
+                    .arg(self.directory.join(COVERAGE_DIR))
+                    .arg("--output-file")
+                    .arg(&raw))?;
+                run(Command::new("lcov")
+                    .arg("--quiet")
+                    .arg("--extract")
+                    .arg(&raw)
+                    .arg(format!("{}/src/*", self.directory.display()))
+                    .arg(format!("{}/include/*", self.directory.display()))
+                    .arg("--output-file")
+                    .arg(&lcov))?;
+            }
+            CompilerFamily::Clang => {
+                let profiles: Vec<PathBuf> = match fs::read_dir(self.profiles_dir()) {
+                    Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+                    Err(_) => vec![],
+                };
+                if profiles.is_empty() {
+                    return Err(error!(CustomError, "No coverage profiles were written"));
+                }
+
+                let profdata = report_dir.join("coverage.profdata");
+                run(Command::new("llvm-profdata")
+                    .args(["merge", "-sparse"])
+                    .args(&profiles)
+                    .arg("-o")
+                    .arg(&profdata))?;
+
+                let Some((first, others)) = self.test_binaries.split_first() else {
+                    return Err(error!(
+                        CustomError,
+                        "There are no test binaries to report on"
+                    ));
+                };
+                let output = Command::new("llvm-cov")
+                    .args(["export", "-format=lcov"])
+                    .arg(format!("-instr-profile={}", profdata.display()))
+                    .arg(first)
+                    .args(others.iter().map(|b| format!("-object={}", b.display())))
+                    .arg(self.directory.join("src"))
+                    .arg(self.directory.join("include"))
+                    .output()?;
+                if !output.status.success() {
+                    return Err(error!(
+                        CustomError,
+                        "llvm-cov failed: {}",
+                        String::from_utf8_lossy(&output.stderr)
+                    ));
+                }
+                fs::write(&lcov, output.stdout)?;
+            }
+        }
+
+        Ok(lcov)
+    }
+
+    pub fn html(&self, lcov: &Path) -> Result<PathBuf> {
+        let html_dir = self.report_dir().join("html");
+        run(Command::new("genhtml")
+            .arg("--quiet")
+            .arg(lcov)
+            .arg("--output-directory")
+            .arg(&html_dir))?;
+        Ok(html_dir.join("index.html"))
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileCoverage {
+    /// Path relative to the project directory.
+    pub file: String,
+    /// `(line, hits)` for every instrumented line.
+    pub lines: Vec<(u32, u64)>,
+    pub functions_found: u32,
+    pub functions_hit: u32,
+}
+
+impl FileCoverage {
+    pub fn lines_found(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.iter().filter(|(_, hits)| *hits > 0).count()
+    }
+
+    pub fn percent(&self) -> f64 {
+        percent(self.lines_hit(), self.lines_found())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub files: Vec<FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Parses an lcov tracefile. Source paths under `directory` are made relative to it.
+    pub fn from_lcov(contents: &str, directory: &Path) -> CoverageReport {
+        let mut report = CoverageReport::default();
+        let mut current = FileCoverage::default();
+
+        for line in contents.lines() {
+            let (key, value) = line.split_once(':').unwrap_or((line, ""));
+            match key {
+                "SF" => {
+                    let path = Path::new(value);
+                    current.file = path
+                        .strip_prefix(directory)
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .into_owned();
+                }
+                "DA" => {
+                    let mut parts = value.split(',');
+                    let line = parts.next().and_then(|l| l.parse().ok());
+                    let hits = parts.next().and_then(|h| h.parse().ok());
+                    if let (Some(line), Some(hits)) = (line, hits) {
+                        current.lines.push((line, hits));
+                    }
+                }
+                "FNF" => current.functions_found = value.parse().unwrap_or(0),
+                "FNH" => current.functions_hit = value.parse().unwrap_or(0),
+                "end_of_record" => report.files.push(std::mem::take(&mut current)),
+                _ => (),
+            }
+        }
+
+        report.files.sort_by(|a, b| a.file.cmp(&b.file));
+        report
+    }
+
+    pub fn lines_found(&self) -> usize {
+        self.files.iter().map(|f| f.lines_found()).sum()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.files.iter().map(|f| f.lines_hit()).sum()
+    }
+
+    pub fn percent(&self) -> f64 {
+        percent(self.lines_hit(), self.lines_found())
+    }
+
+    pub fn print_summary(&self) {
+        let width = self
+            .files
+            .iter()
+            .map(|f| f.file.len())
+            .max()
+            .unwrap_or(0)
+            .max("TOTAL".len());
+
+        println!(
+            "\n{:<width$}  {:>7}  {:>7}  {:>7}",
+            "File",
+            "Lines",
+            "Hit",
+            "Cover",
+            width = width
+        );
+        for f in &self.files {
+            println!(
+                "{:<width$}  {:>7}  {:>7}  {}",
+                f.file,
+                f.lines_found(),
+                f.lines_hit(),
+                paint_percent(f.percent()),
+                width = width
+            );
+        }
+        println!(
+            "{:<width$}  {:>7}  {:>7}  {}\n",
+            "TOTAL",
+            self.lines_found(),
+            self.lines_hit(),
+            paint_percent(self.percent()),
+            width = width
+        );
+    }
+
+    pub fn to_cobertura(&self, name: &str, directory: &Path) -> String {
+        let rate = |hit: usize, found: usize| percent(hit, found) / 100.0;
+
+        let mut content = vec![];
+        content.push(r#"<?xml version="1.0" ?>"#.to_string());
+        content.push(
+            r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+                .to_string(),
+        );
+        content.push(format!(
+            r#"<coverage line-rate="{:.4}" branch-rate="0" lines-covered="{}" lines-valid="{}" branches-covered="0" branches-valid="0" complexity="0" version="forge">"#,
+            rate(self.lines_hit(), self.lines_found()),
+            self.lines_hit(),
+            self.lines_found()
+        ));
+        content.push(format!(
+            "  <sources><source>{}</source></sources>",
+            escape(directory.to_string_lossy().as_ref())
+        ));
+        content.push("  <packages>".to_string());
+        content.push(format!(
+            r#"    <package name="{}" line-rate="{:.4}" branch-rate="0" complexity="0">"#,
+            escape(name),
+            rate(self.lines_hit(), self.lines_found())
+        ));
+        content.push("      <classes>".to_string());
+
+        for f in &self.files {
+            content.push(format!(
+                r#"        <class name="{}" filename="{}" line-rate="{:.4}" branch-rate="0" complexity="0">"#,
+                escape(&f.file),
+                escape(&f.file),
+                rate(f.lines_hit(), f.lines_found())
+            ));
+            content.push("          <methods/>".to_string());
+            content.push("          <lines>".to_string());
+            for (line, hits) in &f.lines {
+                content.push(format!(
+                    r#"            <line number="{}" hits="{}"/>"#,
+                    line, hits
+                ));
+            }
+            content.push("          </lines>".to_string());
+            content.push("        </class>".to_string());
+        }
+
+        content.push("      </classes>".to_string());
+        content.push("    </package>".to_string());
+        content.push("  </packages>".to_string());
+        content.push("</coverage>\n".to_string());
+        content.join("\n")
+    }
+}
+
+/// An empty report has no coverage, rather than full coverage.
+fn percent(hit: usize, found: usize) -> f64 {
+    match found {
+        0 => 0.0,
+        _ => hit as f64 * 100.0 / found as f64,
+    }
+}
+
+fn paint_percent(pct: f64) -> String {
+    let text = format!("{:>6.2}%", pct);
+    match pct {
+        p if p >= 80.0 => Green.paint(text).to_string(),
+        p if p >= 50.0 => Yellow.paint(text).to_string(),
+        _ => Red.paint(text).to_string(),
+    }
+}
+
+fn run(cmd: &mut Command) -> Result<()> {
+    let status = cmd.status()?;
+    if !status.success() {
+        return Err(error!(CustomError, "Command failed: {:?}", cmd));
+    }
+    Ok(())
+}
+
+/// Recursively removes every file with the given extension under `dir`.
+fn remove_files(dir: &Path, extension: &str) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            remove_files(&path, extension)?;
+        } else if path.extension().and_then(|e| e.to_str()) == Some(extension) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LCOV: &str = "TN:
+SF:/home/dev/dummy/src/lib.c
+FNF:2
+FNH:1
+DA:3,1
+DA:4,5
+DA:5,5
+DA:9,0
+end_of_record
+SF:/home/dev/dummy/include/lib.h
+DA:1,1
+end_of_record
+";
+
+    #[test]
+    fn test_from_lcov() {
+        // Test
+        let report = CoverageReport::from_lcov(LCOV, Path::new("/home/dev/dummy"));
+
+        // Validate
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.files[0].file, "include/lib.h");
+        assert_eq!(report.files[1].file, "src/lib.c");
+        assert_eq!(report.files[1].lines_found(), 4);
+        assert_eq!(report.files[1].lines_hit(), 3);
+        assert_eq!(report.files[1].functions_found, 2);
+        assert_eq!(report.lines_found(), 5);
+        assert_eq!(report.lines_hit(), 4);
+        assert!((report.percent() - 80.0).abs() < 1e-9);
+        assert_eq!(CoverageReport::default().percent(), 0.0);
+    }
+
+    #[test]
+    fn test_to_cobertura() {
+        let report = CoverageReport::from_lcov(LCOV, Path::new("/home/dev/dummy"));
+
+        // Test
+        let xml = report.to_cobertura("dummy", Path::new("/home/dev/dummy"));
+
+        // Validate
+        assert!(xml.contains(r#"<coverage line-rate="0.8000""#));
+        assert!(xml.contains(r#"filename="src/lib.c" line-rate="0.7500""#));
+        assert!(xml.contains(r#"<line number="9" hits="0"/>"#));
+    }
+
+    #[test]
+    fn test_flags_by_family() {
+        let dir = PathBuf::from("dummy");
+
+        // Test
+        let gcc = Coverage::new(CompilerFamily::Gcc, dir.clone(), vec![]);
+        let clang = Coverage::new(CompilerFamily::Clang, dir, vec![]);
+
+        // Validate
+        assert_eq!(gcc.compile_flags(), vec!["--coverage", "-O0", "-g"]);
+        assert_eq!(gcc.link_flags(), vec!["--coverage"]);
+        assert!(gcc.test_env().is_empty());
+        assert_eq!(
+            clang.compile_flags(),
+            vec![
+                "-fprofile-instr-generate",
+                "-fcoverage-mapping",
+                "-O0",
+                "-g"
+            ]
+        );
+        assert_eq!(clang.test_env()[0].0, "LLVM_PROFILE_FILE");
+    }
+}
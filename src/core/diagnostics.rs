@@ -0,0 +1,365 @@
+use crate::{error, Error, Result};
+use ansi_term::Colour::{Green, Red, Yellow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How build diagnostics are reported, like cargo's `--message-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Compiler output passed through, followed by a summary.
+    #[default]
+    Human,
+    /// One JSON object per line on stdout, for editors and CI.
+    Json,
+}
+
+impl MessageFormat {
+    pub fn variants() -> Vec<&'static str> {
+        vec!["human", "json"]
+    }
+
+    pub fn parse(s: &str) -> Result<MessageFormat> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(error!(
+                CustomError,
+                "Unknown message format `{}`, expected one of: {}",
+                s,
+                MessageFormat::variants().join(", ")
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageFormat::Human => "human",
+            MessageFormat::Json => "json",
+        }
+    }
+}
+
+/// A line of `--message-format json` output.
+#[derive(Debug, Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum BuildMessage<'a> {
+    CompilerMessage { message: &'a Diagnostic },
+    BuildFinished { success: bool },
+}
+
+/// A diagnostic as printed by gcc's `-fdiagnostics-format=json`.
+#[derive(Debug, Deserialize)]
+struct GccDiagnostic {
+    kind: String,
+    message: String,
+    #[serde(default)]
+    option: Option<String>,
+    #[serde(default)]
+    locations: Vec<GccLocation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GccLocation {
+    caret: GccPosition,
+}
+
+#[derive(Debug, Deserialize)]
+struct GccPosition {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Severity> {
+        match s {
+            "note" => Some(Severity::Note),
+            "warning" | "style" | "performance" | "portability" => Some(Severity::Warning),
+            "error" | "fatal error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+}
+
+/// A compiler or tool diagnostic, e.g. from gcc, clang, clang-tidy or cppcheck.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub line: u32,
+    pub column: u32,
+    pub severity: Severity,
+    pub message: String,
+    /// The warning flag or check that produced it, e.g. `-Wunused-variable` or
+    /// `bugprone-branch-clone`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub check: Option<String>,
+}
+
+impl Diagnostic {
+    /// Parses a `file:line:col: severity: message [check]` line as printed by gcc, clang and
+    /// clang-tidy. Returns `None` for anything else (source excerpts, carets, summaries).
+    pub fn parse_line(line: &str) -> Option<Diagnostic> {
+        let (location, rest) = split_severity(line)?;
+        let (severity, message) = rest;
+
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next()?.trim().parse().ok()?;
+        let line_no = parts.next()?.trim().parse().ok()?;
+        let file = parts.next()?;
+
+        let (message, check) = match message.strip_suffix(']') {
+            Some(m) => match m.rfind(" [") {
+                Some(i) => (&m[..i], Some(m[i + 2..].to_string())),
+                None => (message, None),
+            },
+            None => (message, None),
+        };
+
+        Some(Diagnostic {
+            file: PathBuf::from(file),
+            line: line_no,
+            column,
+            severity,
+            message: message.trim().to_string(),
+            check,
+        })
+    }
+
+    /// Parses every diagnostic in a tool's output, skipping notes.
+    pub fn parse_output(output: &str) -> Vec<Diagnostic> {
+        output
+            .lines()
+            .filter_map(Diagnostic::parse_line)
+            .filter(|d| d.severity != Severity::Note)
+            .collect()
+    }
+
+    /// Parses one line of gcc's `-fdiagnostics-format=json` output. Child notes and
+    /// diagnostics without a location are skipped.
+    pub fn parse_gcc_json(json: &str) -> Result<Vec<Diagnostic>> {
+        let parsed: Vec<GccDiagnostic> = serde_json::from_str(json)?;
+
+        Ok(parsed
+            .into_iter()
+            .filter_map(|d| {
+                let severity = Severity::parse(&d.kind)?;
+                let caret = &d.locations.first()?.caret;
+                Some(Diagnostic {
+                    file: PathBuf::from(&caret.file),
+                    line: caret.line,
+                    column: caret.column,
+                    severity,
+                    message: d.message,
+                    check: d.option,
+                })
+            })
+            .filter(|d| d.severity != Severity::Note)
+            .collect())
+    }
+
+    /// Parses the diagnostics in a build's output, where compilers may print either text or
+    /// JSON depending on their flags.
+    pub fn parse_build_output(output: &str) -> Vec<Diagnostic> {
+        output
+            .lines()
+            .flat_map(|line| match is_json_line(line) {
+                true => Diagnostic::parse_gcc_json(line.trim()).unwrap_or_default(),
+                false => Diagnostic::parse_line(line)
+                    .filter(|d| d.severity != Severity::Note)
+                    .into_iter()
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Whether `line` is gcc's JSON diagnostics output, printed once per translation unit, `[]`
+/// when there is nothing to report.
+pub fn is_json_line(line: &str) -> bool {
+    let line = line.trim();
+    line == "[]" || line.starts_with("[{")
+}
+
+/// Prints the warning counts per flag and a one-line summary of a build. Prints nothing when
+/// the build was clean.
+pub fn print_build_summary(diagnostics: &[Diagnostic]) {
+    if diagnostics.is_empty() {
+        return;
+    }
+
+    let mut by_flag: BTreeMap<&str, usize> = BTreeMap::new();
+    for d in diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Warning)
+    {
+        *by_flag
+            .entry(d.check.as_deref().unwrap_or("other"))
+            .or_default() += 1;
+    }
+    if !by_flag.is_empty() {
+        println!("\nWarnings by flag:");
+        for (flag, count) in &by_flag {
+            println!("  {:>4}  {}", count, Yellow.paint(*flag));
+        }
+    }
+
+    let count = |severity: Severity| {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    };
+    let errors = count(Severity::Error);
+    let mut files: Vec<&Path> = diagnostics.iter().map(|d| d.file.as_path()).collect();
+    files.sort();
+    files.dedup();
+    let summary = format!(
+        "{} warning(s), {} error(s) in {} file(s)",
+        count(Severity::Warning),
+        errors,
+        files.len()
+    );
+    match errors {
+        0 => println!("\nbuild: {}\n", Green.paint(summary)),
+        _ => println!("\nbuild: {}\n", Red.paint(summary)),
+    }
+}
+
+/// Drops diagnostics outside the project (system and dependency headers, or anything under
+/// `build_dir`) and the duplicates produced by headers included from several translation units.
+/// Paths become relative to `directory`, sorted by location.
+pub fn project_diagnostics(
+    directory: &Path,
+    build_dir: &Path,
+    diagnostics: Vec<Diagnostic>,
+) -> Vec<Diagnostic> {
+    let build = directory.join(build_dir);
+    let mut result: Vec<Diagnostic> = vec![];
+
+    for mut d in diagnostics {
+        let absolute = directory.join(&d.file);
+        if !absolute.starts_with(directory) || absolute.starts_with(&build) {
+            continue;
+        }
+        d.file = absolute
+            .strip_prefix(directory)
+            .unwrap_or(&absolute)
+            .to_path_buf();
+        if !result.contains(&d) {
+            result.push(d);
+        }
+    }
+
+    result.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    result
+}
+
+/// Splits `location: severity: message` at the severity.
+fn split_severity(line: &str) -> Option<(&str, (Severity, &str))> {
+    let mut search = 0;
+    while let Some(i) = line[search..].find(": ") {
+        let start = search + i;
+        let rest = &line[start + 2..];
+        if let Some(end) = rest.find(": ") {
+            if let Some(severity) = Severity::parse(&rest[..end]) {
+                return Some((&line[..start], (severity, &rest[end + 2..])));
+            }
+        }
+        search = start + 2;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output() {
+        let output = "\
+/home/dev/dummy/src/lib.cpp:12:5: warning: repeated branch body in conditional chain [bugprone-branch-clone]
+   12 |     if (a) {
+      |     ^
+/home/dev/dummy/src/lib.cpp:14:3: note: end of the original
+/home/dev/dummy/src/main.cpp:3:10: fatal error: 'missing.h' file not found
+src/lib.c:7:9: style: Variable 'x' is assigned a value that is never used. [unreadVariable]
+2 warnings generated.
+";
+
+        // Test
+        let diagnostics = Diagnostic::parse_output(output);
+
+        // Validate
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic {
+                file: PathBuf::from("/home/dev/dummy/src/lib.cpp"),
+                line: 12,
+                column: 5,
+                severity: Severity::Warning,
+                message: "repeated branch body in conditional chain".to_string(),
+                check: Some("bugprone-branch-clone".to_string()),
+            }
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+        assert_eq!(diagnostics[1].check, None);
+        assert_eq!(diagnostics[2].check.as_deref(), Some("unreadVariable"));
+    }
+
+    #[test]
+    fn test_parse_build_output() {
+        let output = r#"make: Entering directory '/home/dev/dummy'
+[{"kind": "warning", "locations": [{"caret": {"file": "src/lib.c", "line": 3, "display-column": 9, "byte-column": 9, "column": 9}}], "message": "unused variable 'x'", "option": "-Wunused-variable", "children": [], "column-origin": 1}, {"kind": "note", "locations": [{"caret": {"file": "src/lib.c", "line": 1, "column": 1}}], "message": "declared here", "children": []}]
+[]
+src/main.c:4:12: error: expected ';' before '}' token
+make: *** [Makefile:20: build/obj/main.o] Error 1
+"#;
+
+        // Test
+        let diagnostics = Diagnostic::parse_build_output(output);
+        let message = BuildMessage::CompilerMessage {
+            message: &diagnostics[0],
+        };
+
+        // Validate
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic {
+                file: PathBuf::from("src/lib.c"),
+                line: 3,
+                column: 9,
+                severity: Severity::Warning,
+                message: "unused variable 'x'".to_string(),
+                check: Some("-Wunused-variable".to_string()),
+            }
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+        assert!(serde_json::to_string(&message)
+            .unwrap()
+            .starts_with("{\"reason\":\"compiler-message\",\"message\":{\"file\":\"src/lib.c\""));
+        assert_eq!(
+            MessageFormat::parse("json").unwrap().as_str(),
+            MessageFormat::Json.as_str()
+        );
+        assert!(MessageFormat::parse("short").is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use super::config::ProjectConfig;
+use crate::{error, Error, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use which::which;
+
+/// Doxygen output, relative to the project directory.
+pub const DOC_DIR: &str = "build/doc";
+
+/// Doxygen configuration, created on first use and owned by the project afterwards.
+pub const DOXYFILE: &str = "Doxyfile";
+
+/// Contents of a new Doxyfile for `project`. Only the options forge relies on are set, Doxygen
+/// uses its defaults for everything else.
+pub fn doxyfile(project: &ProjectConfig) -> String {
+    let mut content = vec![];
+    content.push("# Created by forge, edit freely.".to_string());
+    content.push(format!("PROJECT_NAME           = \"{}\"", project.name));
+    content.push(format!("PROJECT_NUMBER         = {}", project.version));
+    if !project.description.is_empty() {
+        content.push(format!(
+            "PROJECT_BRIEF          = \"{}\"",
+            project.description.replace('"', "\\\"")
+        ));
+    }
+    content.push("INPUT                  = include src".to_string());
+    content.push("RECURSIVE              = YES".to_string());
+    content.push(format!("OUTPUT_DIRECTORY       = {}", DOC_DIR));
+    content.push("GENERATE_HTML          = YES".to_string());
+    content.push("GENERATE_LATEX         = NO".to_string());
+    content.push("EXTRACT_ALL            = NO".to_string());
+    content.push("WARN_IF_UNDOCUMENTED   = YES".to_string());
+    content.push("QUIET                  = YES".to_string());
+    content.push(String::new());
+    content.join("\n")
+}
+
+pub struct Doc {
+    directory: PathBuf,
+}
+
+impl Doc {
+    pub fn new(directory: PathBuf) -> Doc {
+        Doc { directory }
+    }
+
+    /// Writes the Doxyfile unless the project already has one. Returns whether it was written.
+    pub fn init(&self, project: &ProjectConfig) -> Result<bool> {
+        let path = self.directory.join(DOXYFILE);
+        if path.exists() {
+            return Ok(false);
+        }
+        fs::write(path, doxyfile(project))?;
+        Ok(true)
+    }
+
+    pub fn index(&self) -> PathBuf {
+        self.directory.join(DOC_DIR).join("html").join("index.html")
+    }
+
+    fn warnings_log(&self) -> PathBuf {
+        self.directory.join(DOC_DIR).join("warnings.log")
+    }
+
+    /// Runs doxygen with the project's Doxyfile and returns its warnings. The output and log
+    /// locations are forced so the Doxyfile can't move them.
+    pub fn generate(&self) -> Result<String> {
+        if which("doxygen").is_err() {
+            return Err(error!(CustomError, "doxygen is not installed"));
+        }
+        fs::create_dir_all(self.directory.join(DOC_DIR))?;
+
+        let mut config = fs::read_to_string(self.directory.join(DOXYFILE))?;
+        config.push_str(&format!("\nOUTPUT_DIRECTORY = {}\n", DOC_DIR));
+        config.push_str(&format!(
+            "WARN_LOGFILE = {}\n",
+            self.warnings_log().display()
+        ));
+
+        let mut child = Command::new("doxygen")
+            .arg("-")
+            .stdin(Stdio::piped())
+            .current_dir(&self.directory)
+            .spawn()?;
+        if let Some(stdin) = child.stdin.as_mut() {
+            stdin.write_all(config.as_bytes())?;
+        }
+        if !child.wait()?.success() {
+            return Err(error!(CustomError, "doxygen failed"));
+        }
+
+        Ok(fs::read_to_string(self.warnings_log()).unwrap_or_default())
+    }
+}
+
+/// Undocumented-symbol warnings from a doxygen log that point into `include_dir`.
+pub fn undocumented(log: &str, include_dir: &Path) -> Vec<String> {
+    log.lines()
+        .filter(|l| l.contains(": warning: ") && l.contains("is not documented"))
+        .filter(|l| {
+            let file = l.split(':').next().unwrap_or_default();
+            Path::new(file).starts_with(include_dir)
+        })
+        .map(|l| l.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        language::{CStandard, Language},
+        version::Version,
+    };
+
+    #[test]
+    fn test_doxyfile() {
+        let mut project = ProjectConfig::new("dummy".to_string(), Language::C(CStandard::C11));
+        project.version = Version::new(1, 2, 0);
+        project.description = "A \"small\" library".to_string();
+
+        // Test
+        let content = doxyfile(&project);
+
+        // Validate
+        assert!(content.contains("PROJECT_NAME           = \"dummy\"\n"));
+        assert!(content.contains("PROJECT_NUMBER         = 1.2.0\n"));
+        assert!(content.contains("PROJECT_BRIEF          = \"A \\\"small\\\" library\"\n"));
+        assert!(content.contains("INPUT                  = include src\n"));
+        assert!(content.contains("OUTPUT_DIRECTORY       = build/doc\n"));
+    }
+
+    #[test]
+    fn test_undocumented() {
+        let log = "/p/include/lib.h:12: warning: Member add(int a, int b) (function) of file lib.h is not documented.\n\
+                   /p/src/lib.c:3: warning: Member helper() (function) of file lib.c is not documented.\n\
+                   /p/include/lib.h:20: warning: argument 'x' of command @param is not found\n";
+
+        // Test
+        let warnings = undocumented(log, Path::new("/p/include"));
+
+        // Validate
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("/p/include/lib.h:12:"));
+    }
+}
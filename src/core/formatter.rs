@@ -0,0 +1,237 @@
+use crate::{error, Error, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use which::which;
+
+/// Directories `forge fmt` formats, relative to the project directory.
+pub const FORMAT_DIRS: [&str; 3] = ["src", "include", "test"];
+
+const SOURCE_EXTENSIONS: [&str; 10] = [
+    "c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx", "ipp", "inl",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatStyle {
+    Llvm,
+    Google,
+    Chromium,
+    Mozilla,
+    WebKit,
+    Custom,
+}
+
+impl FormatStyle {
+    pub fn variants() -> Vec<&'static str> {
+        vec!["LLVM", "Google", "Chromium", "Mozilla", "WebKit", "Custom"]
+    }
+
+    pub fn parse(s: &str) -> Result<FormatStyle> {
+        match s {
+            "LLVM" => Ok(FormatStyle::Llvm),
+            "Google" => Ok(FormatStyle::Google),
+            "Chromium" => Ok(FormatStyle::Chromium),
+            "Mozilla" => Ok(FormatStyle::Mozilla),
+            "WebKit" => Ok(FormatStyle::WebKit),
+            "Custom" => Ok(FormatStyle::Custom),
+            _ => Err(error!(
+                CustomError,
+                "Unknown format style `{}`, expected one of: {}",
+                s,
+                FormatStyle::variants().join(", ")
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FormatStyle::Llvm => "LLVM",
+            FormatStyle::Google => "Google",
+            FormatStyle::Chromium => "Chromium",
+            FormatStyle::Mozilla => "Mozilla",
+            FormatStyle::WebKit => "WebKit",
+            FormatStyle::Custom => "Custom",
+        }
+    }
+
+    /// Contents of the `.clang-format` file. Custom starts from LLVM with the options people
+    /// most often change spelled out.
+    pub fn config(&self) -> String {
+        let mut content = vec![];
+
+        match self {
+            FormatStyle::Custom => {
+                content.push("BasedOnStyle: LLVM".to_string());
+                content.push("IndentWidth: 4".to_string());
+                content.push("ColumnLimit: 100".to_string());
+                content.push("UseTab: Never".to_string());
+                content.push("BreakBeforeBraces: Attach".to_string());
+                content.push("PointerAlignment: Right".to_string());
+                content.push("AllowShortFunctionsOnASingleLine: Empty".to_string());
+                content.push("SortIncludes: true".to_string());
+            }
+            style => content.push(format!("BasedOnStyle: {}", style.as_str())),
+        }
+
+        content.push(String::new());
+        content.join("\n")
+    }
+}
+
+pub struct Formatter {
+    directory: PathBuf,
+}
+
+impl Formatter {
+    pub fn new(directory: PathBuf) -> Formatter {
+        Formatter { directory }
+    }
+
+    pub fn write_config(&self, style: &FormatStyle) -> Result<()> {
+        fs::write(self.directory.join(".clang-format"), style.config())?;
+        Ok(())
+    }
+
+    /// Every C/C++ source and header under `FORMAT_DIRS`, sorted.
+    pub fn sources(&self) -> Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        for dir in FORMAT_DIRS {
+            collect_sources(&self.directory.join(dir), &mut files)?;
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Formats every source in place.
+    pub fn format(&self) -> Result<usize> {
+        check_tool()?;
+        let files = self.sources()?;
+
+        // Pass files in batches to stay under the command line length limit.
+        for chunk in files.chunks(100) {
+            let status = Command::new("clang-format")
+                .args(["-i", "--style=file"])
+                .args(chunk)
+                .current_dir(&self.directory)
+                .status()?;
+
+            if !status.success() {
+                return Err(error!(CustomError, "clang-format failed"));
+            }
+        }
+
+        Ok(files.len())
+    }
+
+    /// Sources whose contents differ from clang-format's output, relative to the project
+    /// directory.
+    pub fn check(&self) -> Result<Vec<PathBuf>> {
+        check_tool()?;
+        let mut unformatted = vec![];
+
+        for file in self.sources()? {
+            let output = Command::new("clang-format")
+                .arg("--style=file")
+                .arg(&file)
+                .current_dir(&self.directory)
+                .output()?;
+
+            if !output.status.success() {
+                return Err(error!(
+                    CustomError,
+                    "clang-format failed on {}:\n{}",
+                    file.display(),
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            if output.stdout != fs::read(&file)? {
+                let relative = file.strip_prefix(&self.directory).unwrap_or(&file);
+                unformatted.push(relative.to_path_buf());
+            }
+        }
+
+        Ok(unformatted)
+    }
+}
+
+fn check_tool() -> Result<()> {
+    if which("clang-format").is_err() {
+        return Err(error!(CustomError, "clang-format is not installed"));
+    }
+    Ok(())
+}
+
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_sources(&path, files)?;
+        } else if is_source(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub fn is_source(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SOURCE_EXTENSIONS.contains(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::env;
+
+    #[test]
+    fn test_style_config() -> anyhow::Result<()> {
+        assert_eq!(
+            FormatStyle::parse("Google")?.config(),
+            "BasedOnStyle: Google\n"
+        );
+        assert_eq!(FormatStyle::parse("WebKit")?, FormatStyle::WebKit);
+        assert!(FormatStyle::parse("webkit").is_err());
+        assert!(FormatStyle::Custom
+            .config()
+            .starts_with("BasedOnStyle: LLVM\nIndentWidth: 4"));
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_sources() -> anyhow::Result<()> {
+        let path = env::current_dir()?.join("dummy");
+        fs::create_dir_all(path.join("src").join("detail"))?;
+        fs::create_dir_all(path.join("include"))?;
+        fs::create_dir_all(path.join("build"))?;
+        fs::write(path.join("src").join("main.cpp"), "")?;
+        fs::write(path.join("src").join("detail").join("impl.cc"), "")?;
+        fs::write(path.join("src").join("notes.txt"), "")?;
+        fs::write(path.join("include").join("lib.hpp"), "")?;
+        fs::write(path.join("build").join("generated.cpp"), "")?;
+
+        // Test
+        let sources = Formatter::new(path.clone()).sources()?;
+
+        // Validate
+        let expected = vec![
+            path.join("include").join("lib.hpp"),
+            path.join("src").join("detail").join("impl.cc"),
+            path.join("src").join("main.cpp"),
+        ];
+        assert_eq!(sources, expected);
+
+        // Clean-up
+        fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+use super::package_manager::VCPKG_MANIFEST;
+use crate::{error, Error, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files merged with the existing one instead of replaced. `.gitignore` gets the missing patterns
+/// appended, and vcpkg adds the test framework to an existing manifest.
+const MERGED_FILES: [&str; 2] = [".gitignore", VCPKG_MANIFEST];
+
+/// What `forge init` does with a file that already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExistingFiles {
+    /// Stop before writing anything.
+    #[default]
+    Refuse,
+    /// `--keep-existing`, only create the missing files.
+    Keep,
+    /// `--force`, replace them.
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    Create,
+    Keep,
+    Overwrite,
+    Merge,
+}
+
+impl FileAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileAction::Create => "create",
+            FileAction::Keep => "keep",
+            FileAction::Overwrite => "overwrite",
+            FileAction::Merge => "merge",
+        }
+    }
+}
+
+/// The files `forge init` writes and what happens to each, decided before anything is written.
+#[derive(Debug)]
+pub struct InitPlan {
+    directory: PathBuf,
+    existing: ExistingFiles,
+    files: Vec<(PathBuf, FileAction)>,
+}
+
+impl InitPlan {
+    /// `files` are relative to `directory`.
+    pub fn new(directory: &Path, files: Vec<PathBuf>, existing: ExistingFiles) -> InitPlan {
+        let files = files
+            .into_iter()
+            .map(|file| {
+                let action = if !directory.join(&file).exists() {
+                    FileAction::Create
+                } else if MERGED_FILES.iter().any(|m| file == Path::new(m)) {
+                    FileAction::Merge
+                } else if existing == ExistingFiles::Keep {
+                    FileAction::Keep
+                } else {
+                    FileAction::Overwrite
+                };
+                (file, action)
+            })
+            .collect();
+
+        InitPlan {
+            directory: directory.to_path_buf(),
+            existing,
+            files,
+        }
+    }
+
+    pub fn files(&self) -> &[(PathBuf, FileAction)] {
+        &self.files
+    }
+
+    pub fn print(&self) {
+        println!("forge init will write:");
+        for (file, action) in &self.files {
+            println!("  {:<10} {}", action.as_str(), file.display());
+        }
+    }
+
+    /// Fails when existing files would be overwritten without `--force`.
+    pub fn check(&self) -> Result<()> {
+        let overwritten: Vec<String> = self
+            .files
+            .iter()
+            .filter(|(_, action)| *action == FileAction::Overwrite)
+            .map(|(file, _)| file.display().to_string())
+            .collect();
+
+        if self.existing == ExistingFiles::Refuse && !overwritten.is_empty() {
+            return Err(error!(
+                CustomError,
+                "{} already exist(s), pass --keep-existing to keep them or --force to overwrite them",
+                overwritten.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// What happens to `file`, relative to the project directory. Files the plan doesn't list
+    /// are created.
+    pub fn action(&self, file: &Path) -> FileAction {
+        self.files
+            .iter()
+            .find(|(f, _)| f == file)
+            .map(|(_, action)| *action)
+            .unwrap_or(FileAction::Create)
+    }
+
+    /// Whether `file` is written at all, kept files are left alone.
+    pub fn writes(&self, file: &Path) -> bool {
+        self.action(file) != FileAction::Keep
+    }
+
+    /// Writes `contents` to `file` following the plan. Merged files get the lines they're
+    /// missing appended.
+    pub fn write(&self, file: &Path, contents: &str) -> Result<()> {
+        let path = self.directory.join(file);
+        match self.action(file) {
+            FileAction::Keep => Ok(()),
+            FileAction::Merge => {
+                let original = fs::read_to_string(&path)?;
+                fs::write(&path, merge_lines(&original, contents))?;
+                Ok(())
+            }
+            FileAction::Create | FileAction::Overwrite => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, contents)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// `original` with the patterns of `generated` it doesn't have yet appended.
+pub fn merge_lines(original: &str, generated: &str) -> String {
+    let existing: Vec<&str> = original.lines().map(str::trim).collect();
+    let missing: Vec<&str> = generated
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && !existing.contains(l))
+        .collect();
+    if missing.is_empty() {
+        return original.to_string();
+    }
+
+    let mut merged = original.to_string();
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+    merged.push_str("\n# Added by forge\n");
+    merged.push_str(&missing.join("\n"));
+    merged.push('\n');
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::env;
+
+    #[test]
+    #[serial]
+    fn test_init_plan() -> anyhow::Result<()> {
+        let path = env::current_dir()?.join("dummy");
+
+        // Set-up
+        fs::create_dir_all(path.join("src"))?;
+        fs::write(path.join(".gitignore"), "/build/\nnotes.txt")?;
+        fs::write(
+            path.join("src").join("main.c"),
+            "int main(void) { return 1; }\n",
+        )?;
+        let files: Vec<PathBuf> = [".gitignore", "src/main.c", "src/lib.c"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+
+        // Test
+        let refuse = InitPlan::new(&path, files.clone(), ExistingFiles::Refuse);
+        let keep = InitPlan::new(&path, files.clone(), ExistingFiles::Keep);
+        for file in &files {
+            keep.write(file, "# Build\n/build/\n.cache\n")?;
+        }
+
+        // Validate
+        let actions: Vec<FileAction> = refuse.files().iter().map(|(_, a)| *a).collect();
+        assert_eq!(
+            actions,
+            vec![FileAction::Merge, FileAction::Overwrite, FileAction::Create]
+        );
+        assert!(refuse.check().is_err());
+        assert!(keep.check().is_ok());
+        assert!(InitPlan::new(&path, files, ExistingFiles::Overwrite)
+            .check()
+            .is_ok());
+        assert_eq!(
+            fs::read_to_string(path.join(".gitignore"))?,
+            "/build/\nnotes.txt\n\n# Added by forge\n.cache\n"
+        );
+        assert_eq!(
+            fs::read_to_string(path.join("src").join("main.c"))?,
+            "int main(void) { return 1; }\n"
+        );
+        assert_eq!(
+            fs::read_to_string(path.join("src").join("lib.c"))?,
+            "# Build\n/build/\n.cache\n"
+        );
+        assert!(!keep.writes(Path::new("src/main.c")));
+
+        // Clean-up
+        fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+}
@@ -45,6 +45,13 @@ impl Language {
             Language::Cpp(_) => "CXX",
         }
     }
+
+    pub fn meson_identifier(&self) -> &str {
+        match self {
+            Language::C(_) => "c",
+            Language::Cpp(_) => "cpp",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -75,6 +82,15 @@ impl CStandard {
             _ => CStandard::C89,
         }
     }
+
+    /// Closest supported standard to a numeric one like CMake's `C_STANDARD`, e.g. `17` is C11.
+    pub fn from_version(v: &str) -> CStandard {
+        match v {
+            "89" | "90" => CStandard::C89,
+            "99" => CStandard::C99,
+            _ => CStandard::C11,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -110,4 +126,16 @@ impl CppStandard {
             _ => CppStandard::Cpp11,
         }
     }
+
+    /// Closest supported standard to a numeric one like CMake's `CXX_STANDARD`, e.g. `98` is
+    /// C++11.
+    pub fn from_version(v: &str) -> CppStandard {
+        match v {
+            "14" => CppStandard::Cpp14,
+            "17" => CppStandard::Cpp17,
+            "20" => CppStandard::Cpp20,
+            "23" | "26" => CppStandard::Cpp23,
+            _ => CppStandard::Cpp11,
+        }
+    }
 }
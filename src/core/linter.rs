@@ -0,0 +1,338 @@
+use super::compile_db::{run_parallel, CompileDb};
+use super::diagnostics::{self, Diagnostic, Severity};
+use super::language::Language;
+use crate::{error, Error, Result};
+use ansi_term::Colour::{Green, Red, Yellow};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use which::which;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintPreset {
+    Minimal,
+    Recommended,
+    Strict,
+}
+
+impl LintPreset {
+    pub fn variants() -> Vec<&'static str> {
+        vec!["Minimal", "Recommended", "Strict"]
+    }
+
+    /// Case-insensitive, so it also parses the command line.
+    pub fn parse(s: &str) -> Result<LintPreset> {
+        match s.to_ascii_lowercase().as_str() {
+            "minimal" => Ok(LintPreset::Minimal),
+            "recommended" => Ok(LintPreset::Recommended),
+            "strict" => Ok(LintPreset::Strict),
+            _ => Err(error!(
+                CustomError,
+                "Unknown preset `{}`, expected one of: {}",
+                s,
+                LintPreset::variants().join(", ")
+            )),
+        }
+    }
+
+    pub fn checks(&self, language: &Language) -> Vec<&'static str> {
+        let mut checks = vec!["-*", "clang-analyzer-*", "bugprone-*"];
+
+        match self {
+            LintPreset::Minimal => (),
+            LintPreset::Recommended => {
+                checks.extend(["performance-*", "portability-*"]);
+                checks.extend([
+                    "readability-misleading-indentation",
+                    "misc-redundant-expression",
+                ]);
+            }
+            LintPreset::Strict => {
+                checks.extend(["performance-*", "portability-*", "readability-*", "misc-*"]);
+                checks.extend(["cert-*", "-readability-magic-numbers"]);
+            }
+        }
+
+        if let (Language::Cpp(_), LintPreset::Recommended | LintPreset::Strict) = (language, self) {
+            checks.extend(["modernize-*", "-modernize-use-trailing-return-type"]);
+        }
+        if let (Language::Cpp(_), LintPreset::Strict) = (language, self) {
+            checks.push("cppcoreguidelines-*");
+        }
+
+        checks
+    }
+
+    /// Contents of the `.clang-tidy` file.
+    pub fn config(&self, language: &Language) -> String {
+        let mut content = vec!["Checks: >-".to_string()];
+        let checks = self.checks(language);
+        for (i, check) in checks.iter().enumerate() {
+            let sep = if i + 1 < checks.len() { "," } else { "" };
+            content.push(format!("  {}{}", check, sep));
+        }
+        content.push("WarningsAsErrors: ''".to_string());
+        content.push("HeaderFilterRegex: '.*/(src|include|test)/.*'".to_string());
+        content.push("FormatStyle: file".to_string());
+        content.push(String::new());
+        content.join("\n")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintBackend {
+    ClangTidy,
+    Cppcheck,
+}
+
+impl LintBackend {
+    pub fn variants() -> Vec<&'static str> {
+        vec!["clang-tidy", "cppcheck"]
+    }
+
+    pub fn parse(s: &str) -> Result<LintBackend> {
+        match s {
+            "clang-tidy" => Ok(LintBackend::ClangTidy),
+            "cppcheck" => Ok(LintBackend::Cppcheck),
+            _ => Err(error!(
+                CustomError,
+                "Unknown lint backend `{}`, expected one of: {}",
+                s,
+                LintBackend::variants().join(", ")
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintBackend::ClangTidy => "clang-tidy",
+            LintBackend::Cppcheck => "cppcheck",
+        }
+    }
+}
+
+pub struct Linter {
+    directory: PathBuf,
+    build_dir: PathBuf,
+    language: Language,
+}
+
+impl Linter {
+    pub fn new(directory: PathBuf, build_dir: PathBuf, language: Language) -> Linter {
+        Linter {
+            directory,
+            build_dir,
+            language,
+        }
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.directory.join(".clang-tidy")
+    }
+
+    pub fn write_config(&self, preset: &LintPreset) -> Result<()> {
+        fs::write(self.config_path(), preset.config(&self.language))?;
+        Ok(())
+    }
+
+    /// Runs `backend` over `sources`, the absolute paths of the project's translation units,
+    /// and returns the deduplicated diagnostics for project files.
+    pub fn lint(
+        &self,
+        backend: &LintBackend,
+        sources: &[PathBuf],
+        fix: bool,
+        jobs: usize,
+    ) -> Result<Vec<Diagnostic>> {
+        if which(backend.as_str()).is_err() {
+            return Err(error!(CustomError, "{} is not installed", backend.as_str()));
+        }
+
+        let output = match backend {
+            LintBackend::ClangTidy => self.clang_tidy(sources, fix, jobs)?,
+            LintBackend::Cppcheck if fix => {
+                return Err(error!(CustomError, "cppcheck can't apply fixes"));
+            }
+            LintBackend::Cppcheck => self.cppcheck(jobs)?,
+        };
+
+        Ok(self.project_diagnostics(Diagnostic::parse_output(&output)))
+    }
+
+    fn clang_tidy(&self, sources: &[PathBuf], fix: bool, jobs: usize) -> Result<String> {
+        let db_path = self
+            .directory
+            .join(&self.build_dir)
+            .join("compile_commands.json");
+        let db = CompileDb::from_file(&db_path)?;
+        let files: Vec<PathBuf> = db
+            .project_commands(sources)
+            .iter()
+            .map(|c| c.path())
+            .collect();
+
+        if files.is_empty() {
+            return Err(error!(CustomError, "No translation units to lint"));
+        }
+
+        // Fixes to a shared header from several processes would clobber each other.
+        let jobs = if fix { 1 } else { jobs };
+        println!("Linting {} file(s) with clang-tidy", files.len());
+
+        let outputs = run_parallel(&files, jobs, |file| {
+            let mut cmd = Command::new("clang-tidy");
+            cmd.arg("-p").arg(&self.build_dir).arg("--quiet");
+            if fix {
+                cmd.arg("--fix");
+            }
+            cmd.arg(file).current_dir(&self.directory).output()
+        });
+
+        let mut combined = String::new();
+        for output in outputs {
+            let output = output?;
+            combined.push_str(&String::from_utf8_lossy(&output.stdout));
+            combined.push_str(&String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(combined)
+    }
+
+    fn cppcheck(&self, jobs: usize) -> Result<String> {
+        let db_path = self
+            .directory
+            .join(&self.build_dir)
+            .join("compile_commands.json");
+        if !db_path.exists() {
+            return Err(error!(
+                CustomError,
+                "{} does not exist, configure the project first",
+                db_path.display()
+            ));
+        }
+
+        println!("Linting with cppcheck");
+        let output = Command::new("cppcheck")
+            .arg(format!("--project={}", db_path.display()))
+            .arg("--enable=warning,style,performance,portability")
+            .arg("--inline-suppr")
+            .arg("--suppress=missingIncludeSystem")
+            .arg("--quiet")
+            .arg(format!("-j{}", jobs))
+            .arg("-i")
+            .arg(self.directory.join(&self.build_dir))
+            .arg("--template={file}:{line}:{column}: {severity}: {message} [{id}]")
+            .current_dir(&self.directory)
+            .output()?;
+
+        Ok(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+
+    fn project_diagnostics(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics::project_diagnostics(&self.directory, &self.build_dir, diagnostics)
+    }
+}
+
+/// Prints each diagnostic followed by the number of findings per check.
+pub fn print_summary(diagnostics: &[Diagnostic]) {
+    if diagnostics.is_empty() {
+        println!("\nlint: {}\n", Green.paint("no warnings"));
+        return;
+    }
+
+    println!();
+    for d in diagnostics {
+        let severity = match d.severity {
+            Severity::Error => Red.paint(d.severity.as_str()),
+            _ => Yellow.paint(d.severity.as_str()),
+        };
+        let check = d.check.as_deref().map(|c| format!(" [{}]", c));
+        println!(
+            "{}:{}:{}: {}: {}{}",
+            d.file.display(),
+            d.line,
+            d.column,
+            severity,
+            d.message,
+            check.unwrap_or_default()
+        );
+    }
+
+    let mut checks: BTreeMap<&str, usize> = BTreeMap::new();
+    for d in diagnostics {
+        *checks
+            .entry(d.check.as_deref().unwrap_or("other"))
+            .or_default() += 1;
+    }
+    let mut checks: Vec<(&str, usize)> = checks.into_iter().collect();
+    checks.sort_by_key(|c| std::cmp::Reverse(c.1));
+
+    println!("\nWarnings by check:");
+    for (check, count) in checks {
+        println!("{:>6}  {}", count, check);
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let mut files: Vec<&Path> = diagnostics.iter().map(|d| d.file.as_path()).collect();
+    files.dedup();
+
+    println!(
+        "\nlint: {} warning(s), {} error(s) in {} file(s)\n",
+        diagnostics.len() - errors,
+        errors,
+        files.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::language::{CStandard, CppStandard};
+
+    #[test]
+    fn test_preset_config() -> anyhow::Result<()> {
+        let cpp = Language::Cpp(CppStandard::Cpp17);
+        let c = Language::C(CStandard::C11);
+
+        // Test
+        let preset = LintPreset::parse("recommended")?;
+
+        // Validate
+        assert_eq!(preset, LintPreset::Recommended);
+        assert!(preset.checks(&cpp).contains(&"modernize-*"));
+        assert!(!preset.checks(&c).contains(&"modernize-*"));
+        assert!(LintPreset::Minimal
+            .config(&c)
+            .starts_with("Checks: >-\n  -*,\n  clang-analyzer-*,\n  bugprone-*\nWarningsAsErrors"));
+        assert!(LintPreset::parse("pedantic").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_diagnostics() {
+        let linter = Linter::new(
+            PathBuf::from("/home/dev/dummy"),
+            PathBuf::from("build"),
+            Language::C(CStandard::C11),
+        );
+        let output = "\
+/home/dev/dummy/include/lib.h:3:1: warning: header warning [misc-a]
+/home/dev/dummy/src/main.c:4:2: warning: main warning [bugprone-b]
+/usr/include/stdio.h:10:1: warning: system warning [misc-a]
+/home/dev/dummy/build/_deps/x.c:1:1: warning: dependency warning [misc-a]
+/home/dev/dummy/include/lib.h:3:1: warning: header warning [misc-a]
+";
+
+        // Test
+        let actual = linter.project_diagnostics(Diagnostic::parse_output(output));
+
+        // Validate
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].file, PathBuf::from("include/lib.h"));
+        assert_eq!(actual[1].file, PathBuf::from("src/main.c"));
+    }
+}
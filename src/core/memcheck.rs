@@ -0,0 +1,326 @@
+use crate::{error, Error, Result};
+use ansi_term::Colour::{Green, Red};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use which::which;
+
+/// Where valgrind writes its XML logs, relative to the project directory.
+pub const MEMCHECK_DIR: &str = "build/memcheck";
+
+/// `[memcheck]` section of Forge.toml.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MemcheckConfig {
+    /// Valgrind suppression files, relative to the project directory.
+    #[serde(default)]
+    pub suppressions: Vec<PathBuf>,
+}
+
+pub struct Memcheck {
+    directory: PathBuf,
+    config: MemcheckConfig,
+}
+
+impl Memcheck {
+    pub fn new(directory: PathBuf, config: MemcheckConfig) -> Memcheck {
+        Memcheck { directory, config }
+    }
+
+    pub fn log_dir(&self) -> PathBuf {
+        self.directory.join(MEMCHECK_DIR)
+    }
+
+    /// Clears logs from a previous run and checks valgrind is installed.
+    pub fn prepare(&self) -> Result<()> {
+        if which("valgrind").is_err() {
+            return Err(error!(CustomError, "valgrind is required for --memcheck"));
+        }
+
+        for supp in &self.config.suppressions {
+            if !self.directory.join(supp).exists() {
+                return Err(error!(
+                    CustomError,
+                    "Suppression file {} does not exist",
+                    supp.display()
+                ));
+            }
+        }
+
+        if self.log_dir().exists() {
+            fs::remove_dir_all(self.log_dir())?;
+        }
+        fs::create_dir_all(self.log_dir())?;
+        Ok(())
+    }
+
+    /// The valgrind command line used to wrap a binary. `prefix` names the XML logs, one per
+    /// process.
+    pub fn wrapper(&self, prefix: &str) -> Vec<String> {
+        let xml_file = self.log_dir().join(format!("{}-%p.xml", prefix));
+
+        let mut args = vec![
+            "valgrind".to_string(),
+            "--tool=memcheck".to_string(),
+            "--leak-check=full".to_string(),
+            "--track-origins=yes".to_string(),
+            "--xml=yes".to_string(),
+            format!("--xml-file={}", xml_file.display()),
+        ];
+
+        for supp in &self.config.suppressions {
+            args.push(format!(
+                "--suppressions={}",
+                self.directory.join(supp).display()
+            ));
+        }
+        args
+    }
+
+    /// Collects the errors from every log written since `prepare`.
+    pub fn summary(&self) -> Result<MemcheckSummary> {
+        let mut summary = MemcheckSummary::default();
+
+        for entry in fs::read_dir(self.log_dir())? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("xml") {
+                let contents = fs::read_to_string(&path)?;
+                summary.errors.extend(MemcheckSummary::from_xml(&contents)?);
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemcheckError {
+    /// Valgrind error kind, e.g. `InvalidRead` or `Leak_DefinitelyLost`.
+    pub kind: String,
+    pub what: String,
+    pub leaked_bytes: u64,
+    /// First stack frame with source information, as `file:line`.
+    pub location: Option<String>,
+}
+
+impl MemcheckError {
+    pub fn is_leak(&self) -> bool {
+        self.kind.starts_with("Leak_")
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemcheckSummary {
+    pub errors: Vec<MemcheckError>,
+}
+
+impl MemcheckSummary {
+    /// Parses a valgrind `--xml=yes` log.
+    pub fn from_xml(xml: &str) -> Result<Vec<MemcheckError>> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut errors = vec![];
+        let mut current: Option<MemcheckError> = None;
+        let mut path: Vec<String> = vec![];
+        let mut frame_file: Option<String> = None;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => {
+                    let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                    match name.as_str() {
+                        "error" => current = Some(MemcheckError::default()),
+                        "frame" => frame_file = None,
+                        _ => (),
+                    }
+                    path.push(name);
+                }
+                Event::End(e) => {
+                    match e.name().as_ref() {
+                        b"error" => {
+                            if let Some(err) = current.take() {
+                                errors.push(err);
+                            }
+                        }
+                        b"frame" => frame_file = None,
+                        _ => (),
+                    }
+                    path.pop();
+                }
+                Event::Text(t) => {
+                    let Some(err) = current.as_mut() else {
+                        continue;
+                    };
+                    let text = t.unescape()?.into_owned();
+                    let parent = path.len().checked_sub(2).map(|i| path[i].as_str());
+
+                    match (parent, path.last().map(|s| s.as_str())) {
+                        (Some("error"), Some("kind")) => err.kind = text,
+                        (Some("error"), Some("what")) => err.what = text,
+                        (Some("xwhat"), Some("text")) => err.what = text,
+                        (Some("xwhat"), Some("leakedbytes")) => {
+                            err.leaked_bytes = text.parse().unwrap_or(0)
+                        }
+                        (Some("frame"), Some("file")) => frame_file = Some(text),
+                        (Some("frame"), Some("line")) => {
+                            // Skip valgrind's own allocator replacements.
+                            if let Some(file) = frame_file.take() {
+                                if err.location.is_none() && !file.starts_with("vg_replace") {
+                                    err.location = Some(format!("{}:{}", file, text));
+                                }
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+
+        Ok(errors)
+    }
+
+    pub fn leaked_bytes(&self) -> u64 {
+        self.errors.iter().map(|e| e.leaked_bytes).sum()
+    }
+
+    pub fn success(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn print_summary(&self) {
+        if self.success() {
+            println!("\nmemcheck: {}\n", Green.paint("no errors"));
+            return;
+        }
+
+        let mut kinds: BTreeMap<&str, usize> = BTreeMap::new();
+        for err in &self.errors {
+            *kinds.entry(err.kind.as_str()).or_default() += 1;
+        }
+
+        println!();
+        for err in &self.errors {
+            let location = err.location.as_deref().unwrap_or("<unknown>");
+            println!("{} {} ({})", Red.paint(&err.kind), err.what, location);
+        }
+
+        let kinds: Vec<String> = kinds.iter().map(|(k, n)| format!("{} {}", n, k)).collect();
+        println!(
+            "\nmemcheck: {}. {} error(s): {}; {} bytes leaked\n",
+            Red.paint("FAILED"),
+            self.errors.len(),
+            kinds.join(", "),
+            self.leaked_bytes()
+        );
+    }
+}
+
+/// Splits a wrapper into the program and its options, for runners that take them separately
+/// (ctest's `MemoryCheckCommand` and `MemoryCheckCommandOptions`).
+pub fn split_wrapper(wrapper: &[String]) -> (&str, String) {
+    let (cmd, args) = wrapper.split_first().map_or(("", &[][..]), |(c, a)| (c, a));
+    (cmd, args.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALGRIND_XML: &str = r#"<?xml version="1.0"?>
+<valgrindoutput>
+<protocolversion>4</protocolversion>
+<error>
+  <unique>0x0</unique>
+  <tid>1</tid>
+  <kind>InvalidRead</kind>
+  <what>Invalid read of size 4</what>
+  <stack>
+    <frame>
+      <ip>0x109156</ip>
+      <obj>/home/dev/dummy/build/bin/dummy</obj>
+      <fn>main</fn>
+      <dir>/home/dev/dummy/src</dir>
+      <file>main.c</file>
+      <line>7</line>
+    </frame>
+  </stack>
+</error>
+<error>
+  <unique>0x1</unique>
+  <tid>1</tid>
+  <kind>Leak_DefinitelyLost</kind>
+  <xwhat>
+    <text>40 bytes in 1 blocks are definitely lost in loss record 1 of 1</text>
+    <leakedbytes>40</leakedbytes>
+    <leakedblocks>1</leakedblocks>
+  </xwhat>
+  <stack>
+    <frame>
+      <ip>0x483B7F3</ip>
+      <fn>malloc</fn>
+      <file>vg_replace_malloc.c</file>
+      <line>309</line>
+    </frame>
+    <frame>
+      <ip>0x10914A</ip>
+      <fn>get_greeting</fn>
+      <file>lib.c</file>
+      <line>4</line>
+    </frame>
+  </stack>
+</error>
+<errorcounts>
+</errorcounts>
+</valgrindoutput>
+"#;
+
+    #[test]
+    fn test_from_xml() -> anyhow::Result<()> {
+        // Test
+        let errors = MemcheckSummary::from_xml(VALGRIND_XML)?;
+
+        // Validate
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].kind, "InvalidRead");
+        assert_eq!(errors[0].what, "Invalid read of size 4");
+        assert_eq!(errors[0].location.as_deref(), Some("main.c:7"));
+        assert!(!errors[0].is_leak());
+        assert_eq!(errors[1].kind, "Leak_DefinitelyLost");
+        assert_eq!(errors[1].leaked_bytes, 40);
+        assert_eq!(errors[1].location.as_deref(), Some("lib.c:4"));
+        assert!(errors[1].is_leak());
+
+        let summary = MemcheckSummary { errors };
+        assert_eq!(summary.leaked_bytes(), 40);
+        assert!(!summary.success());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrapper() {
+        let config = MemcheckConfig {
+            suppressions: vec![PathBuf::from("valgrind.supp")],
+        };
+        let memcheck = Memcheck::new(PathBuf::from("/home/dev/dummy"), config);
+
+        // Test
+        let wrapper = memcheck.wrapper("run");
+
+        // Validate
+        assert_eq!(wrapper[0], "valgrind");
+        assert!(
+            wrapper.contains(&"--xml-file=/home/dev/dummy/build/memcheck/run-%p.xml".to_string())
+        );
+        assert!(wrapper.contains(&"--suppressions=/home/dev/dummy/valgrind.supp".to_string()));
+
+        let (cmd, options) = split_wrapper(&wrapper);
+        assert_eq!(cmd, "valgrind");
+        assert!(options.starts_with("--tool=memcheck --leak-check=full"));
+    }
+}
@@ -1,9 +1,33 @@
+pub mod bench;
 pub mod build_system;
+pub mod build_tree;
+pub mod checker;
+pub mod clean;
+pub mod cmake_import;
+pub mod compile_db;
 pub mod compiler;
+pub mod compiler_cache;
 pub mod config;
+pub mod coverage;
+pub mod diagnostics;
+pub mod doc;
+pub mod formatter;
+pub mod init_plan;
 pub mod language;
+pub mod linter;
+pub mod memcheck;
 pub mod package_manager;
+pub mod packager;
+pub mod profile;
+pub mod regen;
+pub mod sanitizer;
 pub mod scaffolder;
+pub mod targets;
 pub mod test_framework;
+pub mod test_report;
+pub mod test_selection;
+pub mod timings;
+pub mod version;
+pub mod warnings;
 
 pub use config::ForgeConfig;
@@ -1,7 +1,11 @@
-use super::{language::Language, test_framework::TestFramework};
+use super::{bench::BenchFramework, language::Language, test_framework::TestFramework};
 use crate::{error, Error, Result};
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, process::Command};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
 use which::which;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -46,6 +50,12 @@ impl PackageManager {
         }
     }
 
+    /// Moves the project to `directory`.
+    pub fn relocate(&mut self, directory: &Path) {
+        self.directory = directory.to_path_buf();
+        self.test_framework.relocate(directory);
+    }
+
     pub fn init(&self) -> Result<()> {
         match self.variant {
             PackageManagers::Vcpkg => VcpkgManager::init(&self.directory)?,
@@ -61,23 +71,80 @@ impl PackageManager {
         }
         Ok(())
     }
+
+    /// Manifest files written by `init` and `config`, relative to the project directory.
+    pub fn manifest_files(&self) -> Vec<PathBuf> {
+        match self.variant {
+            PackageManagers::Vcpkg => vec![
+                PathBuf::from(VCPKG_MANIFEST),
+                PathBuf::from(VCPKG_CONFIGURATION),
+            ],
+            PackageManagers::Conan => vec![],
+        }
+    }
+
+    /// Directories the installed dependencies live in, relative to the project directory.
+    /// Conan keeps its packages in the user's cache instead.
+    pub fn deps_dirs(&self) -> Vec<PathBuf> {
+        match self.variant {
+            PackageManagers::Vcpkg => vec![PathBuf::from("vcpkg_installed")],
+            PackageManagers::Conan => vec![],
+        }
+    }
+
+    /// Adds the benchmark framework to the project's dependencies.
+    pub fn bench_setup(&self, bench: BenchFramework) -> Result<()> {
+        match self.variant {
+            PackageManagers::Vcpkg => bench.vcpkg_setup(&self.directory),
+            PackageManagers::Conan => bench.conan_setup(),
+        }
+    }
 }
 
+pub const VCPKG_MANIFEST: &str = "vcpkg.json";
+pub const VCPKG_CONFIGURATION: &str = "vcpkg-configuration.json";
+
 struct VcpkgManager;
 
 impl VcpkgManager {
-    pub fn init(project_dir: &PathBuf) -> Result<()> {
-        match which("vcpkg") {
-            Ok(_) => Command::new("vcpkg")
-                .args(["new", "--application"])
-                .current_dir(project_dir)
-                .status()
-                .map_err(|e| error!(CustomError, "vcpkg failed: {}", e))?
-                .success()
-                .then_some(())
-                .ok_or_else(|| error!(CustomError, "vcpkg command failed")),
-            Err(e) => Err(error!(CustomError, "{}", e)),
+    pub fn init(project_dir: &Path) -> Result<()> {
+        // An existing manifest is kept, `config` adds the test framework to it.
+        let manifest = fs::read_to_string(project_dir.join(VCPKG_MANIFEST)).ok();
+        let configuration = project_dir.join(VCPKG_CONFIGURATION);
+        let has_baseline = configuration.exists()
+            || manifest
+                .as_deref()
+                .is_some_and(|m| m.contains("\"builtin-baseline\""));
+        if manifest.is_some() && has_baseline {
+            return Ok(());
+        }
+        if let Err(e) = which("vcpkg") {
+            return Err(error!(CustomError, "{}", e));
         }
+        if manifest.is_none() {
+            return Self::new_manifest(project_dir);
+        }
+
+        // `vcpkg new` won't run next to a manifest, so the registry baseline it picks is taken
+        // from a manifest created elsewhere.
+        let scratch = env::temp_dir().join(format!("forge-vcpkg-{}", process::id()));
+        fs::create_dir_all(&scratch)?;
+        let created = Self::new_manifest(&scratch)
+            .and_then(|_| Ok(fs::copy(scratch.join(VCPKG_CONFIGURATION), &configuration)?));
+        fs::remove_dir_all(&scratch)?;
+        created?;
+        Ok(())
+    }
+
+    fn new_manifest(dir: &Path) -> Result<()> {
+        Command::new("vcpkg")
+            .args(["new", "--application"])
+            .current_dir(dir)
+            .status()
+            .map_err(|e| error!(CustomError, "vcpkg failed: {}", e))?
+            .success()
+            .then_some(())
+            .ok_or_else(|| error!(CustomError, "vcpkg command failed"))
     }
 
     pub fn config(dir: &PathBuf, test_framework: &TestFramework) -> Result<()> {
@@ -0,0 +1,178 @@
+use super::config::ProjectConfig;
+use crate::{error, Error, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use strum_macros::{Display, EnumString};
+
+/// Where `forge package` writes artifacts, relative to the project directory.
+pub const PACKAGE_DIR: &str = "build/package";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum PackageFormat {
+    Tgz,
+    Deb,
+    Rpm,
+}
+
+impl PackageFormat {
+    pub fn variants() -> Vec<&'static str> {
+        vec!["tgz", "deb", "rpm"]
+    }
+
+    /// Parses a comma separated list such as `tgz,deb`.
+    pub fn parse_list(s: &str) -> Result<Vec<PackageFormat>> {
+        let mut formats = vec![];
+
+        for name in s.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let format: PackageFormat = name.parse().map_err(|_| {
+                error!(
+                    CustomError,
+                    "Unknown package format `{}`, expected one of: {}",
+                    name,
+                    PackageFormat::variants().join(", ")
+                )
+            })?;
+            formats.push(format);
+        }
+
+        formats.sort();
+        formats.dedup();
+
+        if formats.is_empty() {
+            return Err(error!(CustomError, "No package formats given"));
+        }
+        Ok(formats)
+    }
+
+    pub fn cpack_generator(&self) -> &'static str {
+        match self {
+            PackageFormat::Tgz => "TGZ",
+            PackageFormat::Deb => "DEB",
+            PackageFormat::Rpm => "RPM",
+        }
+    }
+}
+
+/// `<name>-<version>-<os>`, the archive name CPack uses by default.
+pub fn archive_stem(project: &ProjectConfig) -> String {
+    format!(
+        "{}-{}-{}",
+        project.name,
+        project.version,
+        std::env::consts::OS
+    )
+}
+
+/// Writes `<out_dir>/<stem>.tar.gz` from the `<stem>` directory in `stage`.
+pub fn archive(stage: &Path, stem: &str, out_dir: &Path) -> Result<PathBuf> {
+    let tarball = out_dir.join(format!("{}.tar.gz", stem));
+
+    let tar_status = Command::new("tar")
+        .arg("czf")
+        .arg(&tarball)
+        .arg("-C")
+        .arg(stage)
+        .arg(stem)
+        .status()?;
+
+    if !tar_status.success() {
+        return Err(error!(
+            CustomError,
+            "Failed to create {}",
+            tarball.display()
+        ));
+    }
+    Ok(tarball)
+}
+
+/// Package files CPack left in `out_dir`, sorted.
+pub fn package_artifacts(out_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut artifacts = vec![];
+    for entry in fs::read_dir(out_dir)? {
+        let path = entry?.path();
+        let name = path.to_string_lossy();
+        if path.is_file()
+            && [".tar.gz", ".deb", ".rpm"]
+                .iter()
+                .any(|e| name.ends_with(e))
+        {
+            artifacts.push(path);
+        }
+    }
+    artifacts.sort();
+    Ok(artifacts)
+}
+
+/// Quotes `s` for use as a CMake string argument.
+pub fn cmake_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// CPack variables and `include(CPack)` for the generated CMakeLists.txt.
+pub fn cpack_rules(project: &ProjectConfig) -> String {
+    let maintainer = project.maintainer();
+
+    let mut contents = vec![];
+    contents.push("# Packaging".to_string());
+    contents.push(format!("set(CPACK_PACKAGE_NAME {})", project.name));
+    contents.push(format!(
+        "set(CPACK_PACKAGE_VENDOR {})",
+        cmake_string(maintainer)
+    ));
+    contents.push(format!(
+        "set(CPACK_PACKAGE_CONTACT {})",
+        cmake_string(maintainer)
+    ));
+    contents.push("set(CPACK_PACKAGE_DESCRIPTION_SUMMARY \"${PROJECT_DESCRIPTION}\")".to_string());
+    contents.push(format!(
+        "set(CPACK_DEBIAN_PACKAGE_MAINTAINER {})",
+        cmake_string(maintainer)
+    ));
+    if !project.license.is_empty() {
+        contents.push(format!(
+            "set(CPACK_RPM_PACKAGE_LICENSE {})",
+            cmake_string(&project.license)
+        ));
+    }
+    contents.push("set(CPACK_GENERATOR TGZ)".to_string());
+    contents.push("include(CPack)\n".to_string());
+    contents.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::language::{CStandard, Language};
+
+    #[test]
+    fn test_parse_list() -> anyhow::Result<()> {
+        // Test
+        let formats = PackageFormat::parse_list("rpm, tgz,rpm")?;
+
+        // Validate
+        assert_eq!(formats, vec![PackageFormat::Tgz, PackageFormat::Rpm]);
+        assert!(PackageFormat::parse_list("zip").is_err());
+        assert!(PackageFormat::parse_list("").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cpack_rules() {
+        let mut project = ProjectConfig::new("dummy".to_string(), Language::C(CStandard::C99));
+        project.maintainer = "Jane \"JD\" Doe <jane@example.com>".to_string();
+        project.license = "MIT".to_string();
+
+        // Test
+        let rules = cpack_rules(&project);
+
+        // Validate
+        assert!(rules.contains(
+            "set(CPACK_DEBIAN_PACKAGE_MAINTAINER \"Jane \\\"JD\\\" Doe <jane@example.com>\")"
+        ));
+        assert!(rules.contains("set(CPACK_RPM_PACKAGE_LICENSE \"MIT\")"));
+        assert!(rules.ends_with("include(CPack)\n"));
+    }
+}
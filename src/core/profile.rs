@@ -0,0 +1,330 @@
+use super::build_system::BUILD_DIR;
+use super::compiler;
+use super::language::Language;
+use crate::{error, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use strum_macros::{Display, EnumString};
+use which::which;
+
+/// Build configuration a tree is compiled with. Debug uses the default tree, the other profiles
+/// get their own tree under `build/`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Profile {
+    #[default]
+    Debug,
+    Release,
+}
+
+impl Profile {
+    pub fn variants() -> Vec<&'static str> {
+        vec!["debug", "release"]
+    }
+
+    pub fn parse(s: &str) -> Result<Profile> {
+        s.parse().map_err(|_| {
+            error!(
+                CustomError,
+                "Unknown profile `{}`, expected one of: {}",
+                s,
+                Profile::variants().join(", ")
+            )
+        })
+    }
+
+    pub fn from_release(release: bool) -> Profile {
+        match release {
+            true => Profile::Release,
+            false => Profile::Debug,
+        }
+    }
+
+    /// Build tree, relative to the project directory.
+    pub fn build_dir(&self) -> PathBuf {
+        match self {
+            Profile::Debug => PathBuf::from(BUILD_DIR),
+            Profile::Release => PathBuf::from(BUILD_DIR).join("release"),
+        }
+    }
+
+    /// Suffix for trees derived from this profile, e.g. sanitized ones.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Profile::Debug => "",
+            Profile::Release => "-release",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Lto {
+    #[default]
+    Off,
+    /// clang's ThinLTO.
+    Thin,
+    Full,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Linker {
+    /// Whatever the compiler uses by default.
+    #[default]
+    Default,
+    Lld,
+    Mold,
+    Gold,
+}
+
+impl Linker {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Linker::Default => "default",
+            Linker::Lld => "lld",
+            Linker::Mold => "mold",
+            Linker::Gold => "gold",
+        }
+    }
+}
+
+/// `[build.debug]` and `[build.release]` sections of Forge.toml.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProfileConfig {
+    #[serde(default)]
+    pub lto: Lto,
+    #[serde(default)]
+    pub linker: Linker,
+}
+
+impl ProfileConfig {
+    pub fn is_default(&self) -> bool {
+        *self == ProfileConfig::default()
+    }
+
+    /// Flags for compiling and linking, except for full LTO under CMake, which uses
+    /// `INTERPROCEDURAL_OPTIMIZATION`.
+    pub fn compile_flags(&self) -> Vec<String> {
+        match self.lto {
+            Lto::Off => vec![],
+            Lto::Thin => vec!["-flto=thin".to_string()],
+            Lto::Full => vec!["-flto".to_string()],
+        }
+    }
+
+    pub fn link_flags(&self) -> Vec<String> {
+        let mut flags = self.compile_flags();
+        if self.linker != Linker::Default {
+            flags.push(format!("-fuse-ld={}", self.linker.as_str()));
+        }
+        flags
+    }
+
+    /// Checks that the linker is installed and that `compiler` can link with these settings.
+    pub fn check_support(&self, compiler: &str, language: &Language) -> Result<()> {
+        if self.linker != Linker::Default {
+            let executable = format!("ld.{}", self.linker.as_str());
+            if which(&executable).is_err() {
+                return Err(error!(
+                    CustomError,
+                    "The {} linker is not installed, {} was not found",
+                    self.linker.as_str(),
+                    executable
+                ));
+            }
+        }
+
+        let flags = self.link_flags();
+        if let Some(errors) = compiler::probe(compiler, language, &flags)? {
+            return Err(error!(
+                CustomError,
+                "{} can't link with {}:\n{}",
+                compiler,
+                flags.join(" "),
+                errors
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Per-profile settings, flattened into `[build]`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProfilesConfig {
+    #[serde(default, skip_serializing_if = "ProfileConfig::is_default")]
+    pub debug: ProfileConfig,
+    #[serde(default, skip_serializing_if = "ProfileConfig::is_default")]
+    pub release: ProfileConfig,
+}
+
+impl ProfilesConfig {
+    pub fn get(&self, profile: Profile) -> &ProfileConfig {
+        match profile {
+            Profile::Debug => &self.debug,
+            Profile::Release => &self.release,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.debug.is_default() && self.release.is_default()
+    }
+
+    /// CMake snippet applying each profile's settings, keyed on `CMAKE_BUILD_TYPE` since the
+    /// debug tree is configured without one.
+    pub fn cmake_rules(&self) -> String {
+        let rules = |config: &ProfileConfig| {
+            let mut lines = vec![];
+            let compile = match config.lto {
+                Lto::Full => {
+                    lines.push("    set(CMAKE_INTERPROCEDURAL_OPTIMIZATION ON)".to_string());
+                    vec![]
+                }
+                _ => config.compile_flags(),
+            };
+            let link: Vec<String> = config
+                .link_flags()
+                .into_iter()
+                .filter(|f| config.lto != Lto::Full || f.starts_with("-fuse-ld"))
+                .collect();
+            if !compile.is_empty() {
+                lines.push(format!("    add_compile_options({})", compile.join(" ")));
+            }
+            if !link.is_empty() {
+                lines.push(format!("    add_link_options({})", link.join(" ")));
+            }
+            lines
+        };
+
+        let mut contents = vec!["# Link-time optimization and linker, per profile".to_string()];
+        contents.push("if(CMAKE_BUILD_TYPE STREQUAL \"Release\")".to_string());
+        contents.extend(rules(&self.release));
+        contents.push("else()".to_string());
+        contents.extend(rules(&self.debug));
+        contents.push("endif()\n".to_string());
+        contents.join("\n")
+    }
+
+    /// Meson snippet applying each profile's settings, keyed on the buildtype.
+    pub fn meson_rules(&self, lang: &str) -> String {
+        let list = |flags: Vec<String>| {
+            let quoted: Vec<String> = flags.iter().map(|f| format!("'{}'", f)).collect();
+            quoted.join(", ")
+        };
+        let rules = |config: &ProfileConfig| {
+            let mut lines = vec![];
+            if !config.compile_flags().is_empty() {
+                lines.push(format!(
+                    "  add_project_arguments({}, language: '{}')",
+                    list(config.compile_flags()),
+                    lang
+                ));
+            }
+            if !config.link_flags().is_empty() {
+                lines.push(format!(
+                    "  add_project_link_arguments({}, language: '{}')",
+                    list(config.link_flags()),
+                    lang
+                ));
+            }
+            lines
+        };
+
+        let mut contents = vec!["# Link-time optimization and linker, per profile".to_string()];
+        contents.push("if get_option('buildtype') == 'release'".to_string());
+        contents.extend(rules(&self.release));
+        contents.push("else".to_string());
+        contents.extend(rules(&self.debug));
+        contents.push("endif\n".to_string());
+        contents.join("\n")
+    }
+
+    /// Makefile snippet setting `LTO` and `LINKER` from `PROFILE`, which forge passes to make.
+    pub fn make_rules(&self) -> String {
+        let mut contents = vec!["# Link-time optimization and linker, per profile".to_string()];
+        contents.push("PROFILE ?= debug".to_string());
+        for profile in [Profile::Debug, Profile::Release] {
+            let config = self.get(profile);
+            contents.push(format!("ifeq ($(PROFILE),{})", profile));
+            contents.push(format!("LTO := {}", config.compile_flags().join(" ")));
+            let linker: Vec<String> = config
+                .link_flags()
+                .into_iter()
+                .filter(|f| f.starts_with("-fuse-ld"))
+                .collect();
+            contents.push(format!("LINKER := {}", linker.join(" ")));
+            contents.push("endif".to_string());
+        }
+        contents.push(String::new());
+        contents.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::language::CStandard;
+
+    #[test]
+    fn test_build_dir() {
+        assert_eq!(Profile::default().build_dir(), PathBuf::from("build"));
+        assert_eq!(
+            Profile::from_release(true).build_dir(),
+            PathBuf::from("build").join("release")
+        );
+        assert_eq!("release".parse::<Profile>().ok(), Some(Profile::Release));
+    }
+
+    #[test]
+    fn test_profile_rules() {
+        let profiles = ProfilesConfig {
+            debug: ProfileConfig {
+                lto: Lto::Off,
+                linker: Linker::Lld,
+            },
+            release: ProfileConfig {
+                lto: Lto::Full,
+                linker: Linker::Mold,
+            },
+        };
+
+        // Test
+        let cmake = profiles.cmake_rules();
+        let meson = profiles.meson_rules("cpp");
+        let make = profiles.make_rules();
+
+        // Validate
+        assert!(cmake.contains(
+            "if(CMAKE_BUILD_TYPE STREQUAL \"Release\")\n    set(CMAKE_INTERPROCEDURAL_OPTIMIZATION ON)\n    add_link_options(-fuse-ld=mold)\nelse()\n    add_link_options(-fuse-ld=lld)\nendif()"
+        ));
+        assert!(meson
+            .contains("  add_project_link_arguments('-flto', '-fuse-ld=mold', language: 'cpp')\n"));
+        assert!(make.contains("ifeq ($(PROFILE),release)\nLTO := -flto\nLINKER := -fuse-ld=mold\n"));
+        assert_eq!(
+            ProfileConfig {
+                lto: Lto::Thin,
+                linker: Linker::Default
+            }
+            .link_flags(),
+            vec!["-flto=thin"]
+        );
+    }
+
+    #[test]
+    fn test_check_support() {
+        let language = Language::C(CStandard::C11);
+        let config = |lto, linker| ProfileConfig { lto, linker };
+
+        // Test
+        let full = config(Lto::Full, Linker::Default).check_support("gcc", &language);
+        let thin = config(Lto::Thin, Linker::Default).check_support("gcc", &language);
+        let missing = config(Lto::Off, Linker::Mold).check_support("gcc", &language);
+
+        // Validate
+        assert!(full.is_ok());
+        assert!(thin.unwrap_err().to_string().contains("-flto=thin"));
+        if which("ld.mold").is_err() {
+            assert!(missing.unwrap_err().to_string().contains("ld.mold"));
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const BEGIN: &str = "# forge:begin ";
+const END: &str = "# forge:end ";
+const ORPHANED: &str =
+    "# forge: the generator no longer has this region, move its lines or delete it";
+
+/// First line of every generated build file.
+pub const HEADER: &str = "# Generated by forge from Forge.toml and rewritten by `forge regen`.
+# Only edits between `forge:begin` and `forge:end` markers are kept.\n";
+
+/// Empty user-owned region called `name`.
+pub fn region(name: &str) -> String {
+    format!("{}{}\n{}{}\n", BEGIN, name, END, name)
+}
+
+/// Whether `contents` came from a generator with guarded regions.
+pub fn is_managed(contents: &str) -> bool {
+    contents.lines().any(|l| l.trim_start().starts_with(BEGIN))
+}
+
+/// Lines inside each region of `contents`, by region name.
+pub fn user_regions(contents: &str) -> HashMap<String, Vec<String>> {
+    let mut regions = HashMap::new();
+    let mut current: Option<(String, Vec<String>)> = None;
+
+    for line in contents.lines() {
+        let trimmed = line.trim_start();
+        if let Some(name) = trimmed.strip_prefix(BEGIN) {
+            current = Some((name.trim().to_string(), vec![]));
+        } else if let Some(name) = trimmed.strip_prefix(END) {
+            if let Some((open, lines)) = current.take() {
+                if open == name.trim() {
+                    regions.insert(open, lines);
+                }
+            }
+        } else if let Some((_, lines)) = current.as_mut() {
+            lines.push(line.to_string());
+        }
+    }
+
+    regions
+}
+
+/// Fills the regions of freshly generated contents with the user's lines from `existing`.
+/// Regions the generator no longer has are appended under a comment rather than dropped.
+pub fn merge(generated: &str, existing: &str) -> String {
+    let mut regions = user_regions(existing);
+    let mut merged = vec![];
+
+    for line in generated.split('\n') {
+        merged.push(line.to_string());
+        if let Some(name) = line.trim_start().strip_prefix(BEGIN) {
+            if let Some(lines) = regions.remove(name.trim()) {
+                merged.extend(lines);
+            }
+        }
+    }
+
+    let mut orphans: Vec<(String, Vec<String>)> = regions
+        .into_iter()
+        .filter(|(_, lines)| lines.iter().any(|l| !l.trim().is_empty()))
+        .collect();
+    orphans.sort();
+    if !orphans.is_empty() && merged.last().is_some_and(|l| l.is_empty()) {
+        merged.pop();
+    }
+    for (name, lines) in orphans {
+        merged.push(ORPHANED.to_string());
+        merged.push(format!("{}{}", BEGIN, name));
+        merged.extend(lines);
+        merged.push(format!("{}{}", END, name));
+        merged.push(String::new());
+    }
+
+    merged.join("\n")
+}
+
+/// Regions of `existing` with lines that `generated` has no place for.
+fn orphaned_regions(generated: &str, existing: &str) -> Vec<String> {
+    let generated = user_regions(generated);
+    let mut names: Vec<String> = user_regions(existing)
+        .into_iter()
+        .filter(|(name, lines)| {
+            !generated.contains_key(name) && lines.iter().any(|l| !l.trim().is_empty())
+        })
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names
+}
+
+/// Writes a generated build file, keeping the user's regions from the file it replaces. The
+/// file is left untouched when nothing changed, so the build system doesn't reconfigure.
+pub fn write_generated(path: &Path, generated: &str) -> Result<()> {
+    let existing = fs::read_to_string(path).ok();
+    let contents = match &existing {
+        Some(e) => merge(generated, e),
+        None => generated.to_string(),
+    };
+    for name in orphaned_regions(generated, existing.as_deref().unwrap_or_default()) {
+        println!(
+            "Warning: region `{}` is no longer generated in {}, kept it at the end of the file",
+            name,
+            path.display()
+        );
+    }
+
+    match existing {
+        Some(e) if e == contents => return Ok(()),
+        Some(_) => println!("Regenerated {}", path.display()),
+        None => (),
+    }
+    write_if_changed(path, &contents)
+}
+
+/// Writes `contents` unless the file already has them, keeping its modification time.
+pub fn write_if_changed(path: &Path, contents: &str) -> Result<()> {
+    if fs::read_to_string(path).ok().as_deref() != Some(contents) {
+        fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+/// Whether `source` was modified after `generated`, or `generated` doesn't exist.
+pub fn is_stale(source: &Path, generated: &Path) -> Result<bool> {
+    let generated = match fs::metadata(generated) {
+        Ok(m) => m.modified()?,
+        Err(_) => return Ok(true),
+    };
+    Ok(fs::metadata(source)?.modified()? > generated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let existing = format!(
+            "{}old generated line\n# forge:begin settings\nset(FOO ON)\n\n# forge:end settings\n# forge:begin targets\n# forge:end targets\n",
+            HEADER
+        );
+        let generated = format!(
+            "{}new generated line\n{}{}",
+            HEADER,
+            region("settings"),
+            region("targets")
+        );
+
+        // Test
+        let merged = merge(&generated, &existing);
+
+        // Validate
+        assert!(is_managed(&merged));
+        assert!(!merged.contains("old generated line"));
+        assert!(merged.contains(
+            "new generated line\n# forge:begin settings\nset(FOO ON)\n\n# forge:end settings\n"
+        ));
+        assert_eq!(merge(&generated, "no markers"), generated);
+    }
+
+    #[test]
+    fn test_merge_orphaned_region() {
+        let existing = format!(
+            "{}# forge:begin settings\n# forge:end settings\n# forge:begin old\nset(BAR ON)\n# forge:end old\n",
+            HEADER
+        );
+        let generated = format!("{}{}", HEADER, region("settings"));
+
+        // Test
+        let merged = merge(&generated, &existing);
+
+        // Validate
+        assert_eq!(orphaned_regions(&generated, &existing), vec!["old"]);
+        assert!(merged.ends_with(&format!(
+            "{}\n# forge:begin old\nset(BAR ON)\n# forge:end old\n",
+            ORPHANED
+        )));
+        assert_eq!(merge(&generated, &merged), merged);
+        assert_eq!(merge(&generated, &generated), generated);
+    }
+}
@@ -0,0 +1,188 @@
+use super::compiler::{self, CompilerFamily};
+use super::language::Language;
+use crate::{error, Error, Result};
+use std::env;
+use std::path::PathBuf;
+use strum_macros::{Display, EnumString};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Sanitizer {
+    Address,
+    Undefined,
+    Thread,
+    Memory,
+}
+
+impl Sanitizer {
+    pub fn variants() -> Vec<&'static str> {
+        vec!["address", "undefined", "thread", "memory"]
+    }
+
+    /// Runtime options forge sets unless the user already has them in their environment.
+    pub fn default_options(&self) -> (&'static str, &'static str) {
+        match self {
+            Sanitizer::Address => ("ASAN_OPTIONS", "halt_on_error=1:detect_leaks=1"),
+            Sanitizer::Undefined => ("UBSAN_OPTIONS", "halt_on_error=1:print_stacktrace=1"),
+            Sanitizer::Thread => ("TSAN_OPTIONS", "halt_on_error=1"),
+            Sanitizer::Memory => ("MSAN_OPTIONS", "halt_on_error=1"),
+        }
+    }
+
+    /// Sanitizers that can't be linked into the same binary as this one.
+    fn conflicts(&self) -> &'static [Sanitizer] {
+        match self {
+            Sanitizer::Address => &[Sanitizer::Thread, Sanitizer::Memory],
+            Sanitizer::Thread => &[Sanitizer::Address, Sanitizer::Memory],
+            Sanitizer::Memory => &[Sanitizer::Address, Sanitizer::Thread],
+            Sanitizer::Undefined => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizerSet {
+    sanitizers: Vec<Sanitizer>,
+}
+
+impl SanitizerSet {
+    /// Parses a comma separated list such as `address,undefined`.
+    pub fn parse(s: &str) -> Result<SanitizerSet> {
+        let mut sanitizers = vec![];
+
+        for name in s.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let sanitizer: Sanitizer = name.parse().map_err(|_| {
+                error!(
+                    CustomError,
+                    "Unknown sanitizer `{}`, expected one of: {}",
+                    name,
+                    Sanitizer::variants().join(", ")
+                )
+            })?;
+            sanitizers.push(sanitizer);
+        }
+
+        sanitizers.sort();
+        sanitizers.dedup();
+
+        if sanitizers.is_empty() {
+            return Err(error!(CustomError, "No sanitizers given"));
+        }
+
+        for s in &sanitizers {
+            if let Some(other) = s.conflicts().iter().find(|c| sanitizers.contains(c)) {
+                return Err(error!(
+                    CustomError,
+                    "The {} and {} sanitizers can't be combined", s, other
+                ));
+            }
+        }
+
+        Ok(SanitizerSet { sanitizers })
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.sanitizers.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Each set of sanitizers gets its own build tree, relative to the project directory.
+    pub fn build_dir(&self) -> PathBuf {
+        PathBuf::from("build").join(format!("sanitize-{}", self.names().join("-")))
+    }
+
+    pub fn compile_flags(&self) -> Vec<String> {
+        let mut flags = vec![
+            format!("-fsanitize={}", self.names().join(",")),
+            "-fno-omit-frame-pointer".to_string(),
+            "-g".to_string(),
+        ];
+        if self.sanitizers.contains(&Sanitizer::Memory) {
+            flags.push("-fsanitize-memory-track-origins".to_string());
+        }
+        flags
+    }
+
+    pub fn link_flags(&self) -> Vec<String> {
+        vec![format!("-fsanitize={}", self.names().join(","))]
+    }
+
+    /// Runtime options for binaries built with this set. Values already present in the
+    /// environment take precedence.
+    pub fn env(&self) -> Vec<(String, String)> {
+        self.sanitizers
+            .iter()
+            .map(|s| s.default_options())
+            .filter(|(key, _)| env::var(key).is_err())
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Checks the sanitizers are supported by the compiler family and that `compiler` can
+    /// actually build and link a sanitized program.
+    pub fn check_support(&self, compiler: &str, language: &Language) -> Result<()> {
+        let family = CompilerFamily::from_compiler(compiler);
+        if self.sanitizers.contains(&Sanitizer::Memory) && family != CompilerFamily::Clang {
+            return Err(error!(
+                CustomError,
+                "The memory sanitizer is only supported by clang, not {}", compiler
+            ));
+        }
+
+        if let Some(errors) = compiler::probe(compiler, language, &self.compile_flags())? {
+            return Err(error!(
+                CustomError,
+                "{} can't build with -fsanitize={}:\n{}",
+                compiler,
+                self.names().join(","),
+                errors
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> anyhow::Result<()> {
+        // Test
+        let set = SanitizerSet::parse("undefined, address,address")?;
+
+        // Validate
+        assert_eq!(set.names(), vec!["address", "undefined"]);
+        assert_eq!(
+            set.build_dir(),
+            PathBuf::from("build").join("sanitize-address-undefined")
+        );
+        assert_eq!(set.compile_flags()[0], "-fsanitize=address,undefined");
+        assert_eq!(set.link_flags(), vec!["-fsanitize=address,undefined"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(SanitizerSet::parse("").is_err());
+        assert!(SanitizerSet::parse("addres").is_err());
+        assert!(SanitizerSet::parse("address,thread").is_err());
+        assert!(SanitizerSet::parse("memory,address").is_err());
+        assert!(SanitizerSet::parse("thread,memory").is_err());
+        assert!(SanitizerSet::parse("thread,undefined").is_ok());
+    }
+
+    #[test]
+    fn test_memory_requires_clang() -> anyhow::Result<()> {
+        let set = SanitizerSet::parse("memory")?;
+        let language = Language::C(crate::core::language::CStandard::C99);
+
+        // Test
+        let result = set.check_support("gcc", &language);
+
+        // Validate
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
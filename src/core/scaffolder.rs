@@ -1,3 +1,4 @@
+use super::init_plan::InitPlan;
 use super::language::Language;
 use crate::Result;
 use serde_json::json;
@@ -19,6 +20,27 @@ impl Scaffolder {
         }
     }
 
+    /// Files written by `build`, relative to the project directory.
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.contents().into_iter().map(|(file, _)| file).collect()
+    }
+
+    /// Every file `build` writes with its contents, relative to the project directory.
+    fn contents(&self) -> Vec<(PathBuf, String)> {
+        let mut files = vec![
+            (PathBuf::from(".gitignore"), self.gitignore()),
+            (
+                PathBuf::from(".vscode").join("c_cpp_properties.json"),
+                self.vscode_config(),
+            ),
+        ];
+        match self.language {
+            Language::C(_) => files.extend(CHelloWorld::files()),
+            Language::Cpp(_) => files.extend(CppHelloWorld::files()),
+        }
+        files
+    }
+
     pub fn create_dir(&self) -> Result<()> {
         fs::create_dir_all(&self.project_dir)?;
         Ok(())
@@ -38,11 +60,15 @@ impl Scaffolder {
             .arg("init")
             .current_dir(&self.project_dir)
             .status()?;
+        Ok(())
+    }
 
+    fn gitignore(&self) -> String {
         let mut content = vec![];
         content.push("# Ignore build output");
         content.push("/build/");
-        content.push("/bin/\n");
+        content.push("/bin/");
+        content.push("/pch/\n");
         content.push("# Ignore CMake files");
         content.push("/CMakeFiles/");
         content.push("/CMakeCache.txt");
@@ -58,13 +84,10 @@ impl Scaffolder {
         content.push("compile_commands.json");
         content.push("vcpkg-configuration.json");
         content.push("vcpkg.json\n");
-
-        let path = self.project_dir.join(".gitignore");
-        fs::write(path, content.join("\n"))?;
-        Ok(())
+        content.join("\n")
     }
 
-    pub fn vscode_init(&self) -> Result<()> {
+    fn vscode_config(&self) -> String {
         let content = json!(
         {
           "configurations": [
@@ -84,60 +107,32 @@ impl Scaffolder {
           ],
           "version": 4
         });
-
-        let path = self
-            .project_dir
-            .join(".vscode")
-            .join("c_cpp_properties.json");
-        fs::write(path, serde_json::to_string_pretty(&content)?)?;
-        Ok(())
-    }
-
-    pub fn create_hello_world(&self) -> Result<()> {
-        match self.language {
-            Language::C(_) => {
-                let x = CHelloWorld {
-                    // name: self.name.clone(),
-                    path: self.project_dir.clone(),
-                };
-                x.build()?;
-            }
-            Language::Cpp(_) => {
-                let x = CppHelloWorld {
-                    // name: self.name.clone(),
-                    path: self.project_dir.clone(),
-                };
-                x.build()?;
-            }
-        }
-        Ok(())
+        format!("{:#}", content)
     }
 
-    pub fn build(&self) -> Result<()> {
+    /// Scaffolds the project, leaving the files `plan` keeps alone.
+    pub fn build(&self, plan: &InitPlan) -> Result<()> {
         self.create_dir()?;
         self.create_structure()?;
         self.git_init()?;
-        self.vscode_init()?;
-        self.create_hello_world()?;
+        for (file, contents) in self.contents() {
+            plan.write(&file, &contents)?;
+        }
         Ok(())
     }
 }
 
-struct CHelloWorld {
-    // name: String,
-    path: PathBuf,
-}
+struct CHelloWorld {}
 
 impl CHelloWorld {
-    fn build(&self) -> Result<()> {
-        self.header()?;
-        self.lib()?;
-        self.bin()?;
-        self.tests()?;
-        Ok(())
+    /// The sources, in the order `Scaffolder::files` lists them.
+    fn files() -> Vec<(PathBuf, String)> {
+        let mut files = vec![Self::header(), Self::lib(), Self::bin()];
+        files.extend(Self::tests());
+        files
     }
 
-    fn bin(&self) -> Result<()> {
+    fn bin() -> (PathBuf, String) {
         let mut content = vec![];
         content.push("#include <stdio.h>");
         content.push("#include \"lib.h\"\n");
@@ -147,14 +142,10 @@ impl CHelloWorld {
         content.push("  return 0;");
         content.push("}");
 
-        let path = self.path.join("src").join("main.c");
-        match fs::write(path, content.join("\n")) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+        (PathBuf::from("src").join("main.c"), content.join("\n"))
     }
 
-    fn lib(&self) -> Result<()> {
+    fn lib() -> (PathBuf, String) {
         let mut content = vec![];
         content.push("#include \"lib.h\"\n");
         content.push("void get_greeting(const char* name) {");
@@ -165,28 +156,34 @@ impl CHelloWorld {
         content.push("}\n");
         content.push("int add(int a, int b) { return a + b; }");
 
-        let path = self.path.join("src").join("lib.c");
-        match fs::write(path, content.join("\n")) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+        (PathBuf::from("src").join("lib.c"), content.join("\n"))
     }
 
-    fn header(&self) -> Result<()> {
+    fn header() -> (PathBuf, String) {
         let mut content = vec![];
+        content.push("/**");
+        content.push(" * @file lib.h");
+        content.push(" * @brief Public interface of the library.");
+        content.push(" */");
         content.push("#pragma once\n");
         content.push("#include <stdio.h>\n");
+        content.push("/**");
+        content.push(" * @brief Prints a greeting to standard output.");
+        content.push(" * @param name Null-terminated string to print.");
+        content.push(" */");
         content.push("void get_greeting(const char* name);\n");
+        content.push("/**");
+        content.push(" * @brief Adds two integers.");
+        content.push(" * @param a First operand.");
+        content.push(" * @param b Second operand.");
+        content.push(" * @return The sum of @p a and @p b.");
+        content.push(" */");
         content.push("int add(int a, int b);\n");
 
-        let path = self.path.join("include").join("lib.h");
-        match fs::write(path, content.join("\n")) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+        (PathBuf::from("include").join("lib.h"), content.join("\n"))
     }
 
-    fn tests(&self) -> Result<()> {
+    fn tests() -> [(PathBuf, String); 2] {
         let mut content = vec![];
         content.push("#include <setjmp.h>");
         content.push("#include <stdarg.h>");
@@ -215,31 +212,27 @@ impl CHelloWorld {
         content.push("const struct CMUnitTest libTests[] = {cmocka_unit_test(test_add)};");
         content.push("const size_t libTestsSize = sizeof(libTests) / sizeof(libTests[0]);");
 
-        let path_main = self.path.join("test").join("test_main.c");
-        fs::write(path_main, main_content.join("\n"))?;
-
-        let path_sub = self.path.join("test").join("test_lib.c");
-        fs::write(path_sub, content.join("\n"))?;
-
-        Ok(())
+        [
+            (
+                PathBuf::from("test").join("test_main.c"),
+                main_content.join("\n"),
+            ),
+            (PathBuf::from("test").join("test_lib.c"), content.join("\n")),
+        ]
     }
 }
 
-struct CppHelloWorld {
-    // name: String,
-    path: PathBuf,
-}
+struct CppHelloWorld {}
 
 impl CppHelloWorld {
-    fn build(&self) -> Result<()> {
-        self.header()?;
-        self.lib()?;
-        self.bin()?;
-        self.tests()?;
-        Ok(())
+    /// The sources, in the order `Scaffolder::files` lists them.
+    fn files() -> Vec<(PathBuf, String)> {
+        let mut files = vec![Self::header(), Self::lib(), Self::bin()];
+        files.extend(Self::tests());
+        files
     }
 
-    fn bin(&self) -> Result<()> {
+    fn bin() -> (PathBuf, String) {
         let mut content = vec![];
         content.push("#include <iostream>");
         content.push("#include \"lib.hpp\"\n");
@@ -249,41 +242,38 @@ impl CppHelloWorld {
         content.push("  return 0;");
         content.push("}");
 
-        let path = self.path.join("src").join("main.cpp");
-        match fs::write(path, content.join("\n")) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+        (PathBuf::from("src").join("main.cpp"), content.join("\n"))
     }
 
-    fn lib(&self) -> Result<()> {
+    fn lib() -> (PathBuf, String) {
         let mut content = vec![];
         content.push("#include \"lib.hpp\"\n");
         content.push("std::string get_greeting(const std::string& name) {");
         content.push("  return \"Hello, \" + name + \"!\";");
         content.push("}");
 
-        let path = self.path.join("src").join("lib.cpp");
-        match fs::write(path, content.join("\n")) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+        (PathBuf::from("src").join("lib.cpp"), content.join("\n"))
     }
 
-    fn header(&self) -> Result<()> {
+    fn header() -> (PathBuf, String) {
         let mut content = vec![];
+        content.push("/**");
+        content.push(" * @file lib.hpp");
+        content.push(" * @brief Public interface of the library.");
+        content.push(" */");
         content.push("#pragma once\n");
         content.push("#include <string>\n");
+        content.push("/**");
+        content.push(" * @brief Builds a greeting for @p name.");
+        content.push(" * @param name Who to greet.");
+        content.push(" * @return The greeting, e.g. `Hello, World!`.");
+        content.push(" */");
         content.push("std::string get_greeting(const std::string& name);");
 
-        let path = self.path.join("include").join("lib.hpp");
-        match fs::write(path, content.join("\n")) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+        (PathBuf::from("include").join("lib.hpp"), content.join("\n"))
     }
 
-    fn tests(&self) -> Result<()> {
+    fn tests() -> [(PathBuf, String); 2] {
         // this should be test specific
         let mut content = vec![];
         content.push("#include <gtest/gtest.h>\n");
@@ -301,19 +291,23 @@ impl CppHelloWorld {
         content.push("  EXPECT_EQ(get_greeting(\"Test\"), \"Hello, Test!\");");
         content.push("}\n");
 
-        let path_main = self.path.join("test").join("test_main.cpp");
-        fs::write(path_main, main_content.join("\n"))?;
-
-        let path = self.path.join("test").join("test_lib.cpp");
-        fs::write(path, content.join("\n"))?;
-
-        Ok(())
+        [
+            (
+                PathBuf::from("test").join("test_main.cpp"),
+                main_content.join("\n"),
+            ),
+            (
+                PathBuf::from("test").join("test_lib.cpp"),
+                content.join("\n"),
+            ),
+        ]
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::init_plan::ExistingFiles;
     use crate::core::language::{CStandard, CppStandard};
     use serial_test::serial;
     use std::{
@@ -354,10 +348,12 @@ mod tests {
 
         // Test
         let scaffolder = Scaffolder::new(name.to_string(), path.clone(), language);
-        scaffolder.build()?;
+        let plan = InitPlan::new(&path, scaffolder.files(), ExistingFiles::Overwrite);
+        scaffolder.build(&plan)?;
 
         // Validate
         assert!(check_file_exits(&path.join(".gitignore")));
+        assert!(fs::read_to_string(path.join(".gitignore"))?.contains("/pch/\n"));
         assert!(check_file_exits(&path.join(".git")));
         assert!(check_file_exits(&path.join("include").join("lib.h")));
         assert!(check_file_exits(&path.join("src").join("lib.c")));
@@ -387,7 +383,8 @@ mod tests {
 
         // Test
         let scaffolder = Scaffolder::new(name.to_string(), path.clone(), language);
-        scaffolder.build()?;
+        let plan = InitPlan::new(&path, scaffolder.files(), ExistingFiles::Overwrite);
+        scaffolder.build(&plan)?;
 
         // Validate
         assert!(check_file_exits(&path.join(".gitignore")));
@@ -406,4 +403,33 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn test_scaffold_keep_existing() -> anyhow::Result<()> {
+        // Set-up
+        let name = "dummy";
+        let cwd = env::current_dir()?;
+        let path = cwd.join(&name);
+        let main = "int main(void) { return 1; }\n";
+        create_dummy_project(&path.join("src"))?;
+        fs::write(path.join("src").join("main.c"), main)?;
+        fs::write(path.join(".gitignore"), "notes.txt\n")?;
+        let language = Language::C(CStandard::C11);
+
+        // Test
+        let scaffolder = Scaffolder::new(name.to_string(), path.clone(), language);
+        let plan = InitPlan::new(&path, scaffolder.files(), ExistingFiles::Keep);
+        scaffolder.build(&plan)?;
+
+        // Validate
+        assert_eq!(fs::read_to_string(path.join("src").join("main.c"))?, main);
+        assert!(fs::read_to_string(path.join(".gitignore"))?.starts_with("notes.txt\n"));
+        assert!(check_file_exits(&path.join("src").join("lib.c")));
+
+        // Clean-up
+        delete_dummy_project(&path)?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,580 @@
+use super::config::ProjectConfig;
+use crate::{error, Error, Result};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    Lib,
+    Bin,
+    Test,
+    Example,
+    Bench,
+}
+
+impl TargetKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TargetKind::Lib => "lib",
+            TargetKind::Bin => "bin",
+            TargetKind::Test => "test",
+            TargetKind::Example => "example",
+            TargetKind::Bench => "bench",
+        }
+    }
+}
+
+/// A `[lib]`, `[[bin]]`, `[[test]]`, `[[example]]` or `[[bench]]` table in Forge.toml. Everything but the
+/// name is optional and defaults to the conventional layout.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TargetConfig {
+    #[serde(default)]
+    pub name: String,
+    /// Source globs relative to the project directory, e.g. `src/net/**/*.c`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_dirs: Vec<String>,
+    /// Preprocessor definitions, `NAME` or `NAME=value`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub defines: Vec<String>,
+    /// Other targets of the project or system libraries, e.g. `m` or `pthread`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub link: Vec<String>,
+    /// Built file relative to the build tree, for build files forge doesn't generate. Recorded
+    /// by `forge init --import`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+}
+
+/// Target tables of Forge.toml. Targets that aren't listed are discovered from the layout:
+///
+/// - `src/**` without the binaries' sources is the library `<name>Lib`
+/// - `src/main.<ext>` is the binary `<name>`, `src/bin/<bin>.<ext>` and `src/bin/<bin>/` are others
+/// - `test/**` is the test `<name>Tests`
+/// - `examples/<example>.<ext>` and `examples/<example>/` are examples
+/// - `bench/<bench>.<ext>` and `bench/<bench>/` are benchmarks
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TargetsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lib: Option<TargetConfig>,
+    #[serde(default, rename = "bin", skip_serializing_if = "Vec::is_empty")]
+    pub bins: Vec<TargetConfig>,
+    #[serde(default, rename = "test", skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<TargetConfig>,
+    #[serde(default, rename = "example", skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<TargetConfig>,
+    #[serde(default, rename = "bench", skip_serializing_if = "Vec::is_empty")]
+    pub benches: Vec<TargetConfig>,
+}
+
+/// A target with its sources resolved, as the generators see it. Paths are relative to the
+/// project directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub name: String,
+    pub kind: TargetKind,
+    pub sources: Vec<PathBuf>,
+    pub include_dirs: Vec<String>,
+    pub defines: Vec<String>,
+    /// Project targets this target links, the library first.
+    pub link_targets: Vec<String>,
+    /// System libraries this target links.
+    pub link_libs: Vec<String>,
+    /// Built file relative to the build tree, when it isn't where forge's build files put it.
+    pub output: Option<PathBuf>,
+}
+
+impl Target {
+    pub fn ident(&self) -> String {
+        ident(&self.name)
+    }
+}
+
+/// `name` as an identifier safe to use for Meson and Make variables.
+pub fn ident(name: &str) -> String {
+    name.chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Targets {
+    pub lib: Option<Target>,
+    pub bins: Vec<Target>,
+    pub tests: Vec<Target>,
+    pub examples: Vec<Target>,
+    pub benches: Vec<Target>,
+}
+
+impl Targets {
+    /// Resolves the configured and discovered targets of the project at `directory`.
+    pub fn resolve(
+        directory: &Path,
+        project: &ProjectConfig,
+        config: &TargetsConfig,
+    ) -> Result<Targets> {
+        Targets::resolve_in(directory, project, config, true)
+    }
+
+    /// Resolves only the configured targets, for build files that aren't generated from the
+    /// layout.
+    pub fn configured(
+        directory: &Path,
+        project: &ProjectConfig,
+        config: &TargetsConfig,
+    ) -> Result<Targets> {
+        Targets::resolve_in(directory, project, config, false)
+    }
+
+    fn resolve_in(
+        directory: &Path,
+        project: &ProjectConfig,
+        config: &TargetsConfig,
+        discover: bool,
+    ) -> Result<Targets> {
+        let ext = project.language.src_suffix();
+        let resolver = Resolver { directory, ext };
+
+        let mut bins = resolver.configured(&config.bins, TargetKind::Bin, |name| {
+            match name == project.name {
+                true => vec![format!("src/main.{}", ext)],
+                false => vec![format!("src/bin/{}.{}", name, ext)],
+            }
+        })?;
+        let mut examples = resolver.configured(&config.examples, TargetKind::Example, |name| {
+            vec![format!("examples/{}.{}", name, ext)]
+        })?;
+        let mut benches = resolver.configured(&config.benches, TargetKind::Bench, |name| {
+            vec![format!("bench/{}.{}", name, ext)]
+        })?;
+        if discover {
+            let mut discovered = vec![];
+            if directory.join(format!("src/main.{}", ext)).is_file() {
+                discovered.push((project.name.clone(), vec![format!("src/main.{}", ext)]));
+            }
+            discovered.extend(resolver.discover("src/bin")?);
+            resolver.add_discovered(&mut bins, discovered, TargetKind::Bin)?;
+
+            let discovered = resolver.discover("examples")?;
+            resolver.add_discovered(&mut examples, discovered, TargetKind::Example)?;
+
+            let discovered = resolver.discover("bench")?;
+            resolver.add_discovered(&mut benches, discovered, TargetKind::Bench)?;
+        }
+
+        // The library gets everything under src/ that isn't a binary's.
+        let taken: BTreeSet<PathBuf> = bins.iter().flat_map(|t| t.sources.clone()).collect();
+        let mut lib_sources = resolver.expand(&[format!("src/**/*.{}", ext)])?;
+        lib_sources.retain(|s| !taken.contains(s) && !s.starts_with("src/bin"));
+        let mut lib_config = config.lib.clone().unwrap_or_default();
+        if lib_config.name.is_empty() {
+            lib_config.name = format!("{}Lib", project.name);
+        }
+        let lib = match (&config.lib, lib_config.sources.is_empty()) {
+            (Some(_), false) => Some(resolver.target(&lib_config, TargetKind::Lib, &[])?),
+            (_, true) if discover && !lib_sources.is_empty() => Some(Target {
+                name: lib_config.name,
+                kind: TargetKind::Lib,
+                sources: lib_sources,
+                include_dirs: lib_config.include_dirs,
+                defines: lib_config.defines,
+                link_targets: vec![],
+                link_libs: lib_config.link,
+                output: lib_config.output,
+            }),
+            _ => None,
+        };
+
+        let mut tests = resolver.configured(&config.tests, TargetKind::Test, |name| {
+            vec![format!("test/{}.{}", name, ext)]
+        })?;
+        if discover && config.tests.is_empty() {
+            let sources = resolver.expand(&[format!("test/**/*.{}", ext)])?;
+            if !sources.is_empty() {
+                tests.push(Target {
+                    name: format!("{}Tests", project.name),
+                    kind: TargetKind::Test,
+                    sources,
+                    include_dirs: vec![],
+                    defines: vec![],
+                    link_targets: vec![],
+                    link_libs: vec![],
+                    output: None,
+                });
+            }
+        }
+
+        let mut targets = Targets {
+            lib,
+            bins,
+            tests,
+            examples,
+            benches,
+        };
+        targets.finish()?;
+        Ok(targets)
+    }
+
+    /// Every target, the library first.
+    pub fn all(&self) -> Vec<&Target> {
+        let mut all: Vec<&Target> = self.lib.iter().collect();
+        all.extend(&self.bins);
+        all.extend(&self.tests);
+        all.extend(&self.examples);
+        all.extend(&self.benches);
+        all
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Target> {
+        self.all().into_iter().find(|t| t.name == name)
+    }
+
+    /// Adds the conventional include dirs and links, then splits links into project targets and
+    /// system libraries.
+    fn finish(&mut self) -> Result<()> {
+        let names: Vec<String> = self.all().iter().map(|t| t.name.clone()).collect();
+        let mut seen = BTreeSet::new();
+        for name in &names {
+            if !seen.insert(name) {
+                return Err(error!(CustomError, "Target `{}` is defined twice", name));
+            }
+        }
+
+        let lib = self.lib.as_ref().map(|l| l.name.clone());
+        if let Some(l) = self.lib.as_mut() {
+            prepend(&mut l.include_dirs, &["include", "libs"]);
+        }
+        for t in self
+            .bins
+            .iter_mut()
+            .chain(self.tests.iter_mut())
+            .chain(self.examples.iter_mut())
+            .chain(self.benches.iter_mut())
+        {
+            if t.kind == TargetKind::Test {
+                prepend(&mut t.include_dirs, &["src"]);
+            }
+            if let Some(l) = &lib {
+                if !t.link_targets.contains(l) && !t.link_libs.contains(l) {
+                    t.link_targets.insert(0, l.clone());
+                }
+            }
+        }
+
+        for t in self
+            .lib
+            .iter_mut()
+            .chain(self.bins.iter_mut())
+            .chain(self.tests.iter_mut())
+            .chain(self.examples.iter_mut())
+            .chain(self.benches.iter_mut())
+        {
+            let (targets, libs): (Vec<String>, Vec<String>) = t
+                .link_libs
+                .drain(..)
+                .partition(|l| names.contains(l) && l != &t.name);
+            t.link_targets.extend(targets);
+            t.link_libs = libs;
+        }
+        Ok(())
+    }
+}
+
+fn prepend(dirs: &mut Vec<String>, defaults: &[&str]) {
+    for (i, d) in defaults.iter().enumerate() {
+        if !dirs.iter().any(|x| x == d) {
+            dirs.insert(i, d.to_string());
+        }
+    }
+}
+
+struct Resolver<'a> {
+    directory: &'a Path,
+    ext: &'a str,
+}
+
+impl Resolver<'_> {
+    /// Files matching `patterns`, relative to the project directory, sorted and deduplicated.
+    fn expand(&self, patterns: &[String]) -> Result<Vec<PathBuf>> {
+        let mut files = BTreeSet::new();
+        for pattern in patterns {
+            let absolute = self.directory.join(pattern);
+            let matches = glob(&absolute.to_string_lossy())
+                .map_err(|e| error!(CustomError, "Invalid source glob `{}`: {}", pattern, e))?;
+
+            for path in matches.filter_map(|p| p.ok()).filter(|p| p.is_file()) {
+                let relative = path.strip_prefix(self.directory).unwrap_or(&path);
+                files.insert(relative.to_path_buf());
+            }
+        }
+        Ok(files.into_iter().collect())
+    }
+
+    fn target(
+        &self,
+        config: &TargetConfig,
+        kind: TargetKind,
+        default: &[String],
+    ) -> Result<Target> {
+        let patterns = match config.sources.is_empty() {
+            true => default,
+            false => &config.sources,
+        };
+        let sources = self.expand(patterns)?;
+        if sources.is_empty() {
+            return Err(error!(
+                CustomError,
+                "{} `{}` has no sources matching {}",
+                kind.as_str(),
+                config.name,
+                patterns.join(", ")
+            ));
+        }
+
+        Ok(Target {
+            name: config.name.clone(),
+            kind,
+            sources,
+            include_dirs: config.include_dirs.clone(),
+            defines: config.defines.clone(),
+            link_targets: vec![],
+            link_libs: config.link.clone(),
+            output: config.output.clone(),
+        })
+    }
+
+    fn configured<F>(
+        &self,
+        configs: &[TargetConfig],
+        kind: TargetKind,
+        default: F,
+    ) -> Result<Vec<Target>>
+    where
+        F: Fn(&str) -> Vec<String>,
+    {
+        let mut targets = vec![];
+        for config in configs {
+            if config.name.is_empty() {
+                return Err(error!(
+                    CustomError,
+                    "A [[{}]] table has no name",
+                    kind.as_str()
+                ));
+            }
+            targets.push(self.target(config, kind, &default(&config.name))?);
+        }
+        Ok(targets)
+    }
+
+    /// `<dir>/<name>.<ext>` files and `<dir>/<name>/` directories, as (name, source globs).
+    fn discover(&self, dir: &str) -> Result<Vec<(String, Vec<String>)>> {
+        let path = self.directory.join(dir);
+        if !path.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut found = vec![];
+        for entry in fs::read_dir(&path)? {
+            let entry = entry?.path();
+            let stem = entry.file_stem().map(|s| s.to_string_lossy().into_owned());
+            let Some(stem) = stem else { continue };
+
+            if entry.is_dir() {
+                found.push((
+                    stem.clone(),
+                    vec![format!("{}/{}/**/*.{}", dir, stem, self.ext)],
+                ));
+            } else if entry.extension().is_some_and(|e| e == self.ext) {
+                let source = format!("{}/{}.{}", dir, stem, self.ext);
+                found.push((stem, vec![source]));
+            }
+        }
+        found.sort();
+        Ok(found)
+    }
+
+    /// Adds discovered targets whose name and sources no configured target already has.
+    fn add_discovered(
+        &self,
+        targets: &mut Vec<Target>,
+        discovered: Vec<(String, Vec<String>)>,
+        kind: TargetKind,
+    ) -> Result<()> {
+        for (name, sources) in discovered {
+            let files = self.expand(&sources)?;
+            // An empty directory isn't a target.
+            let claimed = targets
+                .iter()
+                .any(|t| t.name == name || t.sources.iter().any(|s| files.contains(s)));
+            if files.is_empty() || claimed {
+                continue;
+            }
+            let config = TargetConfig {
+                name,
+                sources,
+                ..TargetConfig::default()
+            };
+            targets.push(self.target(&config, kind, &[])?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::language::{CStandard, Language};
+    use serial_test::serial;
+    use std::env;
+
+    #[test]
+    #[serial]
+    fn test_resolve() -> anyhow::Result<()> {
+        let path = env::current_dir()?.join("dummy");
+        for dir in ["src/net", "src/bin/server", "test", "examples", "bench"] {
+            fs::create_dir_all(path.join(dir))?;
+        }
+        for file in [
+            "src/main.c",
+            "src/lib.c",
+            "src/net/socket.c",
+            "src/bin/client.c",
+            "src/bin/server/main.c",
+            "src/bin/server/config.c",
+            "test/test_lib.c",
+            "test/test_net.c",
+            "examples/echo.c",
+            "bench/bench_net.c",
+        ] {
+            fs::write(path.join(file), "")?;
+        }
+        let project = ProjectConfig::new("dummy".to_string(), Language::C(CStandard::C11));
+        let config = TargetsConfig {
+            bins: vec![TargetConfig {
+                name: "client".to_string(),
+                defines: vec!["CLIENT=1".to_string()],
+                link: vec!["m".to_string()],
+                ..TargetConfig::default()
+            }],
+            ..TargetsConfig::default()
+        };
+
+        // Test
+        let targets = Targets::resolve(&path, &project, &config)?;
+
+        // Validate
+        let lib = targets.lib.as_ref().unwrap();
+        assert_eq!(lib.name, "dummyLib");
+        assert_eq!(
+            lib.sources,
+            vec![
+                PathBuf::from("src/lib.c"),
+                PathBuf::from("src/net/socket.c")
+            ]
+        );
+        let bins: Vec<&str> = targets.bins.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(bins, vec!["client", "dummy", "server"]);
+        let client = targets.find("client").unwrap();
+        assert_eq!(client.defines, vec!["CLIENT=1"]);
+        assert_eq!(client.link_targets, vec!["dummyLib"]);
+        assert_eq!(client.link_libs, vec!["m"]);
+        assert_eq!(targets.find("server").unwrap().sources.len(), 2);
+        assert_eq!(targets.tests[0].name, "dummyTests");
+        assert_eq!(targets.tests[0].include_dirs, vec!["src"]);
+        assert_eq!(targets.examples[0].name, "echo");
+        assert_eq!(targets.benches[0].name, "bench_net");
+        assert_eq!(targets.benches[0].link_targets, vec!["dummyLib"]);
+
+        let duplicate = TargetsConfig {
+            examples: vec![TargetConfig {
+                name: "client".to_string(),
+                sources: vec!["examples/echo.c".to_string()],
+                ..TargetConfig::default()
+            }],
+            ..config
+        };
+        assert!(Targets::resolve(&path, &project, &duplicate).is_err());
+
+        // Clean-up
+        fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_resolve_configured() -> anyhow::Result<()> {
+        let path = env::current_dir()?.join("dummy");
+        for dir in ["src", "tools", "test"] {
+            fs::create_dir_all(path.join(dir))?;
+        }
+        for file in ["src/main.c", "src/lib.c", "tools/app.c", "test/test_lib.c"] {
+            fs::write(path.join(file), "")?;
+        }
+        let project = ProjectConfig::new("dummy".to_string(), Language::C(CStandard::C11));
+        let config = TargetsConfig {
+            bins: vec![TargetConfig {
+                name: "app".to_string(),
+                sources: vec!["tools/app.c".to_string()],
+                output: Some(PathBuf::from("tools/app")),
+                ..TargetConfig::default()
+            }],
+            ..TargetsConfig::default()
+        };
+
+        // Test
+        let targets = Targets::configured(&path, &project, &config)?;
+
+        // Validate
+        let names: Vec<&str> = targets.all().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["app"]);
+        assert_eq!(targets.bins[0].output, Some(PathBuf::from("tools/app")));
+
+        // Clean-up
+        fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_resolve_renamed_main() -> anyhow::Result<()> {
+        let path = env::current_dir()?.join("dummy");
+        fs::create_dir_all(path.join("src"))?;
+        for file in ["src/main.c", "src/lib.c"] {
+            fs::write(path.join(file), "")?;
+        }
+        let project = ProjectConfig::new("dummy".to_string(), Language::C(CStandard::C11));
+        let config = TargetsConfig {
+            bins: vec![TargetConfig {
+                name: "app".to_string(),
+                sources: vec!["src/main.c".to_string()],
+                ..TargetConfig::default()
+            }],
+            ..TargetsConfig::default()
+        };
+
+        // Test
+        let targets = Targets::resolve(&path, &project, &config)?;
+
+        // Validate
+        let bins: Vec<&str> = targets.bins.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(bins, vec!["app"]);
+        assert_eq!(
+            targets.lib.as_ref().unwrap().sources,
+            vec![PathBuf::from("src/lib.c")]
+        );
+
+        // Clean-up
+        fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+}
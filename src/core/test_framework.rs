@@ -1,6 +1,9 @@
+use super::test_report::TestCase;
+use super::test_selection::TestSelection;
 use crate::{error, Error, Result};
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, process::Command};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use which::which;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -37,6 +40,11 @@ impl TestFramework {
     pub fn new(variant: TestFrameworks, dir: PathBuf) -> TestFramework {
         TestFramework { variant, dir }
     }
+
+    /// Moves the project to `dir`.
+    pub fn relocate(&mut self, dir: &Path) {
+        self.dir = dir.to_path_buf();
+    }
     pub fn as_str(&self) -> &'static str {
         return match self.variant {
             TestFrameworks::GTest => "GTest",
@@ -55,6 +63,49 @@ impl TestFramework {
         };
     }
 
+    /// Meson `dependency()` expression for the framework.
+    pub fn meson_dependency(&self) -> &'static str {
+        match self.variant {
+            TestFrameworks::GTest => GTest::meson_dependency(),
+            TestFrameworks::Boost => Boost::meson_dependency(),
+            TestFrameworks::CMocka => CMocka::meson_dependency(),
+            TestFrameworks::Unit => Unit::meson_dependency(),
+        }
+    }
+
+    /// Linker flags for the framework when building with plain Make.
+    pub fn link_libs(&self) -> &'static str {
+        match self.variant {
+            TestFrameworks::GTest => GTest::link_libs(),
+            TestFrameworks::Boost => Boost::link_libs(),
+            TestFrameworks::CMocka => CMocka::link_libs(),
+            TestFrameworks::Unit => Unit::link_libs(),
+        }
+    }
+
+    /// Arguments for running the test binary directly, writing JUnit XML to `junit` where the
+    /// framework supports it. `failed` restricts the run to previously failed cases.
+    pub fn binary_args(
+        &self,
+        selection: &TestSelection,
+        failed: &[&TestCase],
+        junit: &Path,
+    ) -> Result<Vec<String>> {
+        match self.variant {
+            TestFrameworks::GTest => GTest::binary_args(selection, failed, junit),
+            TestFrameworks::Boost => Boost::binary_args(selection, failed, junit),
+            TestFrameworks::CMocka => CMocka::binary_args(selection, failed),
+            TestFrameworks::Unit => Unit::binary_args(selection, failed),
+        }
+    }
+
+    pub fn binary_env(&self, junit: &Path) -> Vec<(String, String)> {
+        match self.variant {
+            TestFrameworks::CMocka => CMocka::binary_env(junit),
+            _ => vec![],
+        }
+    }
+
     pub fn vcpkg_setup(&self) -> Result<()> {
         match self.variant {
             TestFrameworks::GTest => GTest::vcpkg_setup(&self.dir)?,
@@ -105,6 +156,50 @@ impl GTest {
     pub fn cmake_target() -> &'static str {
         "GTest::gtest GTest::gtest_main"
     }
+
+    pub fn meson_dependency() -> &'static str {
+        "dependency('gtest', main: true)"
+    }
+
+    pub fn link_libs() -> &'static str {
+        "-lgtest -lgtest_main -pthread"
+    }
+
+    // Test Binary
+    pub fn binary_args(
+        selection: &TestSelection,
+        failed: &[&TestCase],
+        junit: &Path,
+    ) -> Result<Vec<String>> {
+        if selection.label.is_some() {
+            return Err(error!(CustomError, "GTest has no test labels"));
+        }
+
+        let mut args = vec![format!("--gtest_output=xml:{}", junit.display())];
+
+        let positive = if !failed.is_empty() {
+            failed
+                .iter()
+                .map(|c| format!("{}.{}", c.suite, c.name))
+                .collect::<Vec<_>>()
+                .join(":")
+        } else {
+            match &selection.pattern {
+                Some(p) => format!("*{}*", p),
+                None => "*".to_string(),
+            }
+        };
+
+        let filter = match &selection.exclude {
+            Some(e) => format!("{}-*{}*", positive, e),
+            None => positive,
+        };
+
+        if filter != "*" {
+            args.push(format!("--gtest_filter={}", filter));
+        }
+        Ok(args)
+    }
 }
 
 pub struct CMocka {}
@@ -135,6 +230,32 @@ impl CMocka {
     pub fn cmake_target() -> &'static str {
         "cmocka::cmocka"
     }
+
+    pub fn meson_dependency() -> &'static str {
+        "dependency('cmocka')"
+    }
+
+    pub fn link_libs() -> &'static str {
+        "-lcmocka"
+    }
+
+    // Test Binary
+    pub fn binary_args(selection: &TestSelection, failed: &[&TestCase]) -> Result<Vec<String>> {
+        if selection.is_filtered() || selection.label.is_some() || !failed.is_empty() {
+            return Err(error!(
+                CustomError,
+                "CMocka test binaries can't be filtered, run without a pattern, --exclude, --label or --rerun-failed"
+            ));
+        }
+        Ok(vec![])
+    }
+
+    pub fn binary_env(junit: &Path) -> Vec<(String, String)> {
+        vec![
+            ("CMOCKA_MESSAGE_OUTPUT".to_string(), "xml".to_string()),
+            ("CMOCKA_XML_FILE".to_string(), junit.display().to_string()),
+        ]
+    }
 }
 
 pub struct Boost {}
@@ -165,6 +286,55 @@ impl Boost {
     pub fn cmake_target() -> &'static str {
         "Boost::unit_test_framework\n"
     }
+
+    pub fn meson_dependency() -> &'static str {
+        "dependency('boost', modules: ['unit_test_framework'])"
+    }
+
+    pub fn link_libs() -> &'static str {
+        "-lboost_unit_test_framework"
+    }
+
+    // Test Binary
+    pub fn binary_args(
+        selection: &TestSelection,
+        failed: &[&TestCase],
+        junit: &Path,
+    ) -> Result<Vec<String>> {
+        let mut args = vec![format!("--logger=JUNIT,error,{}", junit.display())];
+
+        // Boost runs the union of several --run_test filters, so only one of them can be given.
+        if !failed.is_empty() {
+            for case in failed {
+                args.push(format!("--run_test={}/{}", case.suite, case.name));
+            }
+            return Ok(args);
+        }
+
+        let filters = [
+            selection
+                .pattern
+                .as_ref()
+                .map(|p| format!("--run_test=*{}*", p)),
+            selection
+                .label
+                .as_ref()
+                .map(|l| format!("--run_test=@{}", l)),
+            selection
+                .exclude
+                .as_ref()
+                .map(|e| format!("--run_test=!*{}*", e)),
+        ];
+        let filters: Vec<String> = filters.into_iter().flatten().collect();
+        if filters.len() > 1 {
+            return Err(error!(
+                CustomError,
+                "Boost test binaries can't combine a pattern, --exclude and --label, pass only one of them"
+            ));
+        }
+        args.extend(filters);
+        Ok(args)
+    }
 }
 
 pub struct Unit {}
@@ -195,6 +365,25 @@ impl Unit {
     pub fn cmake_target() -> &'static str {
         "Unit"
     }
+
+    pub fn meson_dependency() -> &'static str {
+        "dependency('unit')"
+    }
+
+    pub fn link_libs() -> &'static str {
+        "-lunit"
+    }
+
+    // Test Binary
+    pub fn binary_args(selection: &TestSelection, failed: &[&TestCase]) -> Result<Vec<String>> {
+        if selection.is_filtered() || selection.label.is_some() || !failed.is_empty() {
+            return Err(error!(
+                CustomError,
+                "Unit test binaries can't be filtered, run without a pattern, --exclude, --label or --rerun-failed"
+            ));
+        }
+        Ok(vec![])
+    }
 }
 
 #[cfg(test)]
@@ -245,6 +434,72 @@ mod tests {
         assert_eq!(v, TestFrameworks::Unit);
     }
 
+    #[test]
+    fn test_gtest_binary_args() -> anyhow::Result<()> {
+        let test_framework = TestFramework::new(TestFrameworks::GTest, PathBuf::from("dummy"));
+        let junit = PathBuf::from("junit.xml");
+        let selection = TestSelection {
+            pattern: Some("Greeting".to_string()),
+            exclude: Some("Slow".to_string()),
+            ..Default::default()
+        };
+
+        // Test
+        let args = test_framework.binary_args(&selection, &[], &junit)?;
+
+        // Validate
+        let expected = vec![
+            "--gtest_output=xml:junit.xml",
+            "--gtest_filter=*Greeting*-*Slow*",
+        ];
+        assert_eq!(args, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmocka_binary_args_filtered() {
+        let test_framework = TestFramework::new(TestFrameworks::CMocka, PathBuf::from("dummy"));
+        let junit = PathBuf::from("junit.xml");
+        let selection = TestSelection {
+            pattern: Some("add".to_string()),
+            ..Default::default()
+        };
+
+        // Test
+        let result = test_framework.binary_args(&selection, &[], &junit);
+
+        // Validate
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_boost_binary_args_combined() -> anyhow::Result<()> {
+        let test_framework = TestFramework::new(TestFrameworks::Boost, PathBuf::from("dummy"));
+        let junit = PathBuf::from("junit.xml");
+        let selection = TestSelection {
+            exclude: Some("slow".to_string()),
+            ..Default::default()
+        };
+        let combined = TestSelection {
+            pattern: Some("add".to_string()),
+            ..selection.clone()
+        };
+
+        // Test
+        let args = test_framework.binary_args(&selection, &[], &junit)?;
+        let result = test_framework.binary_args(&combined, &[], &junit);
+
+        // Validate
+        assert_eq!(
+            args,
+            vec!["--logger=JUNIT,error,junit.xml", "--run_test=!*slow*"]
+        );
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
     #[test]
     #[serial]
     // #[ignore]
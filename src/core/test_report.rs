@@ -0,0 +1,340 @@
+use crate::Result;
+use ansi_term::Colour::{Green, Red, Yellow};
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl TestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TestStatus::Passed => "ok",
+            TestStatus::Failed => "FAILED",
+            TestStatus::Skipped => "skipped",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    /// The JUnit classname when it differs from the name, e.g. the GTest suite.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub suite: String,
+    pub status: TestStatus,
+    /// Duration in seconds.
+    pub duration: f64,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub output: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TestReport {
+    pub name: String,
+    pub cases: Vec<TestCase>,
+}
+
+impl TestReport {
+    pub fn from_junit_file(path: &Path) -> Result<TestReport> {
+        let contents = fs::read_to_string(path)?;
+        TestReport::from_junit(&contents)
+    }
+
+    /// Parses a JUnit XML document, as written by `ctest --output-junit`, `meson test` or
+    /// `--gtest_output=xml`. Nested `<testsuites>` are flattened into a single list of cases.
+    pub fn from_junit(xml: &str) -> Result<TestReport> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut report = TestReport::default();
+        let mut current: Option<TestCase> = None;
+        let mut in_output = false;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => match e.name().as_ref() {
+                    b"testsuite" | b"testsuites" if report.name.is_empty() => {
+                        report.name = attribute(&e, "name")?.unwrap_or_default();
+                    }
+                    b"testcase" => current = Some(TestReport::test_case(&e)?),
+                    b"failure" | b"error" => set_status(&mut current, TestStatus::Failed),
+                    b"skipped" => set_status(&mut current, TestStatus::Skipped),
+                    b"system-out" | b"system-err" => in_output = true,
+                    _ => (),
+                },
+                Event::Empty(e) => match e.name().as_ref() {
+                    b"testcase" => report.cases.push(TestReport::test_case(&e)?),
+                    b"failure" | b"error" => set_status(&mut current, TestStatus::Failed),
+                    b"skipped" => set_status(&mut current, TestStatus::Skipped),
+                    _ => (),
+                },
+                Event::Text(t) if in_output => {
+                    if let Some(case) = current.as_mut() {
+                        case.output.push_str(&t.unescape()?);
+                    }
+                }
+                Event::CData(t) if in_output => {
+                    if let Some(case) = current.as_mut() {
+                        case.output.push_str(&String::from_utf8_lossy(&t));
+                    }
+                }
+                Event::End(e) => match e.name().as_ref() {
+                    b"testcase" => {
+                        if let Some(case) = current.take() {
+                            report.cases.push(case);
+                        }
+                    }
+                    b"system-out" | b"system-err" => in_output = false,
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn test_case(e: &BytesStart) -> Result<TestCase> {
+        let name = attribute(e, "name")?.unwrap_or_default();
+        let suite = attribute(e, "classname")?
+            .filter(|c| *c != name)
+            .unwrap_or_default();
+        let duration = attribute(e, "time")?
+            .and_then(|t| t.parse::<f64>().ok())
+            .unwrap_or(0.0);
+
+        // ctest marks disabled/not-run tests through the status attribute only.
+        let status = match attribute(e, "status")?.as_deref() {
+            Some("fail") => TestStatus::Failed,
+            Some("notrun") | Some("disabled") => TestStatus::Skipped,
+            _ => TestStatus::Passed,
+        };
+
+        Ok(TestCase {
+            name,
+            suite,
+            status,
+            duration,
+            output: String::new(),
+        })
+    }
+
+    pub fn failed(&self) -> Vec<&TestCase> {
+        self.cases
+            .iter()
+            .filter(|c| c.status == TestStatus::Failed)
+            .collect()
+    }
+
+    pub fn count(&self, status: TestStatus) -> usize {
+        self.cases.iter().filter(|c| c.status == status).count()
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.cases.iter().map(|c| c.duration).sum()
+    }
+
+    pub fn success(&self) -> bool {
+        self.count(TestStatus::Failed) == 0
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_junit(&self) -> String {
+        let mut content = vec![];
+        content.push(r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string());
+        content.push(format!(
+            r#"<testsuite name="{}" tests="{}" failures="{}" skipped="{}" time="{:.3}">"#,
+            escape(&self.name),
+            self.cases.len(),
+            self.count(TestStatus::Failed),
+            self.count(TestStatus::Skipped),
+            self.duration()
+        ));
+
+        for case in &self.cases {
+            let classname = match case.suite.is_empty() {
+                true => &case.name,
+                false => &case.suite,
+            };
+            content.push(format!(
+                r#"  <testcase name="{}" classname="{}" time="{}">"#,
+                escape(&case.name),
+                escape(classname),
+                case.duration
+            ));
+            match case.status {
+                TestStatus::Failed => content.push(r#"    <failure message="Failed"/>"#.into()),
+                TestStatus::Skipped => content.push(r#"    <skipped/>"#.into()),
+                TestStatus::Passed => (),
+            }
+            if !case.output.is_empty() {
+                content.push(format!(
+                    "    <system-out>{}</system-out>",
+                    escape(&case.output)
+                ));
+            }
+            content.push("  </testcase>".into());
+        }
+
+        content.push("</testsuite>\n".into());
+        content.join("\n")
+    }
+
+    /// Writes the report to `path`, as JSON when the extension is `.json` and JUnit XML otherwise.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => self.to_json()?,
+            _ => self.to_junit(),
+        };
+
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn print_summary(&self) {
+        println!();
+        for case in &self.cases {
+            let status = match case.status {
+                TestStatus::Passed => Green.paint(case.status.as_str()),
+                TestStatus::Failed => Red.paint(case.status.as_str()),
+                TestStatus::Skipped => Yellow.paint(case.status.as_str()),
+            };
+            println!("test {} ... {} ({:.2}s)", case.name, status, case.duration);
+        }
+
+        let result = if self.success() {
+            Green.paint("ok")
+        } else {
+            Red.paint("FAILED")
+        };
+
+        println!(
+            "\ntest result: {}. {} passed; {} failed; {} skipped; finished in {:.2}s\n",
+            result,
+            self.count(TestStatus::Passed),
+            self.count(TestStatus::Failed),
+            self.count(TestStatus::Skipped),
+            self.duration()
+        );
+    }
+}
+
+fn attribute(e: &BytesStart, name: &str) -> Result<Option<String>> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(quick_xml::Error::from)?;
+        if attr.key.as_ref() == name.as_bytes() {
+            return Ok(Some(attr.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+fn set_status(case: &mut Option<TestCase>, status: TestStatus) {
+    if let Some(c) = case.as_mut() {
+        c.status = status;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTEST_JUNIT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuite name="dummy" tests="3" failures="1" disabled="1" skipped="0" hostname="" time="0" timestamp="2025-01-01T00:00:00">
+	<testcase name="dummyTests" classname="dummyTests" time="0.0123" status="run">
+		<system-out>[==========] 1 test ran.</system-out>
+	</testcase>
+	<testcase name="otherTests" classname="otherTests" time="0.5" status="fail">
+		<failure message="Failed"/>
+		<system-out>expected 5 &amp; got 4</system-out>
+	</testcase>
+	<testcase name="disabledTests" classname="disabledTests" time="0" status="notrun">
+		<skipped message="Disabled"/>
+	</testcase>
+</testsuite>
+"#;
+
+    #[test]
+    fn test_from_junit() -> anyhow::Result<()> {
+        // Test
+        let report = TestReport::from_junit(CTEST_JUNIT)?;
+
+        // Validate
+        assert_eq!(report.name, "dummy");
+        assert_eq!(report.cases.len(), 3);
+        assert_eq!(report.count(TestStatus::Passed), 1);
+        assert_eq!(report.count(TestStatus::Failed), 1);
+        assert_eq!(report.count(TestStatus::Skipped), 1);
+        assert_eq!(report.cases[1].output, "expected 5 & got 4");
+        assert!((report.duration() - 0.5123).abs() < 1e-9);
+        assert!(!report.success());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_junit_nested_suites() -> anyhow::Result<()> {
+        let xml = r#"<testsuites name="AllTests">
+  <testsuite name="GreetingTest">
+    <testcase name="BasicTest" classname="GreetingTest" time="0.001"/>
+    <testcase name="Skipped" time="0"><skipped/></testcase>
+  </testsuite>
+</testsuites>"#;
+
+        // Test
+        let report = TestReport::from_junit(xml)?;
+
+        // Validate
+        assert_eq!(report.name, "AllTests");
+        assert_eq!(report.cases[0].suite, "GreetingTest");
+        assert_eq!(report.count(TestStatus::Passed), 1);
+        assert_eq!(report.count(TestStatus::Skipped), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_junit_round_trip() -> anyhow::Result<()> {
+        let report = TestReport::from_junit(CTEST_JUNIT)?;
+
+        // Test
+        let actual = TestReport::from_junit(&report.to_junit())?;
+
+        // Validate
+        assert_eq!(actual, report);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_round_trip() -> anyhow::Result<()> {
+        let report = TestReport::from_junit(CTEST_JUNIT)?;
+
+        // Test
+        let actual: TestReport = serde_json::from_str(&report.to_json()?)?;
+
+        // Validate
+        assert_eq!(actual, report);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use crate::Result;
+use std::path::{Path, PathBuf};
+
+/// Backend independent description of which tests to run and how.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TestSelection {
+    pub pattern: Option<String>,
+    pub exclude: Option<String>,
+    pub label: Option<String>,
+    pub jobs: Option<usize>,
+    pub repeat_until_fail: Option<u32>,
+    pub rerun_failed: bool,
+}
+
+impl TestSelection {
+    pub fn ctest_args(&self) -> Vec<String> {
+        let mut args = vec![];
+
+        if let Some(p) = &self.pattern {
+            args.extend(["-R".to_string(), regex_escape(p)]);
+        }
+        if let Some(e) = &self.exclude {
+            args.extend(["-E".to_string(), regex_escape(e)]);
+        }
+        if let Some(l) = &self.label {
+            args.extend(["-L".to_string(), regex_escape(l)]);
+        }
+        if let Some(j) = self.jobs {
+            args.extend(["-j".to_string(), j.to_string()]);
+        }
+        if let Some(n) = self.repeat_until_fail {
+            args.extend(["--repeat".to_string(), format!("until-fail:{}", n)]);
+        }
+        if self.rerun_failed {
+            args.push("--rerun-failed".to_string());
+        }
+
+        args
+    }
+
+    /// Arguments for `meson test`. Meson has no name filtering of its own so `tests` is the list
+    /// of test names already narrowed down by `filter`.
+    pub fn meson_args(&self, tests: &[String]) -> Vec<String> {
+        let mut args = vec![];
+
+        if let Some(l) = &self.label {
+            args.extend(["--suite".to_string(), l.clone()]);
+        }
+        if let Some(j) = self.jobs {
+            args.extend(["--num-processes".to_string(), j.to_string()]);
+        }
+        args.extend(tests.iter().cloned());
+
+        args
+    }
+
+    /// Whether `name` passes the pattern/exclude filters.
+    pub fn matches(&self, name: &str) -> bool {
+        let included = match &self.pattern {
+            Some(p) => name.contains(p.as_str()),
+            None => true,
+        };
+        let excluded = match &self.exclude {
+            Some(e) => name.contains(e.as_str()),
+            None => false,
+        };
+        included && !excluded
+    }
+
+    pub fn filter(&self, names: &[String]) -> Vec<String> {
+        names.iter().filter(|n| self.matches(n)).cloned().collect()
+    }
+
+    pub fn is_filtered(&self) -> bool {
+        self.pattern.is_some() || self.exclude.is_some()
+    }
+
+    /// Runs `run` until it fails, at most `repeat_until_fail` times, for backends that can't
+    /// repeat tests themselves.
+    pub fn repeat<F>(&self, mut run: F) -> Result<bool>
+    where
+        F: FnMut() -> Result<bool>,
+    {
+        let times = self.repeat_until_fail.unwrap_or(1).max(1);
+
+        for _ in 0..times {
+            if !run()? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Quotes `text` so ctest's regex filters match it literally, the same substring match the other
+/// backends do.
+fn regex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A single invocation of the test runner against one build tree.
+#[derive(Debug, Clone)]
+pub struct TestRun {
+    /// Build tree, relative to the project directory.
+    pub build_dir: PathBuf,
+    pub selection: TestSelection,
+    /// Extra flags passed through to the test runner.
+    pub flags: Vec<String>,
+    /// Extra environment for the test processes.
+    pub env: Vec<(String, String)>,
+    /// Where the JUnit XML results are written.
+    pub junit: PathBuf,
+    /// Command each test binary is run under, e.g. valgrind. Empty to run them directly.
+    pub wrapper: Vec<String>,
+}
+
+impl TestRun {
+    pub fn new(directory: &Path, build_dir: PathBuf, selection: TestSelection) -> TestRun {
+        let junit = directory
+            .join(&build_dir)
+            .join("Testing")
+            .join("forge-junit.xml");
+
+        TestRun {
+            build_dir,
+            selection,
+            flags: vec![],
+            env: vec![],
+            junit,
+            wrapper: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ctest_args() {
+        let selection = TestSelection {
+            pattern: Some("lib.add".to_string()),
+            exclude: Some("slow(1)".to_string()),
+            label: Some("unit".to_string()),
+            jobs: Some(4),
+            repeat_until_fail: Some(3),
+            rerun_failed: true,
+        };
+
+        // Test
+        let args = selection.ctest_args();
+
+        // Validate
+        let expected = vec![
+            "-R",
+            "lib\\.add",
+            "-E",
+            "slow\\(1\\)",
+            "-L",
+            "unit",
+            "-j",
+            "4",
+            "--repeat",
+            "until-fail:3",
+            "--rerun-failed",
+        ];
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn test_meson_args() {
+        let selection = TestSelection {
+            pattern: Some("lib".to_string()),
+            label: Some("unit".to_string()),
+            jobs: Some(2),
+            ..Default::default()
+        };
+        let names = vec!["libTests".to_string(), "mainTests".to_string()];
+
+        // Test
+        let args = selection.meson_args(&selection.filter(&names));
+
+        // Validate
+        let expected = vec!["--suite", "unit", "--num-processes", "2", "libTests"];
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn test_filter() {
+        let selection = TestSelection {
+            pattern: Some("Greeting".to_string()),
+            exclude: Some("Slow".to_string()),
+            ..Default::default()
+        };
+        let names = vec![
+            "GreetingTest.Basic".to_string(),
+            "GreetingTest.Slow".to_string(),
+            "MathTest.Add".to_string(),
+        ];
+
+        // Test
+        let actual = selection.filter(&names);
+
+        // Validate
+        assert_eq!(actual, vec!["GreetingTest.Basic".to_string()]);
+    }
+
+    #[test]
+    fn test_repeat_until_fail() -> anyhow::Result<()> {
+        let selection = TestSelection {
+            repeat_until_fail: Some(5),
+            ..Default::default()
+        };
+        let mut runs = 0;
+
+        // Test
+        let success = selection.repeat(|| {
+            runs += 1;
+            Ok(runs < 3)
+        })?;
+
+        // Validate
+        assert!(!success);
+        assert_eq!(runs, 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,460 @@
+use super::compile_db::{normalize, CompileDb};
+use super::targets::Targets;
+use crate::{error, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Timing reports, relative to the project directory.
+pub const TIMINGS_DIR: &str = "build/timings";
+
+/// Log written by `forge time-compile` for trees timed through it, in `.ninja_log`'s format.
+pub const WRAPPER_LOG: &str = "compile.log";
+
+/// Where compile times come from, decided before the build starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimingSource {
+    /// Entries of `.ninja_log` after `offset` bytes, the ones added by this build.
+    NinjaLog { path: PathBuf, offset: u64 },
+    /// The compiler runs through `forge time-compile`, which writes `path`.
+    Wrapper { path: PathBuf },
+}
+
+impl TimingSource {
+    /// Starts recording. Ninja keeps its log, so only its current length is remembered; the
+    /// wrapper log is emptied.
+    pub fn start(ninja_dir: Option<&Path>, directory: &Path) -> Result<TimingSource> {
+        if let Some(dir) = ninja_dir {
+            let path = dir.join(".ninja_log");
+            let offset = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            return Ok(TimingSource::NinjaLog { path, offset });
+        }
+
+        let dir = directory.join(TIMINGS_DIR);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(WRAPPER_LOG);
+        fs::write(&path, "")?;
+        Ok(TimingSource::Wrapper { path })
+    }
+
+    /// Entries recorded since `start`.
+    pub fn entries(&self) -> Result<Vec<LogEntry>> {
+        match self {
+            TimingSource::NinjaLog { path, offset } => {
+                let contents = fs::read_to_string(path).unwrap_or_default();
+                // Ninja rewrites the log when it grows too large, then everything is read.
+                let new = match contents.get(*offset as usize..) {
+                    Some(new) if contents.len() as u64 >= *offset => new,
+                    _ => &contents,
+                };
+                Ok(parse_log(new))
+            }
+            TimingSource::Wrapper { path } => Ok(parse_log(&fs::read_to_string(path)?)),
+        }
+    }
+}
+
+/// One compiler or linker run, with times in milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub start: u64,
+    pub end: u64,
+    pub output: PathBuf,
+}
+
+/// Parses `.ninja_log` lines, `start end mtime output hash` separated by tabs.
+pub fn parse_log(contents: &str) -> Vec<LogEntry> {
+    contents
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| {
+            let mut parts = l.split('\t');
+            let start = parts.next()?.parse().ok()?;
+            let end = parts.next()?.parse().ok()?;
+            let output = parts.nth(1)?;
+            Some(LogEntry {
+                start,
+                end,
+                output: PathBuf::from(output),
+            })
+        })
+        .collect()
+}
+
+/// Runs `command`, a compiler invocation, and appends its start and end to `log`. Used as the
+/// compiler launcher of Make and CMake builds with `--timings`. With `trace` compiles also get
+/// clang's `-ftime-trace`. Returns the compiler's exit code.
+pub fn time_command(log: &Path, command: &[String], trace: bool) -> Result<i32> {
+    let Some((program, args)) = command.split_first() else {
+        return Err(error!(CustomError, "No command to time"));
+    };
+
+    let mut args = args.to_vec();
+    if trace && args.iter().any(|a| a == "-c") {
+        args.push("-ftime-trace".to_string());
+    }
+
+    let start = now_ms();
+    let status = Command::new(program).args(&args).status()?;
+    let end = now_ms();
+
+    if let Some(output) = args
+        .iter()
+        .position(|a| a == "-o")
+        .and_then(|i| args.get(i + 1))
+    {
+        let output = std::env::current_dir()?.join(output);
+        let line = format!("{}\t{}\t0\t{}\t0\n", start, end, output.display());
+        // A single short append, so parallel jobs don't interleave.
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log)?
+            .write_all(line.as_bytes())?;
+    }
+
+    Ok(status.code().unwrap_or(1))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnitTiming {
+    /// The translation unit, relative to the project directory when it's part of it.
+    pub file: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Total {
+    pub name: String,
+    pub ms: u64,
+}
+
+/// Compile times of one build, slowest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimingReport {
+    pub wall_ms: u64,
+    pub units: Vec<UnitTiming>,
+    pub targets: Vec<Total>,
+    /// Time spent parsing each header, summed over translation units. Only available when the
+    /// compiler wrote `-ftime-trace` files.
+    pub headers: Vec<Total>,
+}
+
+impl TimingReport {
+    /// Builds the report from the log entries of the object files. `build_dir` is absolute,
+    /// relative outputs are resolved against it.
+    pub fn new(
+        directory: &Path,
+        build_dir: &Path,
+        entries: &[LogEntry],
+        targets: &Targets,
+        wall_ms: u64,
+    ) -> TimingReport {
+        let db = CompileDb::from_file(&build_dir.join("compile_commands.json")).ok();
+        let mut units = vec![];
+        let mut headers: HashMap<String, u64> = HashMap::new();
+
+        for entry in entries.iter().filter(|e| is_object(&e.output)) {
+            let output = normalize(&build_dir.join(&entry.output));
+            let file = source_of(&output, directory, build_dir, db.as_ref());
+            let target = targets
+                .all()
+                .into_iter()
+                .find(|t| t.sources.contains(&file))
+                .map(|t| t.name.clone());
+            units.push(UnitTiming {
+                file,
+                target,
+                ms: entry.end.saturating_sub(entry.start),
+            });
+
+            if let Ok(trace) = fs::read_to_string(output.with_extension("json")) {
+                for (header, us) in parse_time_trace(&trace) {
+                    *headers.entry(header).or_default() += us / 1000;
+                }
+            }
+        }
+        units.sort_by(|a, b| b.ms.cmp(&a.ms).then(a.file.cmp(&b.file)));
+
+        let mut by_target: HashMap<String, u64> = HashMap::new();
+        for unit in &units {
+            let name = unit.target.clone().unwrap_or_else(|| "-".to_string());
+            *by_target.entry(name).or_default() += unit.ms;
+        }
+
+        TimingReport {
+            wall_ms,
+            units,
+            targets: sorted(by_target),
+            headers: sorted(headers),
+        }
+    }
+
+    /// Writes `timings.json` and `index.html` to `dir`. Returns the HTML file.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        fs::write(
+            dir.join("timings.json"),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        let html = dir.join("index.html");
+        fs::write(&html, self.to_html())?;
+        Ok(html)
+    }
+
+    fn to_html(&self) -> String {
+        let table = |title: &str, rows: Vec<(String, String, u64)>| {
+            let mut html = format!("<h2>{}</h2>\n<table>\n", title);
+            for (name, detail, ms) in rows {
+                html += &format!(
+                    "<tr><td class=\"ms\">{} ms</td><td>{}</td><td>{}</td></tr>\n",
+                    ms,
+                    escape(&name),
+                    escape(&detail)
+                );
+            }
+            html + "</table>\n"
+        };
+
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Build timings</title>\n\
+             <style>body { font-family: sans-serif; } td { padding: 2px 12px; } .ms { text-align: right; }</style>\n\
+             </head>\n<body>\n",
+        );
+        html += &format!(
+            "<h1>Build timings</h1>\n<p>Wall time: {} ms</p>\n",
+            self.wall_ms
+        );
+        html += &table(
+            "Targets",
+            self.targets
+                .iter()
+                .map(|t| (t.name.clone(), String::new(), t.ms))
+                .collect(),
+        );
+        html += &table(
+            "Translation units",
+            self.units
+                .iter()
+                .map(|u| {
+                    let target = u.target.clone().unwrap_or_default();
+                    (u.file.display().to_string(), target, u.ms)
+                })
+                .collect(),
+        );
+        if !self.headers.is_empty() {
+            html += &table(
+                "Headers",
+                self.headers
+                    .iter()
+                    .map(|h| (h.name.clone(), String::new(), h.ms))
+                    .collect(),
+            );
+        }
+        html + "</body>\n</html>\n"
+    }
+
+    /// Prints the `top` slowest translation units, targets and headers.
+    pub fn print(&self, top: usize) {
+        if self.units.is_empty() {
+            println!("\nNothing was compiled, the tree was up to date. Run `forge clean` first to time a full build.\n");
+            return;
+        }
+
+        println!("\nSlowest translation units:");
+        for unit in self.units.iter().take(top) {
+            let target = unit
+                .target
+                .as_deref()
+                .map(|t| format!("  ({})", t))
+                .unwrap_or_default();
+            println!("  {:>7} ms  {}{}", unit.ms, unit.file.display(), target);
+        }
+
+        println!("\nTargets:");
+        for target in self.targets.iter().take(top) {
+            println!("  {:>7} ms  {}", target.ms, target.name);
+        }
+
+        if !self.headers.is_empty() {
+            println!("\nSlowest headers:");
+            for header in self.headers.iter().take(top) {
+                println!("  {:>7} ms  {}", header.ms, header.name);
+            }
+        }
+        println!(
+            "\nbuild: {} translation unit(s) in {} ms\n",
+            self.units.len(),
+            self.wall_ms
+        );
+    }
+}
+
+fn is_object(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "o" || e == "obj")
+}
+
+/// The translation unit compiled to `output`, from the compilation database or else the
+/// Makefile's `obj/<source>.o` layout. Falls back to the object itself.
+fn source_of(output: &Path, directory: &Path, build_dir: &Path, db: Option<&CompileDb>) -> PathBuf {
+    let from_db = db.and_then(|db| {
+        db.commands.iter().find(|c| {
+            c.output
+                .as_ref()
+                .is_some_and(|o| normalize(&c.directory.join(o)) == output)
+        })
+    });
+    let source = match from_db {
+        Some(command) => command.path(),
+        None => match output.strip_prefix(build_dir.join("obj")) {
+            Ok(object) => directory.join(object.with_extension("")),
+            Err(_) => output.to_path_buf(),
+        },
+    };
+
+    source
+        .strip_prefix(directory)
+        .map(Path::to_path_buf)
+        .unwrap_or(source)
+}
+
+/// Header parse times in microseconds from a clang `-ftime-trace` file, the `Source` events.
+fn parse_time_trace(json: &str) -> Vec<(String, u64)> {
+    let Ok(trace) = serde_json::from_str::<serde_json::Value>(json) else {
+        return vec![];
+    };
+    trace["traceEvents"]
+        .as_array()
+        .map(|events| {
+            events
+                .iter()
+                .filter(|e| e["name"] == "Source")
+                .filter_map(|e| {
+                    Some((
+                        e["args"]["detail"].as_str()?.to_string(),
+                        e["dur"].as_u64()?,
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn sorted(totals: HashMap<String, u64>) -> Vec<Total> {
+    let mut totals: Vec<Total> = totals
+        .into_iter()
+        .map(|(name, ms)| Total { name, ms })
+        .collect();
+    totals.sort_by(|a, b| b.ms.cmp(&a.ms).then(a.name.cmp(&b.name)));
+    totals
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        config::ProjectConfig,
+        language::{CStandard, Language},
+        targets::TargetsConfig,
+    };
+    use serial_test::serial;
+    use std::env;
+
+    #[test]
+    fn test_parse_log() {
+        let log = "# ninja log v5\n\
+                   0\t120\t0\tlibdummyLib.a.p/src_lib.c.o\t8f1c\n\
+                   5\t95\t0\tdummy.p/src_main.c.o\t2b3a\n\
+                   120\t140\t0\tlibdummyLib.a\t77aa\n";
+        let trace = r#"{"traceEvents": [
+            {"ph": "X", "name": "Source", "dur": 4000, "args": {"detail": "/usr/include/stdio.h"}},
+            {"ph": "X", "name": "Frontend", "dur": 9000}
+        ]}"#;
+
+        // Test
+        let entries = parse_log(log);
+
+        // Validate
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0],
+            LogEntry {
+                start: 0,
+                end: 120,
+                output: PathBuf::from("libdummyLib.a.p/src_lib.c.o"),
+            }
+        );
+        assert!(!is_object(&entries[2].output));
+        assert_eq!(
+            parse_time_trace(trace),
+            vec![("/usr/include/stdio.h".to_string(), 4000)]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_wrapper_report() -> anyhow::Result<()> {
+        let cwd = env::current_dir()?;
+        let path = cwd.join("dummy");
+        fs::create_dir_all(path.join("src"))?;
+        fs::create_dir_all(path.join("build").join("obj").join("src"))?;
+        fs::write(
+            path.join("src").join("lib.c"),
+            "int add(int a, int b) { return a + b; }\n",
+        )?;
+        let project = ProjectConfig::new("dummy".to_string(), Language::C(CStandard::C11));
+        let targets = Targets::resolve(&path, &project, &TargetsConfig::default())?;
+
+        // Test
+        let source = TimingSource::start(None, &path)?;
+        env::set_current_dir(&path)?;
+        let command: Vec<String> = "gcc -c src/lib.c -o build/obj/src/lib.c.o"
+            .split(' ')
+            .map(String::from)
+            .collect();
+        let code = time_command(&source_path(&source), &command, false);
+        env::set_current_dir(&cwd)?;
+        let report =
+            TimingReport::new(&path, &path.join("build"), &source.entries()?, &targets, 10);
+        let html = report.write(&path.join(TIMINGS_DIR))?;
+
+        // Validate
+        assert_eq!(code?, 0);
+        assert_eq!(report.units.len(), 1);
+        assert_eq!(report.units[0].file, PathBuf::from("src/lib.c"));
+        assert_eq!(report.units[0].target.as_deref(), Some("dummyLib"));
+        assert_eq!(report.targets[0].name, "dummyLib");
+        assert!(fs::read_to_string(html)?.contains("<td>src/lib.c</td>"));
+        assert!(path.join(TIMINGS_DIR).join("timings.json").exists());
+
+        // Clean-up
+        fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+
+    fn source_path(source: &TimingSource) -> PathBuf {
+        match source {
+            TimingSource::NinjaLog { path, .. } | TimingSource::Wrapper { path } => path.clone(),
+        }
+    }
+}
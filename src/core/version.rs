@@ -0,0 +1,181 @@
+use super::config::ProjectConfig;
+use super::regen;
+use crate::{error, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use strum_macros::{Display, EnumString};
+
+/// Generated header exposing the project version, relative to the project directory.
+pub const VERSION_HEADER: &str = "include/version.h";
+
+/// `major.minor.patch` project version, stored as a string in Forge.toml.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum VersionPart {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl VersionPart {
+    pub fn variants() -> Vec<&'static str> {
+        vec!["major", "minor", "patch"]
+    }
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Version> {
+        let parts: Vec<&str> = s.trim().split('.').collect();
+        let numbers: Vec<u64> = parts.iter().filter_map(|p| p.parse().ok()).collect();
+
+        match numbers[..] {
+            [major, minor, patch] if parts.len() == 3 => Ok(Version::new(major, minor, patch)),
+            _ => Err(error!(
+                CustomError,
+                "Invalid version `{}`, expected major.minor.patch", s
+            )),
+        }
+    }
+
+    /// Next version, resetting the lower parts.
+    pub fn bump(&self, part: VersionPart) -> Version {
+        match part {
+            VersionPart::Major => Version::new(self.major + 1, 0, 0),
+            VersionPart::Minor => Version::new(self.major, self.minor + 1, 0),
+            VersionPart::Patch => Version::new(self.major, self.minor, self.patch + 1),
+        }
+    }
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::new(0, 1, 0)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl TryFrom<String> for Version {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Version> {
+        Version::parse(&s)
+    }
+}
+
+impl From<Version> for String {
+    fn from(v: Version) -> String {
+        v.to_string()
+    }
+}
+
+/// Contents of `version.h`. Macros are prefixed with the project name so the header can be
+/// installed next to other projects' headers.
+pub fn version_header(project: &ProjectConfig) -> String {
+    let prefix: String = project
+        .name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect();
+    let version = &project.version;
+
+    let mut content = vec![];
+    content.push("/**".to_string());
+    content.push(" * @file version.h".to_string());
+    content.push(format!(" * @brief Version of {}.", project.name));
+    content.push(" *".to_string());
+    content.push(" * Generated by forge from Forge.toml, do not edit.".to_string());
+    content.push(" */".to_string());
+    content.push("#pragma once\n".to_string());
+    content.push("/** Major version, bumped for incompatible changes. */".to_string());
+    content.push(format!(
+        "#define {}_VERSION_MAJOR {}",
+        prefix, version.major
+    ));
+    content.push("/** Minor version, bumped for new features. */".to_string());
+    content.push(format!(
+        "#define {}_VERSION_MINOR {}",
+        prefix, version.minor
+    ));
+    content.push("/** Patch version, bumped for fixes. */".to_string());
+    content.push(format!(
+        "#define {}_VERSION_PATCH {}",
+        prefix, version.patch
+    ));
+    content.push("/** Full version as a string, `major.minor.patch`. */".to_string());
+    content.push(format!("#define {}_VERSION \"{}\"", prefix, version));
+    content.push(String::new());
+    content.join("\n")
+}
+
+pub fn write_header(directory: &Path, project: &ProjectConfig) -> Result<()> {
+    let path = directory.join(VERSION_HEADER);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    regen::write_if_changed(&path, &version_header(project))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::language::{CStandard, Language};
+
+    #[test]
+    fn test_parse_and_bump() -> anyhow::Result<()> {
+        // Test
+        let version = Version::parse("1.4.2")?;
+
+        // Validate
+        assert_eq!(version.bump(VersionPart::Major), Version::new(2, 0, 0));
+        assert_eq!(version.bump(VersionPart::Minor), Version::new(1, 5, 0));
+        assert_eq!(version.bump(VersionPart::Patch).to_string(), "1.4.3");
+        assert!(Version::parse("1.4").is_err());
+        assert!(Version::parse("1.4.x").is_err());
+        assert_eq!(
+            "minor".parse::<VersionPart>().ok(),
+            Some(VersionPart::Minor)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_version_header() {
+        let mut project = ProjectConfig::new("my-app".to_string(), Language::C(CStandard::C11));
+        project.version = Version::new(3, 0, 7);
+
+        // Test
+        let header = version_header(&project);
+
+        // Validate
+        assert!(header.contains("#define MY_APP_VERSION_MAJOR 3\n"));
+        assert!(header.contains("#define MY_APP_VERSION_PATCH 7\n"));
+        assert!(header.contains("#define MY_APP_VERSION \"3.0.7\"\n"));
+    }
+}
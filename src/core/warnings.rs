@@ -0,0 +1,152 @@
+use super::compiler::CompilerFamily;
+use super::language::Language;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WarningLevel {
+    None,
+    #[default]
+    Default,
+    Strict,
+    Pedantic,
+}
+
+/// `[build.warnings]` section of Forge.toml. Applied to the project's own targets only.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WarningsConfig {
+    #[serde(default)]
+    pub level: WarningLevel,
+    #[serde(default)]
+    pub warnings_as_errors: bool,
+}
+
+impl WarningsConfig {
+    /// Flags for gcc and clang.
+    pub fn flags(&self, family: CompilerFamily) -> Vec<&'static str> {
+        let mut flags = match self.level {
+            WarningLevel::None => vec!["-w"],
+            WarningLevel::Default => vec!["-Wall"],
+            WarningLevel::Strict => vec!["-Wall", "-Wextra", "-Wshadow"],
+            WarningLevel::Pedantic => vec![
+                "-Wall",
+                "-Wextra",
+                "-Wshadow",
+                "-Wpedantic",
+                "-Wconversion",
+                "-Wsign-conversion",
+            ],
+        };
+
+        if matches!(self.level, WarningLevel::Strict | WarningLevel::Pedantic) {
+            match family {
+                CompilerFamily::Gcc => flags.extend(["-Wduplicated-cond", "-Wlogical-op"]),
+                CompilerFamily::Clang => flags.extend(["-Wimplicit-fallthrough"]),
+            }
+        }
+
+        if self.warnings_as_errors && self.level != WarningLevel::None {
+            flags.push("-Werror");
+        }
+        flags
+    }
+
+    pub fn msvc_flags(&self) -> Vec<&'static str> {
+        let mut flags = match self.level {
+            WarningLevel::None => vec!["/W0"],
+            WarningLevel::Default => vec!["/W3"],
+            WarningLevel::Strict => vec!["/W4"],
+            WarningLevel::Pedantic => vec!["/W4", "/permissive-"],
+        };
+
+        if self.warnings_as_errors && self.level != WarningLevel::None {
+            flags.push("/WX");
+        }
+        flags
+    }
+
+    /// CMake snippet defining `FORGE_WARNINGS` with generator expressions per compiler, to be
+    /// added to targets with `target_compile_options(<target> PRIVATE ${FORGE_WARNINGS})`.
+    pub fn cmake_variable(&self, language: &Language) -> String {
+        let id = format!("{}_COMPILER_ID", language.cmake_identifier());
+        let gcc = self.flags(CompilerFamily::Gcc).join(";");
+        let clang = self.flags(CompilerFamily::Clang).join(";");
+        let msvc = self.msvc_flags().join(";");
+
+        let mut content = vec!["set(FORGE_WARNINGS".to_string()];
+        content.push(format!("    \"$<$<{}:GNU>:{}>\"", id, gcc));
+        content.push(format!(
+            "    \"$<$<OR:$<{}:Clang>,$<{}:AppleClang>>:{}>\"",
+            id, id, clang
+        ));
+        content.push(format!("    \"$<$<{}:MSVC>:{}>\"", id, msvc));
+        content.push(")\n".to_string());
+        content.join("\n")
+    }
+
+    /// Meson `override_options` for a target. Meson maps the level to each compiler itself.
+    pub fn meson_options(&self) -> Vec<String> {
+        let level = match self.level {
+            WarningLevel::None => 0,
+            WarningLevel::Default => 1,
+            WarningLevel::Strict => 2,
+            WarningLevel::Pedantic => 3,
+        };
+        vec![
+            format!("warning_level={}", level),
+            format!("werror={}", self.warnings_as_errors),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::language::CppStandard;
+
+    #[test]
+    fn test_flags() {
+        let config = WarningsConfig {
+            level: WarningLevel::Pedantic,
+            warnings_as_errors: true,
+        };
+
+        // Test
+        let gcc = config.flags(CompilerFamily::Gcc);
+        let clang = config.flags(CompilerFamily::Clang);
+
+        // Validate
+        assert!(gcc.contains(&"-Wpedantic") && gcc.contains(&"-Wlogical-op"));
+        assert!(!clang.contains(&"-Wlogical-op"));
+        assert_eq!(gcc.last(), Some(&"-Werror"));
+        assert_eq!(config.msvc_flags(), vec!["/W4", "/permissive-", "/WX"]);
+        assert_eq!(
+            config.meson_options(),
+            vec!["warning_level=3", "werror=true"]
+        );
+        assert_eq!(
+            WarningsConfig::default().flags(CompilerFamily::Clang),
+            vec!["-Wall"]
+        );
+    }
+
+    #[test]
+    fn test_cmake_variable() {
+        let config = WarningsConfig {
+            level: WarningLevel::Strict,
+            warnings_as_errors: false,
+        };
+
+        // Test
+        let actual = config.cmake_variable(&Language::Cpp(CppStandard::Cpp17));
+
+        // Validate
+        let expected = "set(FORGE_WARNINGS
+    \"$<$<CXX_COMPILER_ID:GNU>:-Wall;-Wextra;-Wshadow;-Wduplicated-cond;-Wlogical-op>\"
+    \"$<$<OR:$<CXX_COMPILER_ID:Clang>,$<CXX_COMPILER_ID:AppleClang>>:-Wall;-Wextra;-Wshadow;-Wimplicit-fallthrough>\"
+    \"$<$<CXX_COMPILER_ID:MSVC>:/W4>\"
+)
+";
+        assert_eq!(actual, expected);
+    }
+}
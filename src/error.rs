@@ -19,6 +19,8 @@ pub enum Error {
     TomlDeError(#[from] toml::de::Error),
     #[error("Fmt Error: {0}")]
     FmtError(#[from] std::fmt::Error),
+    #[error("Xml Error: {0}")]
+    XmlError(#[from] quick_xml::Error),
 }
 
 #[macro_export]